@@ -22,6 +22,7 @@ impl Archetype {
         }
     }
 
+    #[allow(unused)]
     fn remove(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         match self {
             Archetype::Struct(struct_archetype) => struct_archetype.remove(sqlx, database),
@@ -31,6 +32,7 @@ impl Archetype {
         }
     }
 
+    #[allow(unused)]
     fn component_deserializer(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         match self {
             Archetype::Struct(struct_archetype) => {
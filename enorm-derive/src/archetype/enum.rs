@@ -17,6 +17,8 @@ impl EnumArchetype {
 
         let deserializer = self.component_deserializer(sqlx, database);
 
+        let tables = self.tables(sqlx, database);
+
         quote! {
             impl ::enorm::archetype::Archetype<#database> for #archetype_name
             {
@@ -25,6 +27,32 @@ impl EnumArchetype {
             impl ::enorm::serialization::Deserializeable<#database> for #archetype_name {
                 #deserializer
             }
+
+            impl ::enorm::tables::Tables<#database> for #archetype_name {
+                #tables
+            }
+        }
+    }
+
+    pub fn tables(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let all_fields: HashSet<_> = self
+            .variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .collect();
+
+        let sub_archetypes = all_fields.iter().map(|field| {
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::enorm::tables::Tables<#database>>::tables(schema);
+            }
+        });
+
+        quote! {
+            fn tables<EntityId: #sqlx::Type<#database>>(schema: &mut ::enorm::schema::Schema<#database, EntityId>) {
+                #(#sub_archetypes)*
+            }
         }
     }
 
@@ -15,11 +15,22 @@ impl StructArchetype {
 
         let remove = self.remove(sqlx, database);
 
+        let tables = self.tables(sqlx, database);
+
+        let serializer = self.component_serializer(sqlx, database);
+
         let deserializer = self.component_deserializer(sqlx, database);
 
+        let component_tables = self.component_tables(database);
+
         quote! {
             impl ::enorm::archetype::Archetype<#database> for #archetype_name
             {
+                #component_tables
+            }
+
+            impl ::enorm::serialization::Serializable<#database> for #archetype_name {
+                #serializer
             }
 
             impl ::enorm::serialization::Deserializeable<#database> for #archetype_name {
@@ -29,6 +40,46 @@ impl StructArchetype {
             impl ::enorm::tables::Removable<#database> for #archetype_name {
                 #remove
             }
+
+            impl ::enorm::tables::Tables<#database> for #archetype_name {
+                #tables
+            }
+        }
+    }
+
+    pub fn tables(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let sub_archetypes = self.fields.iter().map(|field| {
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::enorm::tables::Tables<#database>>::tables(schema);
+            }
+        });
+
+        quote! {
+            fn tables<EntityId: #sqlx::Type<#database>>(schema: &mut ::enorm::schema::Schema<#database, EntityId>) {
+                #(#sub_archetypes)*
+            }
+        }
+    }
+
+    /// Only the optional components which are present are written.
+    pub fn component_tables(&self, database: &TokenStream) -> TokenStream {
+        let sub_archetypes = self.fields.iter().map(|field| {
+            let ident = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                tables.extend(<#typename as ::enorm::archetype::Archetype<#database>>::component_tables(&self.#ident));
+            }
+        });
+
+        quote! {
+            fn component_tables(&self) -> Vec<::enorm::archetype::ComponentTable> {
+                let mut tables = Vec::new();
+                #(#sub_archetypes)*
+                tables
+            }
         }
     }
 
@@ -51,6 +102,102 @@ impl StructArchetype {
         }
     }
 
+    pub fn component_serializer(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let serializers = self.fields.iter().map(|field| {
+            let ident = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                let query = <#typename as ::enorm::serialization::Serializable<#database>>::serialize(&self.#ident, query);
+            }
+        });
+
+        let inserts = self.fields.iter().map(|field| {
+            let ident = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::enorm::serialization::Serializable<#database>>::insert(&self.#ident, query);
+            }
+        });
+
+        let updates = self.fields.iter().map(|field| {
+            let ident = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::enorm::serialization::Serializable<#database>>::update(&self.#ident, query);
+            }
+        });
+
+        let upserts = self.fields.iter().map(|field| {
+            let ident = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                <#typename as ::enorm::serialization::Serializable<#database>>::upsert(&self.#ident, query);
+            }
+        });
+
+        let bulk_inserts = self.fields.iter().map(|field| {
+            let ident = field.ident();
+            let typename = field.typename();
+
+            quote! {
+                {
+                    let components: Vec<_> = rows
+                        .iter()
+                        .map(|(entity, archetype)| (*entity, &archetype.#ident))
+                        .collect();
+
+                    <#typename as ::enorm::serialization::Serializable<#database>>::insert_many(&components, query);
+                }
+            }
+        });
+
+        quote! {
+            fn serialize<'q>(
+                &'q self,
+                query: #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>>,
+            ) -> #sqlx::query::Query<'q, #database, <#database as #sqlx::Database>::Arguments<'q>> {
+                #(#serializers)*
+
+                query
+            }
+
+            fn insert<'query, EntityId>(&'query self, query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #(#inserts)*
+            }
+
+            fn update<'query, EntityId>(&'query self, query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #(#updates)*
+            }
+
+            fn upsert<'query, EntityId>(&'query self, query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                #(#upserts)*
+            }
+
+            fn insert_many<'query, EntityId>(
+                rows: &[(&'query EntityId, &'query Self)],
+                query: &mut ::enorm::bulk::BulkInsert<'query, #database>,
+            )
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + 'query
+            {
+                #(#bulk_inserts)*
+            }
+        }
+    }
+
     pub fn component_deserializer(
         &self,
         sqlx: &TokenStream,
@@ -1,13 +1,17 @@
 use proc_macro2::{Ident, Literal, Punct, TokenStream};
 use quote::{quote, TokenStreamExt as _};
-use r#enum::EnumComponent;
-use syn::{parse::Parse, DeriveInput, Token};
+pub use r#enum::EnumComponent;
+use syn::{parse::Parse, punctuated::Punctuated, DeriveInput, Token, Type};
 
 mod r#enum;
 mod r#struct;
 pub use r#struct::*;
 
-use crate::{implement_for, reflect::reflect_component};
+use crate::{
+    field::{Field, IndexKind},
+    implement_for,
+    reflect::{reflect_component, reflect_enum_component},
+};
 
 #[derive(Debug)]
 pub enum Component {
@@ -17,7 +21,7 @@ pub enum Component {
 
 impl Component {
     pub fn derive(&self) -> TokenStream {
-        let implementation = |database: Ident, placeholder_char: char| {
+        let implementation = |database: Ident, placeholder: Placeholder| {
             #[cfg(feature = "bundled")]
             let sqlx = quote! {::enorm::sqlx};
             #[cfg(not(feature = "bundled"))]
@@ -27,10 +31,10 @@ impl Component {
 
             match self {
                 Component::Struct(struct_component) => {
-                    struct_component.implementation(&sqlx, &database, placeholder_char)
+                    struct_component.implementation(&sqlx, &database, placeholder)
                 }
                 Component::Enum(enum_component) => {
-                    enum_component.implementation(&sqlx, &database, placeholder_char)
+                    enum_component.implementation(&sqlx, &database, placeholder)
                 }
             }
         };
@@ -39,11 +43,13 @@ impl Component {
 
         match self {
             Component::Struct(struct_component) => {
-                implementations.append_all(reflect_component(&struct_component));
+                implementations.append_all(reflect_component(struct_component));
+            }
+            Component::Enum(enum_component) => {
+                implementations.append_all(reflect_enum_component(enum_component));
             }
-            Component::Enum(_) => (),
         }
-        implementations.into()
+        implementations
     }
 }
 
@@ -84,17 +90,48 @@ impl Parse for ComponentAttributeList {
 pub enum ComponentAttribute {
     /// Changes the name of the Component's sql table.
     Table { name: Literal },
+    /// Creates an index spanning several fields, such as `index(a, b)`
+    /// or `unique(a, b)`.
+    Index { kind: IndexKind, fields: Vec<Ident> },
+    /// Adds a version column to the Component's table, which guards
+    /// updates of `Versioned` components against concurrent changes.
+    Versioned,
+    /// Adds a `deleted_at` column to the Component's table, and tombstones
+    /// the Component when it is removed, instead of deleting it.
+    SoftDelete,
+    /// Records every change of the Component in a separate history table.
+    History,
 }
 
 impl ComponentAttribute {
     pub fn table(&self) -> Option<String> {
-        #[allow(irrefutable_let_patterns)]
         if let ComponentAttribute::Table { name } = self {
-            Some(name.to_string())
+            // The literal includes its quotes, which aren't part of the name.
+            Some(name.to_string().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    }
+
+    pub fn index(&self) -> Option<(IndexKind, &[Ident])> {
+        if let ComponentAttribute::Index { kind, fields } = self {
+            Some((*kind, fields))
         } else {
             None
         }
     }
+
+    pub fn versioned(&self) -> bool {
+        matches!(self, ComponentAttribute::Versioned)
+    }
+
+    pub fn soft_delete(&self) -> bool {
+        matches!(self, ComponentAttribute::SoftDelete)
+    }
+
+    pub fn history(&self) -> bool {
+        matches!(self, ComponentAttribute::History)
+    }
 }
 
 impl Parse for ComponentAttribute {
@@ -109,6 +146,24 @@ impl Parse for ComponentAttribute {
                     name: input.parse()?,
                 }
             }
+            "index" | "unique" => {
+                let content;
+                syn::parenthesized!(content in input);
+
+                let fields = Punctuated::<Ident, Token![,]>::parse_separated_nonempty(&content)?;
+
+                ComponentAttribute::Index {
+                    kind: if ident == "unique" {
+                        IndexKind::Unique
+                    } else {
+                        IndexKind::Index
+                    },
+                    fields: fields.into_iter().collect(),
+                }
+            }
+            "versioned" => ComponentAttribute::Versioned,
+            "soft_delete" => ComponentAttribute::SoftDelete,
+            "history" => ComponentAttribute::History,
             _ => {
                 return Err(syn::Error::new(
                     ident.span(),
@@ -119,12 +174,667 @@ impl Parse for ComponentAttribute {
     }
 }
 
+/// Collects the indexes of a component: those declared on individual fields,
+/// followed by the composite ones declared on the component itself.
+///
+/// Composite indexes refer to fields by name, which are resolved to their
+/// columns here so that misspelled fields are reported at compile time.
+pub fn component_indexes<'a>(
+    attributes: &[ComponentAttribute],
+    fields: impl IntoIterator<Item = &'a Field> + Clone,
+) -> syn::Result<Vec<(IndexKind, Vec<String>)>> {
+    let mut indexes: Vec<(IndexKind, Vec<String>)> = Vec::new();
+
+    let single = fields
+        .clone()
+        .into_iter()
+        .filter_map(|field| Some((field.index()?, vec![field.column_name().to_string()])));
+
+    for (kind, idents) in attributes.iter().filter_map(ComponentAttribute::index) {
+        let columns = Result::<Vec<_>, syn::Error>::from_iter(idents.iter().map(|ident| {
+            fields
+                .clone()
+                .into_iter()
+                .find(|field| *ident == field.field_name().to_string())
+                .map(|field| field.column_name().to_string())
+                .ok_or_else(|| syn::Error::new(ident.span(), "no such field on Component"))
+        }))?;
+
+        indexes.push((kind, columns));
+    }
+
+    // Enum variants may share fields, which only need indexing once.
+    for index in single.chain(std::mem::take(&mut indexes)) {
+        if !indexes.contains(&index) {
+            indexes.push(index);
+        }
+    }
+
+    Ok(indexes)
+}
+
+/// Generates the `Component::indexes` implementation.
+pub fn indexes_fn(indexes: &[(IndexKind, Vec<String>)]) -> TokenStream {
+    let definitions = indexes.iter().map(|(kind, columns)| {
+        let unique = *kind == IndexKind::Unique;
+
+        quote! {
+            ::enorm::component::IndexDefinition {
+                columns: &[#(#columns),*],
+                unique: #unique,
+            }
+        }
+    });
+
+    quote! {
+        fn indexes() -> Vec<::enorm::component::IndexDefinition> {
+            vec![#(#definitions,)*]
+        }
+    }
+}
+
+/// Generates the `Tables::tables` implementation, which includes the tables
+/// of referenced components ahead of the component's own, since foreign keys
+/// can only refer to existing tables.
+pub fn tables_fn<'a>(
+    component_name: &Ident,
+    fields: impl IntoIterator<Item = &'a Field>,
+    sqlx: &TokenStream,
+    database: &TokenStream,
+) -> TokenStream {
+    let mut referenced: Vec<&Type> = Vec::new();
+
+    for field in fields {
+        if let Some(reference) = field.references() {
+            let component = &reference.component;
+            let own = quote!(#component).to_string();
+
+            // Self-references don't need to be created ahead of time.
+            if own != "Self" && *component_name != own && !referenced.contains(&component) {
+                referenced.push(component);
+            }
+        }
+    }
+
+    quote! {
+        fn tables<EntityId: #sqlx::Type<#database>>(schema: &mut ::enorm::schema::Schema<#database, EntityId>) {
+            #(<#referenced as ::enorm::tables::Tables<#database>>::tables(schema);)*
+            schema.add::<Self>();
+        }
+    }
+}
+
+/// How a database refers to bound parameters within a statement.
+#[allow(unused)]
+#[derive(Debug, Clone, Copy)]
+pub enum Placeholder {
+    /// Numbered parameters such as `?1` or `$1`, which can appear in any order.
+    Numbered(char),
+    /// Anonymous `?` parameters, which are bound in order of appearance.
+    Positional,
+}
+
+/// How an update statement treats the version column of a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Versioning {
+    /// The component has no version column.
+    None,
+    /// The version is incremented unconditionally.
+    Increment,
+    /// The version is incremented, but only if it matches the one bound
+    /// after the columns.
+    Guard,
+}
+
+impl Versioning {
+    pub fn of(versioned: bool) -> Self {
+        if versioned {
+            Versioning::Increment
+        } else {
+            Versioning::None
+        }
+    }
+}
+
+/// Name of the hidden column holding the version of a component.
+pub const VERSION_COLUMN: &str = "__enorm_version";
+
+/// Generates the definition of the version column within the table creator,
+/// as a format string and its argument.
+pub fn version_column(
+    versioned: bool,
+    sqlx: &TokenStream,
+    database: &TokenStream,
+) -> (String, Option<TokenStream>) {
+    if !versioned {
+        return (String::new(), None);
+    }
+
+    (
+        format!(",\n  {VERSION_COLUMN} {{}} not null default 1"),
+        Some(quote! {
+            <#database as ::enorm::component::DatabaseColumnType>::column_type(
+                &<i64 as #sqlx::Type<#database>>::type_info()
+            ),
+        }),
+    )
+}
+
+/// Name of the column holding the time a soft-deleted component was removed at.
+pub const DELETED_AT_COLUMN: &str = "deleted_at";
+
+/// Rejects `#[enorm(soft_delete)]` on components which already have a
+/// column named like the one it adds.
+pub fn check_soft_delete<'a>(
+    soft_delete: bool,
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> syn::Result<()> {
+    if !soft_delete {
+        return Ok(());
+    }
+
+    match fields
+        .into_iter()
+        .find(|field| field.column_name().eq_ignore_ascii_case(DELETED_AT_COLUMN))
+    {
+        Some(field) => Err(syn::Error::new_spanned(
+            field.ident(),
+            format!("soft_delete components can't have a `{DELETED_AT_COLUMN}` column"),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Generates the definition of the `deleted_at` column within the table creator.
+pub fn deleted_at_column(soft_delete: bool) -> String {
+    if soft_delete {
+        format!(",\n  {DELETED_AT_COLUMN} timestamp null")
+    } else {
+        String::new()
+    }
+}
+
+/// Generates a statement removing the component of the entity, which only
+/// tombstones soft-deleted components.
+pub fn delete_statement(table: &str, placeholder: Placeholder, soft_delete: bool) -> String {
+    let entity = &placeholders(placeholder, 1)[0];
+
+    if soft_delete {
+        format!(
+            "update {table} set {DELETED_AT_COLUMN} = current_timestamp where entity = {entity} and {DELETED_AT_COLUMN} is null"
+        )
+    } else {
+        format!("delete from {table} where entity = {entity}")
+    }
+}
+
+/// Selects the component of the entity, unless it has been removed.
+pub fn exists_statement(table: &str, placeholder: Placeholder, soft_delete: bool) -> String {
+    let entity = &placeholders(placeholder, 1)[0];
+
+    if soft_delete {
+        format!("select 1 from {table} where entity = {entity} and {DELETED_AT_COLUMN} is null")
+    } else {
+        format!("select 1 from {table} where entity = {entity}")
+    }
+}
+
+/// Implements `SoftDeleteComponent` for components declared using
+/// `#[enorm(soft_delete)]`.
+pub fn soft_delete_component(
+    component_name: &Ident,
+    table: &str,
+    soft_delete: bool,
+    database: &TokenStream,
+) -> TokenStream {
+    if !soft_delete {
+        return TokenStream::new();
+    }
+
+    let purge = format!("delete from {table} where {DELETED_AT_COLUMN} is not null");
+
+    quote! {
+        impl ::enorm::soft_delete::SoftDeleteComponent<#database> for #component_name {
+            const PURGE: &'static str = #purge;
+        }
+    }
+}
+
+/// Names of the columns added to the history table of a component.
+pub const OPERATION_COLUMN: &str = "__enorm_operation";
+pub const CHANGED_AT_COLUMN: &str = "__enorm_changed_at";
+
+/// Expression evaluating to the current time of the database, in microseconds
+/// since the Unix epoch. Tombstones of soft-deleted components are stamped by
+/// the same clock, using `current_timestamp`.
+pub fn now(placeholder: Placeholder) -> &'static str {
+    match placeholder {
+        Placeholder::Numbered('$') => {
+            "cast(extract(epoch from current_timestamp) * 1000000 as bigint)"
+        }
+        Placeholder::Numbered(_) => "cast((julianday('now') - 2440587.5) * 86400000000 as integer)",
+        Placeholder::Positional => "cast(unix_timestamp(now(6)) * 1000000 as signed)",
+    }
+}
+
+/// Derived table `__enorm_target` holding the entity bound to `entity`, along
+/// with the time of its latest change recorded in the history table.
+pub fn history_target(table: &str, entity: &str) -> String {
+    format!(
+        "(select __enorm_entity.entity, max({table}_history.{CHANGED_AT_COLUMN}) as changed_at \
+         from (select {entity} as entity) as __enorm_entity \
+         left join {table}_history on {table}_history.entity = __enorm_entity.entity \
+         group by __enorm_entity.entity) as __enorm_target"
+    )
+}
+
+/// Expression evaluating to the time of a change of the entity of
+/// [`history_target`], which is the current time of the database, unless a
+/// change of the entity was recorded since. Changes made in quick succession,
+/// or within a single transaction, are thus still ordered.
+pub fn changed_at(placeholder: Placeholder) -> String {
+    let now = now(placeholder);
+
+    format!(
+        "case when __enorm_target.changed_at >= {now} then __enorm_target.changed_at + 1 else {now} end"
+    )
+}
+
+/// Generates a statement copying the component of the entity into its
+/// history table, which binds the entity.
+///
+/// Removals are recorded without the columns of the component, and nothing
+/// is recorded if the entity has no component.
+pub fn history_statement(
+    table: &str,
+    columns: &[&str],
+    placeholder: Placeholder,
+    soft_delete: bool,
+    operation: &str,
+) -> String {
+    let columns = if operation == "remove" { &[] } else { columns };
+
+    let column_names = columns
+        .iter()
+        .map(|column| format!(", {column}"))
+        .collect::<String>();
+
+    let qualified_names = columns
+        .iter()
+        .map(|column| format!(", {table}.{column}"))
+        .collect::<String>();
+
+    let mut statement = format!(
+        "insert into {table}_history(entity{column_names}, {OPERATION_COLUMN}, {CHANGED_AT_COLUMN}) \
+         select {table}.entity{qualified_names}, '{operation}', {changed_at} from {table} inner join {target} using (entity)",
+        changed_at = changed_at(placeholder),
+        target = history_target(table, &placeholders(placeholder, 1)[0]),
+    );
+
+    if soft_delete {
+        statement.push_str(&format!(" where {table}.{DELETED_AT_COLUMN} is null"));
+    }
+
+    statement
+}
+
+/// Statements recording an upsert in the history table, the first queued
+/// before the upsert, recording whether the component is inserted or updated,
+/// and the second after it, filling in the new columns of the latest change.
+///
+/// Marker components have no columns to fill in, and only need the first.
+pub fn upsert_history_statements(
+    table: &str,
+    columns: &[&str],
+    placeholder: Placeholder,
+    soft_delete: bool,
+) -> (String, Option<String>) {
+    let deleted = if soft_delete {
+        format!(" and {table}.{DELETED_AT_COLUMN} is null")
+    } else {
+        String::new()
+    };
+
+    let entity = &placeholders(placeholder, 1)[0];
+
+    let operation = format!(
+        "insert into {table}_history(entity, {OPERATION_COLUMN}, {CHANGED_AT_COLUMN}) \
+         select __enorm_target.entity, case when exists(select 1 from {table} where {table}.entity = __enorm_target.entity{deleted}) then 'update' else 'insert' end, {changed_at} from {target}",
+        changed_at = changed_at(placeholder),
+        target = history_target(table, entity),
+    );
+
+    if columns.is_empty() {
+        return (operation, None);
+    }
+
+    let assignments = columns
+        .iter()
+        .map(|column| {
+            format!(
+                "{column} = (select {table}.{column} from {table} where {table}.entity = {table}_history.entity)"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let update = match placeholder {
+        Placeholder::Numbered(_) => format!(
+            "update {table}_history set {assignments} where entity = {entity} \
+             and {CHANGED_AT_COLUMN} = (select max({CHANGED_AT_COLUMN}) from {table}_history where entity = {entity})"
+        ),
+        // MySQL can't select from the table being updated, other than through
+        // a derived table which it materializes.
+        Placeholder::Positional => format!(
+            "update {table}_history inner join (select entity, max({CHANGED_AT_COLUMN}) as changed_at from {table}_history where entity = ? group by entity) as __enorm_latest \
+             on {table}_history.entity = __enorm_latest.entity and {table}_history.{CHANGED_AT_COLUMN} = __enorm_latest.changed_at \
+             set {assignments}"
+        ),
+    };
+
+    (operation, Some(update))
+}
+
+/// Generates the members of `Component` which record the history of
+/// components declared using `#[enorm(history)]`.
+///
+/// `definitions` produce the SQL types of the `columns`, all of which are
+/// nullable within the history table.
+#[allow(clippy::too_many_arguments)]
+pub fn history_members(
+    history: bool,
+    table: &str,
+    columns: &[&str],
+    definitions: &[TokenStream],
+    placeholder: Placeholder,
+    soft_delete: bool,
+    sqlx: &TokenStream,
+    database: &TokenStream,
+) -> TokenStream {
+    if !history {
+        return TokenStream::new();
+    }
+
+    let [insert, update, remove] = ["insert", "update", "remove"]
+        .map(|operation| history_statement(table, columns, placeholder, soft_delete, operation));
+
+    let (upsert_operation, upsert_columns) =
+        upsert_history_statements(table, columns, placeholder, soft_delete);
+    let upsert_columns = upsert_columns.map(|statement| {
+        quote! {
+            query.query(#statement, |query| query);
+        }
+    });
+
+    let history_table = format!("{table}_history");
+    let format_str = format!(
+        "create table if not exists {history_table}(\n  entity {{}} not null{columns},\n  {OPERATION_COLUMN} text not null,\n  {CHANGED_AT_COLUMN} {{}} not null{{}}\n);",
+        columns = columns
+            .iter()
+            .map(|column| format!(",\n  {column} {{}} null"))
+            .collect::<String>(),
+    );
+
+    quote! {
+        const HISTORY: bool = true;
+
+        fn record_history<'query, EntityId>(
+            query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>,
+            kind: ::enorm::changes::ChangeKind,
+        ) where
+            EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
+        {
+            let statement = match kind {
+                ::enorm::changes::ChangeKind::Insert => #insert,
+                ::enorm::changes::ChangeKind::Update => #update,
+                ::enorm::changes::ChangeKind::Remove => #remove,
+            };
+
+            query.query(statement, |query| query)
+        }
+
+        fn upsert_component<'query, EntityId>(
+            &'query self,
+            query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>,
+        ) where
+            EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
+        {
+            // Whether the component is inserted or updated is only known before the upsert.
+            query.query(#upsert_operation, |query| query);
+
+            query.query(<Self as ::enorm::component::Component<#database>>::UPSERT, move |query| {
+                <Self as ::enorm::serialization::Serializable<#database>>::serialize(self, query)
+            });
+
+            #upsert_columns
+        }
+
+        fn create_history_statements<EntityId>() -> Vec<String>
+        where
+            EntityId: #sqlx::Type<#database>,
+        {
+            let indexes = [::enorm::component::IndexDefinition {
+                columns: &["entity", #CHANGED_AT_COLUMN],
+                unique: false,
+            }];
+
+            let mut statements = vec![format!(
+                #format_str,
+                <#database as ::enorm::component::DatabaseColumnType>::key_type(
+                    &<EntityId as #sqlx::Type<#database>>::type_info()
+                ),
+                #(#definitions,)*
+                <#database as ::enorm::component::DatabaseColumnType>::column_type(
+                    &<i64 as #sqlx::Type<#database>>::type_info()
+                ),
+                <#database as ::enorm::component::DatabaseColumnType>::inline_indexes(
+                    #history_table,
+                    &indexes,
+                    &[],
+                ),
+            )];
+
+            statements.extend(
+                <#database as ::enorm::component::DatabaseColumnType>::index_statements(
+                    #history_table,
+                    &indexes,
+                ),
+            );
+
+            statements
+        }
+    }
+}
+
+/// Generates the recording of rows inserted by `insert_many` in the history
+/// table of components declared using `#[enorm(history)]`.
+pub fn history_insert_many(
+    history: bool,
+    table: &str,
+    columns: &[&str],
+    placeholder: Placeholder,
+    soft_delete: bool,
+) -> TokenStream {
+    if !history {
+        return TokenStream::new();
+    }
+
+    let statement = history_statement(table, columns, placeholder, soft_delete, "insert");
+
+    quote! {
+        query.query_each(#statement, rows);
+    }
+}
+
 /// Generates placeholder values corresponding to the number of columns.
-pub fn placeholders(character: char, count: usize) -> Vec<String> {
-    std::iter::repeat(character)
-        .enumerate()
-        .skip(1)
-        .take(count)
-        .map(|(i, character)| format!("{character}{i}"))
+pub fn placeholders(placeholder: Placeholder, count: usize) -> Vec<String> {
+    (1..=count)
+        .map(|i| match placeholder {
+            Placeholder::Numbered(character) => format!("{character}{i}"),
+            Placeholder::Positional => "?".to_string(),
+        })
         .collect::<Vec<_>>()
 }
+
+/// Generates an update statement for the given columns, which binds the
+/// entity before the values of the columns, followed by the version if
+/// the update is guarded. Tombstoned components are left unchanged.
+pub fn update_statement(
+    table: &str,
+    columns: &[&str],
+    placeholder: Placeholder,
+    versioning: Versioning,
+    soft_delete: bool,
+) -> String {
+    let placeholders = placeholders(placeholder, columns.len() + 2);
+
+    match placeholder {
+        Placeholder::Numbered(_) => {
+            let mut field_updates = columns
+                .iter()
+                .zip(placeholders.iter().skip(1))
+                .map(|(column, placeholder)| format!("{column} = {placeholder}"))
+                .collect::<Vec<_>>();
+
+            if versioning != Versioning::None {
+                field_updates.push(format!("{VERSION_COLUMN} = {VERSION_COLUMN} + 1"));
+            }
+
+            // Marker components have no columns to update, but the statement
+            // must still be valid and bind the entity.
+            if field_updates.is_empty() {
+                field_updates.push("entity = entity".to_string());
+            }
+
+            let mut statement = format!(
+                "update {table} set {field_updates} where entity = {entity}",
+                field_updates = field_updates.join(", "),
+                entity = placeholders[0],
+            );
+
+            if soft_delete {
+                statement.push_str(&format!(" and {DELETED_AT_COLUMN} is null"));
+            }
+
+            if versioning == Versioning::Guard {
+                statement.push_str(&format!(
+                    " and {VERSION_COLUMN} = {version}",
+                    version = placeholders[columns.len() + 1]
+                ));
+            }
+
+            statement
+        }
+        Placeholder::Positional => {
+            // Anonymous parameters are bound in order, so the entity is
+            // joined in ahead of the assignments instead of filtered on last.
+            let mut field_updates = columns
+                .iter()
+                .map(|column| format!("{table}.{column} = ?"))
+                .collect::<Vec<_>>();
+
+            if versioning != Versioning::None {
+                field_updates.push(format!(
+                    "{table}.{VERSION_COLUMN} = {table}.{VERSION_COLUMN} + 1"
+                ));
+            }
+
+            if field_updates.is_empty() {
+                field_updates.push(format!("{table}.entity = {table}.entity"));
+            }
+
+            let mut statement = format!(
+                "update {table} inner join (select ? as entity) as __enorm_target using (entity) set {field_updates}",
+                field_updates = field_updates.join(", "),
+            );
+
+            let mut conditions = Vec::new();
+
+            if soft_delete {
+                conditions.push(format!("{table}.{DELETED_AT_COLUMN} is null"));
+            }
+
+            // MySQL assigns in order, so the guard can't refer to the
+            // version after it was incremented.
+            if versioning == Versioning::Guard {
+                conditions.push(format!("{table}.{VERSION_COLUMN} = ?"));
+            }
+
+            if !conditions.is_empty() {
+                statement.push_str(&format!(" where {}", conditions.join(" and ")));
+            }
+
+            statement
+        }
+    }
+}
+
+/// Generates an insert statement which replaces the existing component,
+/// if the entity already has one. Tombstoned components are replaced as well.
+pub fn upsert_statement(
+    table: &str,
+    columns: &[&str],
+    placeholder: Placeholder,
+    versioning: Versioning,
+    soft_delete: bool,
+) -> String {
+    let placeholders = placeholders(placeholder, columns.len() + 1);
+
+    let insert = format!(
+        "insert into {table}(entity{column_names}) values({placeholders})",
+        column_names = columns
+            .iter()
+            .map(|column| format!(", {column}"))
+            .collect::<String>(),
+        placeholders = placeholders.join(", "),
+    );
+
+    match placeholder {
+        Placeholder::Numbered(_) => {
+            let mut field_updates = columns
+                .iter()
+                .map(|column| format!("{column} = excluded.{column}"))
+                .collect::<Vec<_>>();
+
+            if versioning != Versioning::None {
+                field_updates.push(format!("{VERSION_COLUMN} = {table}.{VERSION_COLUMN} + 1"));
+            }
+
+            if soft_delete {
+                field_updates.push(format!("{DELETED_AT_COLUMN} = null"));
+            }
+
+            if field_updates.is_empty() {
+                return format!("{insert} on conflict(entity) do nothing");
+            }
+
+            format!(
+                "{insert} on conflict(entity) do update set {field_updates}",
+                field_updates = field_updates.join(", ")
+            )
+        }
+        Placeholder::Positional => {
+            let mut field_updates = columns
+                .iter()
+                .map(|column| format!("{column} = values({column})"))
+                .collect::<Vec<_>>();
+
+            if versioning != Versioning::None {
+                field_updates.push(format!("{VERSION_COLUMN} = {VERSION_COLUMN} + 1"));
+            }
+
+            if soft_delete {
+                field_updates.push(format!("{DELETED_AT_COLUMN} = null"));
+            }
+
+            if field_updates.is_empty() {
+                field_updates.push("entity = entity".to_string());
+            }
+
+            format!(
+                "{insert} on duplicate key update {field_updates}",
+                field_updates = field_updates.join(", ")
+            )
+        }
+    }
+}
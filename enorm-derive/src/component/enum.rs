@@ -2,20 +2,32 @@ use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 use syn::{spanned::Spanned, Data, DeriveInput};
 
-use crate::{component::placeholders, field::Field};
+use crate::{
+    component::{
+        check_soft_delete, component_indexes, delete_statement, deleted_at_column,
+        exists_statement, history_insert_many, history_members, indexes_fn, placeholders,
+        soft_delete_component, tables_fn, update_statement, upsert_statement, version_column,
+        Placeholder, Versioning,
+    },
+    field::{Field, IndexKind},
+};
 
 use super::{ComponentAttribute, ComponentAttributeList};
 
 #[derive(Debug)]
 pub struct Variant {
-    name: Ident,
-    fields: Vec<Field>,
+    pub name: Ident,
+    pub fields: Vec<Field>,
 }
 
 #[derive(Debug)]
 pub struct EnumComponent {
     pub typename: Ident,
     pub table_name: String,
+    pub indexes: Vec<(IndexKind, Vec<String>)>,
+    pub versioned: bool,
+    pub soft_delete: bool,
+    pub history: bool,
     pub variants: Vec<Variant>,
 }
 
@@ -24,25 +36,40 @@ impl EnumComponent {
         &self,
         sqlx: &TokenStream,
         database: &TokenStream,
-        placeholder_char: char,
+        placeholder: Placeholder,
     ) -> TokenStream {
         let component_name = &self.typename;
 
-        let statements = self.statements(placeholder_char);
+        let statements = self.statements(placeholder);
+        let versioned = self.versioned(placeholder, database);
+        let soft_delete = soft_delete_component(
+            component_name,
+            self.table_name.trim_matches('"'),
+            self.soft_delete,
+            database,
+        );
+        let history = self.history(sqlx, database, placeholder);
         let table = self.table();
         let columns = self.columns(sqlx, database);
         let table_creator = self.table_creator(sqlx, database);
+        let indexes = indexes_fn(&self.indexes);
+        let tables = tables_fn(component_name, &self.fields(), sqlx, database);
         let remove = self.remove(sqlx, database);
         let insert = self.insert(sqlx, database);
         let update = self.update(database);
+        let upsert = self.upsert(sqlx, database);
+        let insert_many = self.insert_many(sqlx, database, placeholder);
         let serialize = self.field_serializer(sqlx, database);
         let deserialize = self.field_deserializer(sqlx, database);
+        let variant_encoder = self.variant_encoder(sqlx, database);
 
         quote! {
             impl ::enorm::component::Component<#database> for #component_name {
                 #statements
+                #history
                 #table
                 #columns
+                #indexes
                 #table_creator
             }
 
@@ -52,6 +79,8 @@ impl EnumComponent {
                 #serialize
                 #insert
                 #update
+                #upsert
+                #insert_many
             }
 
             impl ::enorm::serialization::Deserializeable<#database> for #component_name {
@@ -61,10 +90,52 @@ impl EnumComponent {
             impl ::enorm::tables::Removable<#database> for #component_name {
                 #remove
             }
+
+            impl ::enorm::tables::Tables<#database> for #component_name {
+                #tables
+            }
+
+            #versioned
+            #soft_delete
+
+            #variant_encoder
+        }
+    }
+
+    /// Name of the type enumerating the variants, as generated by
+    /// [`reflect_enum_component`](crate::reflect::reflect_enum_component).
+    pub fn variant_typename(&self) -> Ident {
+        Ident::new(&format!("{}Variant", self.typename), self.typename.span())
+    }
+
+    /// Encodes the variant type the same way as the `__enorm_tag` column,
+    /// so it can be compared against in conditions.
+    fn variant_encoder(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let variant_typename = self.variant_typename();
+
+        quote! {
+            impl #sqlx::Type<#database> for #variant_typename {
+                fn type_info() -> <#database as #sqlx::Database>::TypeInfo {
+                    <&str as #sqlx::Type<#database>>::type_info()
+                }
+
+                fn compatible(ty: &<#database as #sqlx::Database>::TypeInfo) -> bool {
+                    <&str as #sqlx::Type<#database>>::compatible(ty)
+                }
+            }
+
+            impl<'q> #sqlx::Encode<'q, #database> for #variant_typename {
+                fn encode_by_ref(
+                    &self,
+                    buf: &mut <#database as #sqlx::Database>::ArgumentBuffer<'q>,
+                ) -> Result<#sqlx::encode::IsNull, #sqlx::error::BoxDynError> {
+                    <&str as #sqlx::Encode<'q, #database>>::encode_by_ref(&self.as_str(), buf)
+                }
+            }
         }
     }
 
-    fn fields(&self) -> Vec<Field> {
+    pub fn fields(&self) -> Vec<Field> {
         self.variants
             .iter()
             .flat_map(|variant| &variant.fields)
@@ -72,7 +143,7 @@ impl EnumComponent {
             .collect()
     }
 
-    fn statements(&self, placeholder_char: char) -> TokenStream {
+    fn statements(&self, placeholder: Placeholder) -> TokenStream {
         let table = &self.table_name.trim_matches('"');
 
         let mut column_names: Vec<_> = self
@@ -83,7 +154,7 @@ impl EnumComponent {
 
         column_names.insert(0, "__enorm_tag".to_string());
 
-        let placeholders = placeholders(placeholder_char, column_names.len() + 1);
+        let placeholders = placeholders(placeholder, column_names.len() + 1);
 
         let insert = format!(
             "insert into {table}(entity, {column_names}) values({placeholders});",
@@ -91,28 +162,108 @@ impl EnumComponent {
             column_names = column_names.join("")
         );
 
-        let update = {
-            let field_updates = column_names
-                .iter()
-                .zip(placeholders.iter().skip(1))
-                .map(|(column, placeholder)| format!("{column} = {placeholder}"))
-                .collect::<Vec<_>>();
-
-            format!(
-                "update {table} set {field_updates} where entity = {placeholder_char}1",
-                field_updates = field_updates.join("")
-            )
-        };
+        let fields = self.fields();
+        let columns = ["__enorm_tag"]
+            .into_iter()
+            .chain(fields.iter().map(Field::column_name))
+            .collect::<Vec<_>>();
 
-        let delete = format!("delete from {table} where entity = {placeholder_char}1");
+        let versioning = Versioning::of(self.versioned);
+        let update = update_statement(table, &columns, placeholder, versioning, self.soft_delete);
+        let upsert = upsert_statement(table, &columns, placeholder, versioning, self.soft_delete);
+        let delete = delete_statement(table, placeholder, self.soft_delete);
+        let soft_delete = self.soft_delete;
+        let versioned = self.versioned;
 
         quote! {
             const INSERT: &'static str = #insert;
             const UPDATE: &'static str = #update;
+            const UPSERT: &'static str = #upsert;
             const DELETE: &'static str = #delete;
+            const SOFT_DELETE: bool = #soft_delete;
+            const VERSIONED: bool = #versioned;
+            const TAGGED: bool = true;
+        }
+    }
+
+    fn history(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder: Placeholder,
+    ) -> TokenStream {
+        let fields = self.fields();
+        let columns = ["__enorm_tag"]
+            .into_iter()
+            .chain(fields.iter().map(Field::column_name))
+            .collect::<Vec<_>>();
+
+        // The tag is declared as text by the table creator as well.
+        let definitions = std::iter::once(quote! { "text" })
+            .chain(fields.iter().map(|field| field.sql_type(sqlx, database)))
+            .collect::<Vec<_>>();
+
+        history_members(
+            self.history,
+            self.table_name.trim_matches('"'),
+            &columns,
+            &definitions,
+            placeholder,
+            self.soft_delete,
+            sqlx,
+            database,
+        )
+    }
+
+    /// Implements `VersionedComponent` for components declared using
+    /// `#[enorm(versioned)]`.
+    fn versioned(&self, placeholder: Placeholder, database: &TokenStream) -> TokenStream {
+        if !self.versioned {
+            return TokenStream::new();
+        }
+
+        let component_name = &self.typename;
+        let table = &self.table_name.trim_matches('"');
+
+        let fields = self.fields();
+        let columns = ["__enorm_tag"]
+            .into_iter()
+            .chain(fields.iter().map(Field::column_name))
+            .collect::<Vec<_>>();
+
+        let guarded_update = update_statement(
+            table,
+            &columns,
+            placeholder,
+            Versioning::Guard,
+            self.soft_delete,
+        );
+        let exists = exists_statement(table, placeholder, self.soft_delete);
+
+        quote! {
+            impl ::enorm::versioned::VersionedComponent<#database> for #component_name {
+                const GUARDED_UPDATE: &'static str = #guarded_update;
+                const EXISTS: &'static str = #exists;
+            }
         }
     }
 
+    /// Start of a multi-row insert statement, and the number of parameters per row.
+    fn bulk_insert_statement(&self) -> (String, usize) {
+        let table = &self.table_name.trim_matches('"');
+
+        let fields = self.fields();
+        let column_names: String = fields
+            .iter()
+            .map(|field| format!(", {}", field.column_name()))
+            .collect();
+
+        (
+            format!("insert into {table}(entity, __enorm_tag{column_names}) values"),
+            fields.len() + 2,
+        )
+    }
+
     fn table_creator(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         let table = &self.table_name.trim_matches('"');
 
@@ -125,40 +276,40 @@ impl EnumComponent {
 
         columns.insert(0, "\n,  __enorm_tag text not null".to_string());
 
+        let (version, version_type) = version_column(self.versioned, sqlx, database);
+        let deleted_at = deleted_at_column(self.soft_delete);
+
         let format_str = format!(
-            "create table if not exists {table}(\n  entity {{}} primary key{columns}\n);",
+            "create table if not exists {table}(\n  entity {{}} primary key{columns}{version}{deleted_at}{{}}{{}}\n);",
             columns = columns.join("")
         );
 
-        let definitions = self.fields().into_iter().map(|field| {
-            let typename = if let Some(intermediate) = field.intermediate() {
-                intermediate
-            } else {
-                field.typename()
-            };
-
-            quote! {<#typename as #sqlx::Type<#database>>::type_info().name() }
-        });
+        let definitions = self
+            .fields()
+            .into_iter()
+            .map(|field| field.sql_type(sqlx, database));
 
         quote! {
-            fn create_component_table<'pool, EntityId>(
-                pool: &'pool #sqlx::Pool<#database>,
-            ) -> impl ::core::future::Future<Output = Result<<#database as #sqlx::Database>::QueryResult, #sqlx::Error>> + Send
+            fn create_table_statement<EntityId>() -> String
             where
                 EntityId: #sqlx::Type<#database>,
             {
-                async move {
-                    use sqlx::TypeInfo as _;
-                    use sqlx::Executor as _;
-
-                    let sql = format!(
-                        #format_str,
-                        <EntityId as #sqlx::Type<#database>>::type_info().name(),
-                        #(#definitions,)*
-                    );
-
-                    pool.execute(sql.as_str()).await
-                }
+                format!(
+                    #format_str,
+                    <#database as ::enorm::component::DatabaseColumnType>::key_type(
+                        &<EntityId as #sqlx::Type<#database>>::type_info()
+                    ),
+                    #(#definitions,)*
+                    #version_type
+                    <#database as ::enorm::component::DatabaseColumnType>::inline_indexes(
+                        #table,
+                        &<Self as ::enorm::component::Component<#database>>::indexes(),
+                        &<Self as ::enorm::component::Component<#database>>::columns(),
+                    ),
+                    <#database as ::enorm::component::DatabaseColumnType>::foreign_keys(
+                        &<Self as ::enorm::component::Component<#database>>::columns(),
+                    ),
+                )
             }
         }
     }
@@ -176,7 +327,16 @@ impl EnumComponent {
         let columns = self
             .fields()
             .into_iter()
-            .map(|field| field.column_definition(sqlx, database));
+            .map(|field| field.column_definition(sqlx, database))
+            // Fields are null for the variants which lack them.
+            .map(|definition| {
+                quote! {
+                    ::enorm::component::ColumnDefinition::<#database> {
+                        nullable: true,
+                        ..#definition
+                    }
+                }
+            });
 
         quote! {
             fn columns() -> Vec<::enorm::component::ColumnDefinition::<#database>> {
@@ -191,6 +351,8 @@ impl EnumComponent {
             where
                 EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
             {
+                <Self as Component<#database>>::record_history(query, ::enorm::changes::ChangeKind::Remove);
+
                 query.query(<Self as Component<#database>>::DELETE, |query| query)
             }
         }
@@ -204,7 +366,57 @@ impl EnumComponent {
             {
                 query.query(<Self as Component<#database>>::INSERT, move |query| {
                     <Self as Serializable<#database>>::serialize(self, query)
-                })
+                });
+
+                <Self as Component<#database>>::record_history(query, ::enorm::changes::ChangeKind::Insert)
+            }
+        }
+    }
+
+    fn upsert(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        quote! {
+            fn upsert<'query, EntityId>(&'query self, query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                <Self as Component<#database>>::upsert_component(self, query)
+            }
+        }
+    }
+
+    fn insert_many(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder: Placeholder,
+    ) -> TokenStream {
+        let (statement, parameters) = self.bulk_insert_statement();
+        let fields = self.fields();
+        let columns = ["__enorm_tag"]
+            .into_iter()
+            .chain(fields.iter().map(Field::column_name))
+            .collect::<Vec<_>>();
+        let history = history_insert_many(
+            self.history,
+            self.table_name.trim_matches('"'),
+            &columns,
+            placeholder,
+            self.soft_delete,
+        );
+
+        quote! {
+            fn insert_many<'query, EntityId>(
+                rows: &[(&'query EntityId, &'query Self)],
+                query: &mut ::enorm::bulk::BulkInsert<'query, #database>,
+            )
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + 'query
+            {
+                query.insert(#statement, #parameters, rows, |query, component| {
+                    <Self as Serializable<#database>>::serialize(component, query)
+                });
+
+                #history
             }
         }
     }
@@ -217,7 +429,9 @@ impl EnumComponent {
             {
                 query.query(<Self as Component<#database>>::UPDATE, move |query| {
                     <Self as Serializable<#database>>::serialize(self, query)
-                })
+                });
+
+                <Self as Component<#database>>::record_history(query, ::enorm::changes::ChangeKind::Update)
             }
         }
     }
@@ -327,7 +541,7 @@ impl EnumComponent {
             .into_iter()
             .chain(self.fields().into_iter().map(|field| match field {
                 Field::Numbered { ident, .. } => {
-                    let ident = format!("column{}", ident.to_string());
+                    let ident = format!("column{}", ident);
                     quote! {
                         #ident
                     }
@@ -367,6 +581,8 @@ impl EnumComponent {
         };
 
         let table_name = &self.table_name;
+        let soft_delete = self.soft_delete;
+        let history = self.history;
 
         quote! {
             fn cte() -> Box<dyn ::enorm::cte::CommonTableExpression> {
@@ -375,6 +591,9 @@ impl EnumComponent {
                     columns: &[
                         #(#columns,)*
                     ],
+                    exclude_deleted: #soft_delete,
+                    history: #history,
+                    as_of: None,
                 })
             }
 
@@ -411,7 +630,7 @@ impl EnumComponent {
         .flatten()
         .collect();
 
-        let variants = Result::from_iter(data.variants.into_iter().map(|variant| {
+        let variants: Vec<Variant> = Result::from_iter(data.variants.into_iter().map(|variant| {
             Ok::<Variant, syn::Error>(Variant {
                 name: variant.ident,
                 fields: Result::from_iter(
@@ -429,11 +648,26 @@ impl EnumComponent {
             .find_map(ComponentAttribute::table)
             .unwrap_or(derive.ident.to_string());
 
+        let indexes = component_indexes(
+            &attributes,
+            variants.iter().flat_map(|variant| &variant.fields),
+        )?;
+
         let type_name = derive.ident.clone();
 
+        let soft_delete = attributes.iter().any(ComponentAttribute::soft_delete);
+        check_soft_delete(
+            soft_delete,
+            variants.iter().flat_map(|variant| &variant.fields),
+        )?;
+
         Ok(EnumComponent {
             typename: type_name,
             table_name,
+            indexes,
+            versioned: attributes.iter().any(ComponentAttribute::versioned),
+            soft_delete,
+            history: attributes.iter().any(ComponentAttribute::history),
             variants,
         })
     }
@@ -2,7 +2,15 @@ use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 use syn::{spanned::Spanned, Data, DeriveInput};
 
-use crate::{component::placeholders, field::Field};
+use crate::{
+    component::{
+        check_soft_delete, component_indexes, delete_statement, deleted_at_column,
+        exists_statement, history_insert_many, history_members, indexes_fn, placeholders,
+        soft_delete_component, tables_fn, update_statement, upsert_statement, version_column,
+        Placeholder, Versioning,
+    },
+    field::{Field, IndexKind},
+};
 
 use super::{ComponentAttribute, ComponentAttributeList};
 
@@ -10,6 +18,10 @@ use super::{ComponentAttribute, ComponentAttributeList};
 pub struct StructComponent {
     pub typename: Ident,
     pub table_name: String,
+    pub indexes: Vec<(IndexKind, Vec<String>)>,
+    pub versioned: bool,
+    pub soft_delete: bool,
+    pub history: bool,
     pub fields: Vec<Field>,
 }
 
@@ -18,25 +30,39 @@ impl StructComponent {
         &self,
         sqlx: &TokenStream,
         database: &TokenStream,
-        placeholder_char: char,
+        placeholder: Placeholder,
     ) -> TokenStream {
         let component_name = &self.typename;
 
-        let statements = self.statements(placeholder_char);
+        let statements = self.statements(placeholder);
+        let versioned = self.versioned(placeholder, database);
+        let soft_delete = soft_delete_component(
+            component_name,
+            self.table_name.trim_matches('"'),
+            self.soft_delete,
+            database,
+        );
+        let history = self.history(sqlx, database, placeholder);
         let table = self.table();
         let columns = self.columns(sqlx, database);
         let table_creator = self.table_creator(sqlx, database);
+        let indexes = indexes_fn(&self.indexes);
+        let tables = tables_fn(component_name, &self.fields, sqlx, database);
         let remove = self.remove(sqlx, database);
         let insert = self.insert(sqlx, database);
         let update = self.update(database);
+        let upsert = self.upsert(sqlx, database);
+        let insert_many = self.insert_many(sqlx, database, placeholder);
         let serialize = self.field_serializer(sqlx, database);
         let deserialize = self.field_deserializer(sqlx, database);
 
         quote! {
             impl ::enorm::component::Component<#database> for #component_name {
                 #statements
+                #history
                 #table
                 #columns
+                #indexes
                 #table_creator
             }
 
@@ -46,6 +72,8 @@ impl StructComponent {
                 #serialize
                 #insert
                 #update
+                #upsert
+                #insert_many
             }
 
             impl ::enorm::serialization::Deserializeable<#database> for #component_name {
@@ -55,10 +83,17 @@ impl StructComponent {
             impl ::enorm::tables::Removable<#database> for #component_name {
                 #remove
             }
+
+            impl ::enorm::tables::Tables<#database> for #component_name {
+                #tables
+            }
+
+            #versioned
+            #soft_delete
         }
     }
 
-    fn statements(&self, placeholder_char: char) -> TokenStream {
+    fn statements(&self, placeholder: Placeholder) -> TokenStream {
         let table = &self.table_name.trim_matches('"');
 
         let column_names: Vec<_> = self
@@ -67,7 +102,7 @@ impl StructComponent {
             .map(|field| format!(", {}", field.column_name()))
             .collect();
 
-        let placeholders = placeholders(placeholder_char, column_names.len() + 1);
+        let placeholders = placeholders(placeholder, column_names.len() + 1);
 
         let insert = format!(
             "insert into {table}(entity{column_names}) values({placeholders});",
@@ -75,28 +110,108 @@ impl StructComponent {
             column_names = column_names.join("")
         );
 
-        let update = {
-            let field_updates = column_names
-                .iter()
-                .zip(placeholders.iter().skip(1))
-                .map(|(column, placeholder)| format!("{column} = {placeholder}"))
-                .collect::<Vec<_>>();
-
-            format!(
-                "update {table} set {field_updates} where entity = {placeholder_char}1",
-                field_updates = field_updates.join(", ")
-            )
-        };
+        let columns = self
+            .fields
+            .iter()
+            .map(Field::column_name)
+            .collect::<Vec<_>>();
 
-        let delete = format!("delete from {table} where entity = {placeholder_char}1");
+        let versioning = Versioning::of(self.versioned);
+        let update = update_statement(table, &columns, placeholder, versioning, self.soft_delete);
+        let upsert = upsert_statement(table, &columns, placeholder, versioning, self.soft_delete);
+        let delete = delete_statement(table, placeholder, self.soft_delete);
+        let soft_delete = self.soft_delete;
+        let versioned = self.versioned;
 
         quote! {
             const INSERT: &'static str = #insert;
             const UPDATE: &'static str = #update;
+            const UPSERT: &'static str = #upsert;
             const DELETE: &'static str = #delete;
+            const SOFT_DELETE: bool = #soft_delete;
+            const VERSIONED: bool = #versioned;
         }
     }
 
+    fn history(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder: Placeholder,
+    ) -> TokenStream {
+        let definitions = self
+            .fields
+            .iter()
+            .map(|field| field.sql_type(sqlx, database))
+            .collect::<Vec<_>>();
+
+        let columns = self
+            .fields
+            .iter()
+            .map(Field::column_name)
+            .collect::<Vec<_>>();
+
+        history_members(
+            self.history,
+            self.table_name.trim_matches('"'),
+            &columns,
+            &definitions,
+            placeholder,
+            self.soft_delete,
+            sqlx,
+            database,
+        )
+    }
+
+    /// Implements `VersionedComponent` for components declared using
+    /// `#[enorm(versioned)]`.
+    fn versioned(&self, placeholder: Placeholder, database: &TokenStream) -> TokenStream {
+        if !self.versioned {
+            return TokenStream::new();
+        }
+
+        let component_name = &self.typename;
+        let table = &self.table_name.trim_matches('"');
+
+        let columns = self
+            .fields
+            .iter()
+            .map(Field::column_name)
+            .collect::<Vec<_>>();
+
+        let guarded_update = update_statement(
+            table,
+            &columns,
+            placeholder,
+            Versioning::Guard,
+            self.soft_delete,
+        );
+        let exists = exists_statement(table, placeholder, self.soft_delete);
+
+        quote! {
+            impl ::enorm::versioned::VersionedComponent<#database> for #component_name {
+                const GUARDED_UPDATE: &'static str = #guarded_update;
+                const EXISTS: &'static str = #exists;
+            }
+        }
+    }
+
+    /// Start of a multi-row insert statement, and the number of parameters per row.
+    fn bulk_insert_statement(&self) -> (String, usize) {
+        let table = &self.table_name.trim_matches('"');
+
+        let column_names: String = self
+            .fields
+            .iter()
+            .map(|field| format!(", {}", field.column_name()))
+            .collect();
+
+        (
+            format!("insert into {table}(entity{column_names}) values"),
+            self.fields.len() + 1,
+        )
+    }
+
     fn table_creator(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         let table = &self.table_name.trim_matches('"');
 
@@ -108,8 +223,12 @@ impl StructComponent {
             .collect::<Vec<_>>()
             .join("");
 
-        let format_str =
-            format!("create table if not exists {table}(\n  entity {{}} primary key{columns}\n);");
+        let (version, version_type) = version_column(self.versioned, sqlx, database);
+        let deleted_at = deleted_at_column(self.soft_delete);
+
+        let format_str = format!(
+            "create table if not exists {table}(\n  entity {{}} primary key{columns}{version}{deleted_at}{{}}{{}}\n);"
+        );
 
         let definitions = self
             .fields
@@ -117,24 +236,28 @@ impl StructComponent {
             .map(|field| field.sql_definition(sqlx, database));
 
         quote! {
-            fn create_component_table<'pool, EntityId>(
-                pool: &'pool #sqlx::Pool<#database>,
-            ) -> impl ::core::future::Future<Output = Result<<#database as #sqlx::Database>::QueryResult, #sqlx::Error>> + Send
+            fn create_table_statement<EntityId>() -> String
             where
                 EntityId: #sqlx::Type<#database>,
             {
-                async move {
-                    use sqlx::TypeInfo as _;
-                    use sqlx::Executor as _;
-
-                    let sql = format!(
-                        #format_str,
-                        <EntityId as #sqlx::Type<#database>>::type_info().name(),
-                        #(#definitions,)*
-                    );
-
-                    pool.execute(sql.as_str()).await
-                }
+                use sqlx::TypeInfo as _;
+
+                format!(
+                    #format_str,
+                    <#database as ::enorm::component::DatabaseColumnType>::key_type(
+                        &<EntityId as #sqlx::Type<#database>>::type_info()
+                    ),
+                    #(#definitions,)*
+                    #version_type
+                    <#database as ::enorm::component::DatabaseColumnType>::inline_indexes(
+                        #table,
+                        &<Self as ::enorm::component::Component<#database>>::indexes(),
+                        &<Self as ::enorm::component::Component<#database>>::columns(),
+                    ),
+                    <#database as ::enorm::component::DatabaseColumnType>::foreign_keys(
+                        &<Self as ::enorm::component::Component<#database>>::columns(),
+                    ),
+                )
             }
         }
     }
@@ -167,6 +290,8 @@ impl StructComponent {
             where
                 EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query,
             {
+                <Self as Component<#database>>::record_history(query, ::enorm::changes::ChangeKind::Remove);
+
                 query.query(<Self as Component<#database>>::DELETE, |query| query)
             }
         }
@@ -180,7 +305,57 @@ impl StructComponent {
             {
                 query.query(<Self as Component<#database>>::INSERT, move |query| {
                     <Self as Serializable<#database>>::serialize(self, query)
-                })
+                });
+
+                <Self as Component<#database>>::record_history(query, ::enorm::changes::ChangeKind::Insert)
+            }
+        }
+    }
+
+    fn upsert(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        quote! {
+            fn upsert<'query, EntityId>(&'query self, query: &mut ::enorm::entity::EntityPrefixedQuery<'query, #database, EntityId>)
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + Clone + 'query
+            {
+                <Self as Component<#database>>::upsert_component(self, query)
+            }
+        }
+    }
+
+    fn insert_many(
+        &self,
+        sqlx: &TokenStream,
+        database: &TokenStream,
+        placeholder: Placeholder,
+    ) -> TokenStream {
+        let (statement, parameters) = self.bulk_insert_statement();
+        let columns = self
+            .fields
+            .iter()
+            .map(Field::column_name)
+            .collect::<Vec<_>>();
+        let history = history_insert_many(
+            self.history,
+            self.table_name.trim_matches('"'),
+            &columns,
+            placeholder,
+            self.soft_delete,
+        );
+
+        quote! {
+            fn insert_many<'query, EntityId>(
+                rows: &[(&'query EntityId, &'query Self)],
+                query: &mut ::enorm::bulk::BulkInsert<'query, #database>,
+            )
+            where
+                EntityId: #sqlx::Encode<'query, #database> + #sqlx::Type<#database> + 'query
+            {
+                query.insert(#statement, #parameters, rows, |query, component| {
+                    <Self as Serializable<#database>>::serialize(component, query)
+                });
+
+                #history
             }
         }
     }
@@ -193,7 +368,9 @@ impl StructComponent {
             {
                 query.query(<Self as Component<#database>>::UPDATE, move |query| {
                     <Self as Serializable<#database>>::serialize(self, query)
-                })
+                });
+
+                <Self as Component<#database>>::record_history(query, ::enorm::changes::ChangeKind::Update)
             }
         }
     }
@@ -219,7 +396,7 @@ impl StructComponent {
 
         let columns = self.fields.iter().map(|field| match field {
             Field::Numbered { ident, .. } => {
-                let ident = format!("column{}", ident.to_string());
+                let ident = format!("column{}", ident);
                 quote! {
                     #ident
                 }
@@ -262,6 +439,8 @@ impl StructComponent {
         };
 
         let table_name = &self.table_name;
+        let soft_delete = self.soft_delete;
+        let history = self.history;
 
         quote! {
             fn cte() -> Box<dyn ::enorm::cte::CommonTableExpression> {
@@ -270,6 +449,9 @@ impl StructComponent {
                     columns: &[
                         #(#columns,)*
                     ],
+                    exclude_deleted: #soft_delete,
+                    history: #history,
+                    as_of: None,
                 })
             }
 
@@ -314,9 +496,18 @@ impl StructComponent {
             data.fields.into_iter().enumerate().map(Field::try_from),
         )?;
 
+        let indexes = component_indexes(&attributes, &fields)?;
+
+        let soft_delete = attributes.iter().any(ComponentAttribute::soft_delete);
+        check_soft_delete(soft_delete, &fields)?;
+
         Ok(StructComponent {
             typename: type_name,
             table_name,
+            indexes,
+            versioned: attributes.iter().any(ComponentAttribute::versioned),
+            soft_delete,
+            history: attributes.iter().any(ComponentAttribute::history),
             fields,
         })
     }
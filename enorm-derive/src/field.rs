@@ -4,6 +4,21 @@ use syn::spanned::Spanned;
 use syn::Token;
 use syn::{parse::Parse, Type};
 
+/// Kind of secondary index on a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexKind {
+    Index,
+    Unique,
+}
+
+/// Foreign key from a field to the table of another component.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub component: Type,
+    /// Variant of `enorm::component::OnDelete`.
+    pub on_delete: Ident,
+}
+
 #[derive(Debug, Clone)]
 pub enum Field {
     Numbered {
@@ -11,12 +26,16 @@ pub enum Field {
         typename: Type,
         intermediate_type: Option<Type>,
         column_name: String,
+        index: Option<IndexKind>,
+        references: Option<Reference>,
     },
     Named {
         ident: TokenStream,
         typename: Type,
         intermediate_type: Option<Type>,
         column_name: String,
+        index: Option<IndexKind>,
+        references: Option<Reference>,
     },
 }
 
@@ -29,17 +48,23 @@ impl std::hash::Hash for Field {
                 typename,
                 intermediate_type,
                 column_name,
+                index,
+                references,
             }
             | Field::Named {
                 ident,
                 typename,
                 intermediate_type,
                 column_name,
+                index,
+                references,
             } => {
                 ident.to_string().hash(state);
                 typename.hash(state);
                 intermediate_type.hash(state);
                 column_name.hash(state);
+                index.hash(state);
+                references.hash(state);
             }
         }
     }
@@ -56,18 +81,24 @@ impl PartialEq for Field {
                     typename: l_typename,
                     intermediate_type: l_intermediate_type,
                     column_name: l_column_name,
+                    index: l_index,
+                    references: l_references,
                 },
                 Self::Numbered {
                     ident: r_ident,
                     typename: r_typename,
                     intermediate_type: r_intermediate_type,
                     column_name: r_column_name,
+                    index: r_index,
+                    references: r_references,
                 },
             ) => {
                 l_ident.to_string() == r_ident.to_string()
                     && l_typename == r_typename
                     && l_intermediate_type == r_intermediate_type
                     && l_column_name == r_column_name
+                    && l_index == r_index
+                    && l_references == r_references
             }
             (
                 Self::Named {
@@ -75,18 +106,24 @@ impl PartialEq for Field {
                     typename: l_typename,
                     intermediate_type: l_intermediate_type,
                     column_name: l_column_name,
+                    index: l_index,
+                    references: l_references,
                 },
                 Self::Named {
                     ident: r_ident,
                     typename: r_typename,
                     intermediate_type: r_intermediate_type,
                     column_name: r_column_name,
+                    index: r_index,
+                    references: r_references,
                 },
             ) => {
                 l_ident.to_string() == r_ident.to_string()
                     && l_typename == r_typename
                     && l_intermediate_type == r_intermediate_type
                     && l_column_name == r_column_name
+                    && l_index == r_index
+                    && l_references == r_references
             }
             _ => false,
         }
@@ -96,13 +133,13 @@ impl PartialEq for Field {
 impl Field {
     pub fn ident(&self) -> &TokenStream {
         match self {
-            Field::Numbered { ident, .. } | Field::Named { ident, .. } => &ident,
+            Field::Numbered { ident, .. } | Field::Named { ident, .. } => ident,
         }
     }
 
     pub fn column_name(&self) -> &str {
         match self {
-            Field::Numbered { column_name, .. } | Field::Named { column_name, .. } => &column_name,
+            Field::Numbered { column_name, .. } | Field::Named { column_name, .. } => column_name,
         }
     }
 
@@ -118,7 +155,7 @@ impl Field {
 
     pub fn typename(&self) -> &Type {
         match self {
-            Field::Numbered { typename, .. } | Field::Named { typename, .. } => &typename,
+            Field::Numbered { typename, .. } | Field::Named { typename, .. } => typename,
         }
     }
 
@@ -133,46 +170,102 @@ impl Field {
         }
     }
 
+    pub fn index(&self) -> Option<IndexKind> {
+        match self {
+            Field::Numbered { index, .. } | Field::Named { index, .. } => *index,
+        }
+    }
+
+    pub fn references(&self) -> Option<&Reference> {
+        match self {
+            Field::Numbered { references, .. } | Field::Named { references, .. } => {
+                references.as_ref()
+            }
+        }
+    }
+
     pub fn column_definition(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
         let name = self.column_name();
-        let typename = self.typename();
+        let typename = self.intermediate().unwrap_or(self.typename());
+        let indexed = self.index().is_some();
+        let unique = self.index() == Some(IndexKind::Unique);
 
-        if let Some(intermediate) = self.intermediate() {
-            quote! {
-                ::enorm::component::ColumnDefinition::<#database> {
-                    name: #name,
-                    type_info: <#intermediate as #sqlx::Type<#database>>::type_info(),
-                }
-            }
+        // Matches the definition of the column within the table creator.
+        let nullable = if self.is_option() {
+            quote! { true }
         } else {
-            quote! {
-                ::enorm::component::ColumnDefinition::<#database> {
-                    name: #name,
-                    type_info: <#typename as #sqlx::Type<#database>>::type_info(),
-                }
+            quote! { #sqlx::TypeInfo::is_null(&<#typename as #sqlx::Type<#database>>::type_info()) }
+        };
+
+        let references = match self.references() {
+            Some(Reference {
+                component,
+                on_delete,
+            }) => quote! {
+                Some(::enorm::component::Reference {
+                    table: <#component as ::enorm::component::Component<#database>>::table(),
+                    on_delete: ::enorm::component::OnDelete::#on_delete,
+                })
+            },
+            None => quote! { None },
+        };
+
+        quote! {
+            ::enorm::component::ColumnDefinition::<#database> {
+                name: #name,
+                type_info: <#typename as #sqlx::Type<#database>>::type_info(),
+                nullable: #nullable,
+                indexed: #indexed,
+                unique: #unique,
+                references: #references,
             }
         }
     }
 
-    pub fn sql_definition(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
-        if let Some(intermediate) = self.intermediate() {
-            quote! {
-                <#intermediate as #sqlx::Type<#database>>::type_info().name(),
-                if <#intermediate as #sqlx::Type<#database>>::type_info().is_null() {
-                    "null"
-                } else {
-                    "not null"
-                }
-            }
+    /// Type of the column, which for references must match the `entity` column.
+    pub fn sql_type(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let typename = self.intermediate().unwrap_or(self.typename());
+
+        let function = if self.references().is_some() {
+            quote! { key_type }
         } else {
-            let typename = self.typename();
-            quote! {
-                <#typename as #sqlx::Type<#database>>::type_info().name(),
-                if <#typename as #sqlx::Type<#database>>::type_info().is_null() {
-                    "null"
-                } else {
-                    "not null"
-                }
+            quote! { column_type }
+        };
+
+        quote! {
+            <#database as ::enorm::component::DatabaseColumnType>::#function(
+                &<#typename as #sqlx::Type<#database>>::type_info()
+            )
+        }
+    }
+
+    /// Whether the field is declared as an `Option`, whose type info is
+    /// indistinguishable from that of the type it wraps.
+    fn is_option(&self) -> bool {
+        let Type::Path(path) = self.intermediate().unwrap_or(self.typename()) else {
+            return false;
+        };
+
+        path.path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option")
+    }
+
+    pub fn sql_definition(&self, sqlx: &TokenStream, database: &TokenStream) -> TokenStream {
+        let typename = self.intermediate().unwrap_or(self.typename());
+        let sql_type = self.sql_type(sqlx, database);
+
+        if self.is_option() {
+            return quote! { #sql_type, "null" };
+        }
+
+        quote! {
+            #sql_type,
+            if <#typename as #sqlx::Type<#database>>::type_info().is_null() {
+                "null"
+            } else {
+                "not null"
             }
         }
     }
@@ -270,28 +363,71 @@ impl TryFrom<(usize, syn::Field)> for Field {
 
         let intermediate_type = attributes.iter().find_map(FieldAttribute::intermediate);
 
+        let on_delete = attributes.iter().find_map(FieldAttribute::on_delete);
+
+        let references = match (
+            attributes.iter().find_map(FieldAttribute::references),
+            on_delete,
+        ) {
+            (Some(component), on_delete) => Some(Reference {
+                component,
+                on_delete: on_delete.unwrap_or_else(|| Ident::new("Restrict", field.span())),
+            }),
+            (None, Some(on_delete)) => {
+                return Err(syn::Error::new(
+                    on_delete.span(),
+                    "on_delete requires the field to reference a component",
+                ))
+            }
+            (None, None) => None,
+        };
+
+        // A unique index makes a plain one redundant, and references are
+        // always indexed, since they are used to look up referencing entities.
+        let index_kind = attributes
+            .iter()
+            .filter_map(FieldAttribute::index)
+            .max_by_key(|kind| *kind == IndexKind::Unique)
+            .or(references.as_ref().map(|_| IndexKind::Index));
+
         let column_name = attributes
             .iter()
             .find_map(FieldAttribute::column)
             .or_else(|| field.ident.as_ref().map(ToString::to_string))
             .unwrap_or_else(|| format!("column{index}"));
 
-        if let Some(ident) = field.ident {
-            Ok(Field::Named {
+        let field = if let Some(ident) = field.ident {
+            Field::Named {
                 ident: quote! { #ident },
                 typename,
                 intermediate_type,
                 column_name,
-            })
+                index: index_kind,
+                references,
+            }
         } else {
             let index = syn::Index::from(index);
-            Ok(Field::Numbered {
+            Field::Numbered {
                 ident: quote! { #index },
                 typename,
                 intermediate_type,
                 column_name,
-            })
+                index: index_kind,
+                references,
+            }
+        };
+
+        // Setting the column to null would leave a row the field can't be read from.
+        if let Some(reference) = field.references() {
+            if reference.on_delete == "SetNull" && !field.is_option() {
+                return Err(syn::Error::new(
+                    reference.on_delete.span(),
+                    "on_delete = set_null requires the field to be an Option",
+                ));
+            }
         }
+
+        Ok(field)
     }
 }
 
@@ -320,6 +456,14 @@ pub enum FieldAttribute {
     Column { name: Literal },
     /// Intermediate type to convert to/from before storing in database.
     Intermediate { typename: Type },
+    /// Creates an index on the field's column.
+    Index,
+    /// Creates a unique index on the field's column.
+    Unique,
+    /// Makes the field reference entities which have the given component.
+    References { component: Type },
+    /// What happens when the referenced component is removed.
+    OnDelete { action: Ident },
 }
 
 impl FieldAttribute {
@@ -338,6 +482,30 @@ impl FieldAttribute {
             None
         }
     }
+
+    pub fn index(&self) -> Option<IndexKind> {
+        match self {
+            FieldAttribute::Index => Some(IndexKind::Index),
+            FieldAttribute::Unique => Some(IndexKind::Unique),
+            _ => None,
+        }
+    }
+
+    pub fn references(&self) -> Option<Type> {
+        if let FieldAttribute::References { component } = self {
+            Some(component.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn on_delete(&self) -> Option<Ident> {
+        if let FieldAttribute::OnDelete { action } = self {
+            Some(action.clone())
+        } else {
+            None
+        }
+    }
 }
 
 impl Parse for FieldAttribute {
@@ -359,6 +527,35 @@ impl Parse for FieldAttribute {
                     typename: input.parse()?,
                 }
             }
+            "index" => FieldAttribute::Index,
+            "unique" => FieldAttribute::Unique,
+            "references" => {
+                input.parse::<Token![=]>()?;
+
+                FieldAttribute::References {
+                    component: input.parse()?,
+                }
+            }
+            "on_delete" => {
+                input.parse::<Token![=]>()?;
+
+                let action: Ident = input.parse()?;
+                let variant = match action.to_string().as_str() {
+                    "restrict" => "Restrict",
+                    "cascade" => "Cascade",
+                    "set_null" => "SetNull",
+                    _ => {
+                        return Err(syn::Error::new(
+                            action.span(),
+                            "expected one of restrict, cascade or set_null",
+                        ))
+                    }
+                };
+
+                FieldAttribute::OnDelete {
+                    action: Ident::new(variant, action.span()),
+                }
+            }
             _ => return Err(syn::Error::new(ident.span(), "unexpected Field attribute")),
         })
     }
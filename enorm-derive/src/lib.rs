@@ -5,14 +5,17 @@ mod reflect;
 mod variant;
 
 use archetype::Archetype;
-use component::Component;
+use component::{Component, Placeholder};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, TokenStreamExt};
 
 #[proc_macro_derive(Component, attributes(enorm))]
 pub fn derive_component(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let stream = TokenStream::from(stream);
-    let component: Component = syn::parse2(stream).unwrap();
+    let component: Component = match syn::parse2(stream) {
+        Ok(component) => component,
+        Err(error) => return error.to_compile_error().into(),
+    };
 
     component.derive().into()
 }
@@ -20,9 +23,12 @@ pub fn derive_component(stream: proc_macro::TokenStream) -> proc_macro::TokenStr
 #[proc_macro_derive(Archetype, attributes(enorm))]
 pub fn derive_archetype(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let stream = TokenStream::from(stream);
-    let archetype: Archetype = syn::parse2(stream).unwrap();
+    let archetype: Archetype = match syn::parse2(stream) {
+        Ok(archetype) => archetype,
+        Err(error) => return error.to_compile_error().into(),
+    };
 
-    let implementation = |database: Ident, _: char| {
+    let implementation = |database: Ident, _: Placeholder| {
         #[cfg(feature = "bundled")]
         let sqlx = quote! {::enorm::sqlx};
         #[cfg(not(feature = "bundled"))]
@@ -37,20 +43,29 @@ pub fn derive_archetype(stream: proc_macro::TokenStream) -> proc_macro::TokenStr
 }
 
 #[allow(unused)]
-fn implement_for(implementer: impl Fn(Ident, char) -> TokenStream) -> TokenStream {
+fn implement_for(implementer: impl Fn(Ident, Placeholder) -> TokenStream) -> TokenStream {
     #[allow(unused_mut)]
     let mut implementations = TokenStream::new();
 
     let span = Span::call_site();
 
     #[cfg(feature = "sqlite")]
-    implementations.append_all(implementer(Ident::new("Sqlite", span), '?'));
+    implementations.append_all(implementer(
+        Ident::new("Sqlite", span),
+        Placeholder::Numbered('?'),
+    ));
 
     #[cfg(feature = "postgres")]
-    implementations.append_all(implementer(Ident::new("Postgres", span), '$'));
+    implementations.append_all(implementer(
+        Ident::new("Postgres", span),
+        Placeholder::Numbered('$'),
+    ));
 
     #[cfg(feature = "mysql")]
-    implementations.append_all(implementer(Ident::new("MySql", span), '?'));
+    implementations.append_all(implementer(
+        Ident::new("MySql", span),
+        Placeholder::Positional,
+    ));
 
     implementations
 }
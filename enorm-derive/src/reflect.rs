@@ -1,7 +1,10 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 
-use crate::{component::StructComponent, field::Field};
+use crate::{
+    component::{EnumComponent, StructComponent},
+    field::Field,
+};
 
 pub fn reflect_component(component: &StructComponent) -> TokenStream {
     let component_name = &component.typename;
@@ -45,3 +48,99 @@ pub fn reflect_component(component: &StructComponent) -> TokenStream {
         }
     }
 }
+
+pub fn reflect_enum_component(component: &EnumComponent) -> TokenStream {
+    let component_name = &component.typename;
+
+    let reflection_name = Ident::new(&format!("Reflected{component_name}"), component_name.span());
+    let variant_name = component.variant_typename();
+
+    let variants: Vec<_> = component
+        .variants
+        .iter()
+        .map(|variant| &variant.name)
+        .collect();
+
+    let tags = variants.iter().map(ToString::to_string);
+
+    // Variants can share columns, but they should only be reflected once.
+    let mut fields = component.fields();
+    let mut seen = std::collections::HashSet::new();
+    fields.retain(|field| seen.insert(field.column_name().to_string()));
+
+    // Columns of other variants are null, so every column is optional.
+    let declarations = fields.iter().map(|field| {
+        let name = field.field_name();
+        let typename = field.intermediate().unwrap_or(field.typename());
+
+        quote! {
+            pub #name: ::enorm::reflect::ReflectedColumn<Option<#typename>>
+        }
+    });
+
+    let constructors = fields.iter().map(|field| {
+        let name = field.field_name();
+        let stringified = format!(
+            "__cte_{table_name}__{column}",
+            table_name = component.table_name,
+            column = field.column_name()
+        );
+
+        quote! {
+            #[allow(unused)]
+            #name: ::enorm::reflect::ReflectedColumn::new(#stringified)
+        }
+    });
+
+    let variant_doc = format!(
+        "Variants of [`{component_name}`], for filtering on the variant of stored components."
+    );
+
+    let tag_column = format!(
+        "__cte_{table_name}____enorm_tag",
+        table_name = component.table_name
+    );
+
+    quote! {
+        #[doc = #variant_doc]
+        #[allow(unused)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum #variant_name {
+            #(#variants,)*
+        }
+
+        impl #variant_name {
+            #[allow(unused)]
+            pub const fn as_str(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #tags,)*
+                }
+            }
+        }
+
+        #[allow(unused)]
+        pub struct #reflection_name {
+            #(#declarations),*
+        }
+
+        impl #reflection_name {
+            #[allow(unused)]
+            pub const fn new() -> Self {
+                Self {
+                    #(#constructors,)*
+                }
+            }
+
+            /// Column holding the variant of the component.
+            #[allow(unused)]
+            pub const fn variant(&self) -> ::enorm::reflect::ReflectedColumn<#variant_name> {
+                ::enorm::reflect::ReflectedColumn::new(#tag_column)
+            }
+        }
+
+        impl ::enorm::reflect::Reflect for #component_name {
+            type ReflectionType = #reflection_name;
+            const FIELDS: Self::ReflectionType = #reflection_name::new();
+        }
+    }
+}
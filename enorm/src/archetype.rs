@@ -1,34 +1,107 @@
-use std::future::Future;
+use std::{fmt::Write, future::Future};
 
 use sqlx::{ColumnIndex, Database, Executor, IntoArguments, Pool};
 
 use crate::{
-    entity::EntityPrefixedQuery,
+    bulk::{BulkInsert, DatabaseBulkInsert},
+    cte::CommonTableExpression,
+    entity::{EntityPrefixedQuery, RowsAffected},
+    error::Error,
     serialization::{Deserializeable, Serializable},
     tables::Removable,
 };
 
 pub trait DatabasePlaceholder {
     const PLACEHOLDER: char = '?';
+
+    /// Maximum number of parameters which can be bound to a single statement.
+    const MAX_PARAMETERS: usize = 65535;
+
+    /// Write the placeholder for the parameter at (1-based) `index`.
+    fn placeholder(f: &mut dyn Write, index: usize) -> std::fmt::Result {
+        write!(f, "{}{}", Self::PLACEHOLDER, index)
+    }
 }
 
 #[cfg(feature = "sqlite")]
-impl DatabasePlaceholder for sqlx::Sqlite {}
+impl DatabasePlaceholder for sqlx::Sqlite {
+    const MAX_PARAMETERS: usize = 32766;
+}
 
+/// MySQL only supports anonymous placeholders, bound in order of appearance.
 #[cfg(feature = "mysql")]
-impl DatabasePlaceholder for sqlx::MySql {}
+impl DatabasePlaceholder for sqlx::MySql {
+    fn placeholder(f: &mut dyn Write, _: usize) -> std::fmt::Result {
+        write!(f, "{}", Self::PLACEHOLDER)
+    }
+}
 
 #[cfg(feature = "postgres")]
 impl DatabasePlaceholder for sqlx::Postgres {
     const PLACEHOLDER: char = '$';
 }
 
+/// Hands out numbered placeholders in the order parameters are bound to a query.
+///
+/// Conditions and common table expressions that bind parameters must write
+/// their placeholders through this, so the numbering stays consistent no
+/// matter how many parameters precede them.
+pub struct Placeholders {
+    count: usize,
+    format: fn(&mut dyn Write, usize) -> std::fmt::Result,
+}
+
+impl Placeholders {
+    pub fn new<DB: DatabasePlaceholder>() -> Self {
+        Placeholders {
+            count: 0,
+            format: <DB as DatabasePlaceholder>::placeholder,
+        }
+    }
+
+    /// Write the placeholder of the next parameter.
+    pub fn next(&mut self, f: &mut dyn Write) -> std::fmt::Result {
+        self.count += 1;
+        (self.format)(f, self.count)
+    }
+
+    /// Number of placeholders written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Table of a component written by an [`Archetype`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentTable {
+    pub name: &'static str,
+    /// Whether removing the component leaves a tombstone, see
+    /// [`SoftDeleteComponent`](crate::soft_delete::SoftDeleteComponent).
+    pub soft_delete: bool,
+}
+
+/// Tables of the components read by the expression.
+pub(crate) fn component_tables(cte: &dyn CommonTableExpression) -> Vec<ComponentTable> {
+    let mut tables = Vec::new();
+    cte.component_tables(&mut tables);
+    tables
+}
+
 pub trait Archetype<DB: Database>: Deserializeable<DB> + Sized {
+    /// Tables of the components written by inserting, updating or upserting
+    /// `self`, used to report [changes](crate::changes::Change).
+    ///
+    /// Defaults to the tables read by [`Deserializeable::cte`], which includes
+    /// optional components regardless of whether they are present.
+    fn component_tables(&self) -> Vec<ComponentTable> {
+        component_tables(<Self as Deserializeable<DB>>::cte().as_ref())
+    }
+
     fn insert<'query, EntityId>(
         &'query self,
         pool: &'query Pool<DB>,
         entity: EntityId,
-    ) -> impl Future<Output = ()> + Send + 'query
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
     where
         Self: Serializable<DB> + Send,
         for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
@@ -37,51 +110,178 @@ pub trait Archetype<DB: Database>: Deserializeable<DB> + Sized {
             Executor<'connection, Database = DB>,
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
     {
+        // The queries are built before beginning the transaction, so the
+        // future doesn't need to hold on to `self`, as with `insert_in`.
         let mut inserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
 
-        <Self as Serializable<DB>>::insert(&self, &mut inserts);
+        <Self as Serializable<DB>>::insert(self, &mut inserts);
 
         async move {
-            let mut tx = pool.begin().await.unwrap();
-            for query in inserts.queries {
-                query.execute(&mut *tx).await.unwrap();
-            }
+            let mut tx = pool.begin().await?;
+            inserts.execute(&mut tx).await?;
+            tx.commit().await?;
+            Ok(())
+        }
+    }
 
-            tx.commit().await.unwrap();
+    /// Insert the components using an existing connection or transaction.
+    fn insert_in<'query, EntityId>(
+        &'query self,
+        connection: &'query mut <DB as Database>::Connection,
+        entity: EntityId,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Serializable<DB> + Send,
+        for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+    {
+        let mut inserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
+
+        <Self as Serializable<DB>>::insert(self, &mut inserts);
+
+        inserts.execute(connection)
+    }
+
+    /// Insert the components of many entities within a single transaction.
+    fn insert_many<'query, EntityId>(
+        rows: &[(&'query EntityId, &'query Self)],
+        pool: &'query Pool<DB>,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Serializable<DB> + Send,
+        DB: DatabaseBulkInsert,
+        for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
+            IntoArguments<'connection, DB> + Send,
+        for<'connection> &'connection mut <DB as sqlx::Database>::Connection:
+            Executor<'connection, Database = DB>,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Send + 'query,
+    {
+        let mut inserts = BulkInsert::new();
+
+        <Self as Serializable<DB>>::insert_many(rows, &mut inserts);
+
+        async move {
+            let mut tx = pool.begin().await?;
+            inserts.execute(&mut tx).await?;
+            tx.commit().await?;
+            Ok(())
         }
     }
 
+    /// Insert the components of many entities using an existing connection or transaction.
+    fn insert_many_in<'query, EntityId>(
+        rows: &[(&'query EntityId, &'query Self)],
+        connection: &'query mut <DB as Database>::Connection,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Serializable<DB> + Send,
+        DB: DatabaseBulkInsert,
+        for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Send + 'query,
+    {
+        let mut inserts = BulkInsert::new();
+
+        <Self as Serializable<DB>>::insert_many(rows, &mut inserts);
+
+        inserts.execute(connection)
+    }
+
     fn update<'query, EntityId>(
         &'query self,
         pool: &'query Pool<DB>,
         entity: EntityId,
-    ) -> impl Future<Output = ()> + Send + 'query
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
     where
         Self: Serializable<DB> + Send,
         for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
             IntoArguments<'connection, DB> + Send,
         for<'connection> &'connection mut <DB as sqlx::Database>::Connection:
             Executor<'connection, Database = DB>,
+        <DB as Database>::QueryResult: RowsAffected,
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
     {
-        let mut inserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
+        let mut updates = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
 
-        <Self as Serializable<DB>>::update(&self, &mut inserts);
+        <Self as Serializable<DB>>::update(self, &mut updates);
 
         async move {
-            let mut tx = pool.begin().await.unwrap();
-            for query in inserts.queries {
-                query.execute(&mut *tx).await.unwrap();
-            }
+            let mut tx = pool.begin().await?;
+            updates.execute_guarded(&mut tx).await?;
+            tx.commit().await?;
+            Ok(())
+        }
+    }
+
+    /// Update the components using an existing connection or transaction.
+    fn update_in<'query, EntityId>(
+        &'query self,
+        connection: &'query mut <DB as Database>::Connection,
+        entity: EntityId,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Serializable<DB> + Send,
+        for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+        <DB as Database>::QueryResult: RowsAffected,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+    {
+        let mut updates = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
+
+        <Self as Serializable<DB>>::update(self, &mut updates);
 
-            tx.commit().await.unwrap();
+        updates.execute_guarded(connection)
+    }
+
+    fn upsert<'query, EntityId>(
+        &'query self,
+        pool: &'query Pool<DB>,
+        entity: EntityId,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Serializable<DB> + Send,
+        for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
+            IntoArguments<'connection, DB> + Send,
+        for<'connection> &'connection mut <DB as sqlx::Database>::Connection:
+            Executor<'connection, Database = DB>,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+    {
+        let mut upserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
+
+        <Self as Serializable<DB>>::upsert(self, &mut upserts);
+
+        async move {
+            let mut tx = pool.begin().await?;
+            upserts.execute(&mut tx).await?;
+            tx.commit().await?;
+            Ok(())
         }
     }
 
+    /// Upsert the components using an existing connection or transaction.
+    fn upsert_in<'query, EntityId>(
+        &'query self,
+        connection: &'query mut <DB as Database>::Connection,
+        entity: EntityId,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Serializable<DB> + Send,
+        for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+    {
+        let mut upserts = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
+
+        <Self as Serializable<DB>>::upsert(self, &mut upserts);
+
+        upserts.execute(connection)
+    }
+
     fn remove<'query, EntityId>(
         pool: &'query Pool<DB>,
         entity: EntityId,
-    ) -> impl Future<Output = ()> + Send + 'query
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
     where
         Self: Removable<DB> + Send,
         for<'connection> <DB as sqlx::Database>::Arguments<'connection>:
@@ -95,21 +295,44 @@ pub trait Archetype<DB: Database>: Deserializeable<DB> + Sized {
         <Self as Removable<DB>>::remove(&mut removes);
 
         async move {
-            let mut tx = pool.begin().await.unwrap();
-            for query in removes.queries {
-                query.execute(&mut *tx).await.unwrap();
-            }
-
-            tx.commit().await.unwrap();
+            let mut tx = pool.begin().await?;
+            removes.execute(&mut tx).await?;
+            tx.commit().await?;
+            Ok(())
         }
     }
+
+    /// Remove the components using an existing connection or transaction.
+    fn remove_in<'query, EntityId>(
+        connection: &'query mut <DB as Database>::Connection,
+        entity: EntityId,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'query
+    where
+        Self: Removable<DB> + Send,
+        for<'c> <DB as sqlx::Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + Send + 'query,
+    {
+        let mut removes = EntityPrefixedQuery::<'_, DB, EntityId>::new(entity);
+
+        <Self as Removable<DB>>::remove(&mut removes);
+
+        removes.execute(connection)
+    }
 }
 
+/// Optional components are only written if present.
 impl<T, DB: Database> Archetype<DB> for Option<T>
 where
     T: Archetype<DB>,
     usize: ColumnIndex<<DB as Database>::Row>,
 {
+    fn component_tables(&self) -> Vec<ComponentTable> {
+        match self {
+            Some(inner) => inner.component_tables(),
+            None => Vec::new(),
+        }
+    }
 }
 
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
@@ -119,7 +342,11 @@ macro_rules! impl_compound_for_db{
         where
             $($list: Archetype<$db>,)*
         {
-
+            fn component_tables(&self) -> Vec<ComponentTable> {
+                let mut tables = Vec::new();
+                $(tables.extend(self.$index.component_tables());)*
+                tables
+            }
         }
     };
 }
@@ -1,21 +1,39 @@
-use std::{future::Future, marker::PhantomData};
+use std::{fmt::Write as _, future::Future, marker::PhantomData, sync::Arc, time::SystemTime};
 
 use async_stream::stream;
 use futures::Stream;
-use sqlx::{ColumnIndex, Database, Executor, IntoArguments, Pool};
+use sqlx::{query::QueryAs, ColumnIndex, Database, Executor, IntoArguments, Pool};
 
 #[cfg(feature = "uuid")]
 use uuid::Uuid;
 
 use crate::{
-    archetype::Archetype,
+    archetype::{Archetype, DatabasePlaceholder, Placeholders},
+    changes::{Broadcast, Change, ChangeKind, DatabaseChanges},
+    component::DatabaseColumnType,
     condition::{All, Condition},
-    cte::{Filter, With, Without},
+    cte::{
+        CommonTableExpression, Filter, JoinRef, Merge, Nearest, Recursive, Single, Traverse, With,
+        Without, ENTITY_COLUMN,
+    },
+    dynamic::{DynamicComponent, DynamicRow, Value},
+    entity::{EntityPrefixedQuery, RowsAffected},
+    error::Error,
+    history,
+    migration::Migration,
+    patch::{Assignments, Patch},
     prelude::{Component, Deserializeable, Serializable},
-    row::Entity,
+    reflect::ReflectedColumn,
+    row::{Entity, Referencing, Traversed},
+    schema::Schema,
+    soft_delete::{SoftDeleteComponent, DELETED_AT_COLUMN},
     tables::Removable,
+    transaction::Transaction,
 };
 
+#[cfg(any(feature = "sqlite", feature = "mysql"))]
+mod reported;
+
 #[cfg(feature = "sqlite")]
 mod sqlite;
 #[cfg(feature = "sqlite")]
@@ -51,21 +69,22 @@ where
         + Send
         + 'static,
 {
-    fn register<T>(
-        &self,
-    ) -> impl Future<Output = Result<<DB as Database>::QueryResult, sqlx::Error>>
+    fn register<T>(&self) -> impl Future<Output = Result<<DB as Database>::QueryResult, Error>>
     where
         T: Component<DB>;
 
-    fn spawn<'a, T>(&'a self, components: &'a T) -> impl Future<Output = EntityId> + 'a
+    fn spawn<'a, T>(
+        &'a self,
+        components: &'a T,
+    ) -> impl Future<Output = Result<EntityId, Error>> + 'a
     where
         EntityId: GenerateNew,
         T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
     {
         async move {
             let entity = EntityId::generate_new();
-            self.insert(&entity, components).await;
-            entity
+            self.insert(&entity, components).await?;
+            Ok(entity)
         }
     }
 
@@ -73,32 +92,418 @@ where
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
         T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
 
+    /// Insert the components of many entities at once, within a single transaction.
+    ///
+    /// Rows are grouped by component table and inserted using as few statements
+    /// as possible.
+    fn insert_many<'a, T>(
+        &'a self,
+        entities: &'a [(EntityId, T)],
+    ) -> impl Future<Output = Result<(), Error>> + 'a
+    where
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
+
+    /// Spawn an entity for each of the given components, returning their Entity IDs.
+    ///
+    /// See [`Backend::insert_many`].
+    fn spawn_many<'a, T>(
+        &'a self,
+        components: &'a [T],
+    ) -> impl Future<Output = Result<Vec<EntityId>, Error>> + 'a
+    where
+        EntityId: GenerateNew,
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
+
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), Error>> + 'a
     where
         T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    /// Insert the components, replacing any which the entity already has.
+    fn upsert<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        components: &'a T,
+    ) -> impl Future<Output = Result<(), Error>> + 'a
+    where
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static;
+
+    fn remove<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<(), Error>> + 'a
     where
         T: Archetype<DB> + Removable<DB> + Unpin + Send + 'static;
 
+    /// Update individual fields of the component `T`, leaving its other
+    /// columns unchanged.
+    ///
+    /// ```ignore
+    /// backend
+    ///     .patch::<Player>(&entity)
+    ///     .set(Player::FIELDS.level, 3)
+    ///     .increment(Player::FIELDS.score, 10)
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    fn patch<'a, T>(&'a self, entity: &'a EntityId) -> Patch<'a, DB, EntityId, T>
+    where
+        T: Component<DB>;
+
+    /// Remove every component of the entity, within a single transaction.
+    ///
+    /// Only components whose tables were created using [`Backend::register`] or
+    /// [`Backend::register_schema`] are removed. Returns the tables of the removed components.
+    fn despawn<'a>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<Vec<&'static str>, Error>> + 'a;
+
+    /// Permanently delete all tombstoned components `T`, returning how many were deleted.
+    ///
+    /// See [`SoftDeleteComponent`].
+    fn purge<T>(&self) -> impl Future<Output = Result<u64, Error>>
+    where
+        T: SoftDeleteComponent<DB>;
+
+    /// Tables of the registered components which the entity has.
+    ///
+    /// Only components whose tables were created using [`Backend::register`] or
+    /// [`Backend::register_schema`] are considered.
+    fn components_of<'a>(
+        &'a self,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<Vec<&'static str>, Error>> + 'a;
+
+    /// Determine whether the entity has all of the components `T`, without
+    /// fetching them.
+    fn has<T>(&self, entity: &EntityId) -> impl Future<Output = Result<bool, Error>>
+    where
+        T: Deserializeable<DB> + Unpin + Send + 'static;
+
+    /// Runtime description of the registered component stored in `table`.
+    fn component(&self, table: &str) -> Option<DynamicComponent<DB>>;
+
+    /// Fetch the columns of a dynamic component of the entity.
+    fn get_dynamic<'a>(
+        &'a self,
+        component: &'a DynamicComponent<DB>,
+        entity: &'a EntityId,
+    ) -> impl Future<Output = Result<DynamicRow, Error>> + 'a;
+
+    /// Fetch the columns of a dynamic component for all entities which have it.
+    fn list_dynamic<'a>(
+        &'a self,
+        component: &'a DynamicComponent<DB>,
+    ) -> impl Future<Output = Result<Vec<(EntityId, DynamicRow)>, Error>> + 'a;
+
+    /// Insert a dynamic component for the entity.
+    ///
+    /// Columns which are left out are set to their default, if any.
+    fn insert_dynamic<'a>(
+        &'a self,
+        component: &'a DynamicComponent<DB>,
+        entity: &'a EntityId,
+        row: &'a [(&'a str, Value)],
+    ) -> impl Future<Output = Result<(), Error>> + 'a;
+
+    /// Update the given columns of a dynamic component of the entity.
+    ///
+    /// Updates of versioned components must include the
+    /// [`VERSION_COLUMN`](crate::versioned::VERSION_COLUMN) they were read at,
+    /// and fail with [`Error::Conflict`] if it changed since.
+    fn update_dynamic<'a>(
+        &'a self,
+        component: &'a DynamicComponent<DB>,
+        entity: &'a EntityId,
+        row: &'a [(&'a str, Value)],
+    ) -> impl Future<Output = Result<(), Error>> + 'a;
+
+    /// Subscribe to the components inserted, updated or removed from now on.
+    ///
+    /// Postgres reports changes made by any client, using triggers created when
+    /// the components are registered. Other backends only report the changes
+    /// made through themselves, including those made by committed transactions.
+    /// Their streams end with [`Error::Lagged`] once they fall behind by more
+    /// than [`CHANGES_BUFFER`](crate::changes::CHANGES_BUFFER) changes.
+    fn changes(
+        &self,
+    ) -> impl Future<
+        Output = Result<impl Stream<Item = Result<Change<EntityId>, Error>> + Send + '_, Error>,
+    >
+    where
+        DB: DatabaseChanges<EntityId>;
+
     /// Query the backend for entities.
     ///
     /// See [`List`] for options allowng further filtering and processing.
     fn list<T>(&self) -> List<DB, EntityId, T, (), All>;
 
-    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
+    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, Error>>
+    where
+        T: Deserializeable<DB> + Unpin + Send + 'static;
+
+    /// Get the components of the entity as they were at the given time.
+    ///
+    /// Components declared using `#[enorm(history)]` are reconstructed from
+    /// their history tables, as of the last change recorded at or before `at`.
+    /// Components which had been removed by then, or not yet inserted, are
+    /// missing, while components without a history are read as they are now.
+    ///
+    /// ```ignore
+    /// let before = SystemTime::now();
+    /// backend.update(&entity, &Player { score: 10 }).await?;
+    ///
+    /// let player: Player = backend.get_at(&entity, before).await?;
+    /// ```
+    fn get_at<T>(
+        &self,
+        entity: &EntityId,
+        at: SystemTime,
+    ) -> impl Future<Output = Result<T, Error>>
     where
         T: Deserializeable<DB> + Unpin + Send + 'static;
+
+    /// Query the backend for entities as they were at the given time,
+    /// see [`Backend::get_at`] and [`List::as_of`].
+    fn list_at<T>(&self, at: SystemTime) -> List<DB, EntityId, T, (), All> {
+        self.list::<T>().as_of(at)
+    }
+
+    /// Create the tables of all components in the schema within a single transaction.
+    fn register_schema<'a>(
+        &'a self,
+        schema: &'a Schema<DB, EntityId>,
+    ) -> impl Future<Output = Result<(), Error>> + 'a;
+
+    /// Names of the tables in the schema which don't exist.
+    ///
+    /// Useful for verifying at startup that all components have been registered.
+    fn missing_tables<'a>(
+        &'a self,
+        schema: &'a Schema<DB, EntityId>,
+    ) -> impl Future<Output = Result<Vec<&'static str>, Error>> + 'a;
+
+    /// Compare the columns of the component `T` with those of its table.
+    ///
+    /// See [`Migration`] for details.
+    fn migration<T>(&self) -> impl Future<Output = Result<Migration<DB>, Error>>
+    where
+        T: Component<DB>;
+
+    /// Apply the migration, returning the new schema version of the table.
+    fn migrate<'a>(
+        &'a self,
+        migration: &'a Migration<DB>,
+    ) -> impl Future<Output = Result<i64, Error>> + 'a;
+
+    /// Schema version recorded by [`Backend::migrate`] for the table of the component `T`.
+    fn schema_version<T>(&self) -> impl Future<Output = Result<Option<i64>, Error>>
+    where
+        T: Component<DB>;
+
+    /// Begin a transaction, allowing several operations to be applied atomically.
+    ///
+    /// See [`Transaction`] for details.
+    fn transaction(
+        &self,
+    ) -> impl Future<Output = Result<Transaction<'static, DB, EntityId>, Error>>;
+}
+
+/// Connection or pool which a [`List`] executes its query against.
+pub trait Source<DB: Database> {
+    fn executor(&mut self) -> impl Executor<'_, Database = DB>;
+
+    /// Begin a transaction, or a savepoint if the source is a transaction itself.
+    fn begin(&mut self) -> impl Future<Output = Result<sqlx::Transaction<'_, DB>, Error>> + Send;
+}
+
+impl<DB: Database> Source<DB> for Pool<DB>
+where
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+{
+    fn executor(&mut self) -> impl Executor<'_, Database = DB> {
+        &*self
+    }
+
+    async fn begin(&mut self) -> Result<sqlx::Transaction<'_, DB>, Error> {
+        Ok(Pool::begin(self).await?)
+    }
+}
+
+impl<DB: Database> Source<DB> for &mut sqlx::Transaction<'_, DB>
+where
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+{
+    fn executor(&mut self) -> impl Executor<'_, Database = DB> {
+        &mut ***self
+    }
+
+    async fn begin(&mut self) -> Result<sqlx::Transaction<'_, DB>, Error> {
+        Ok(sqlx::Transaction::begin(&mut ***self).await?)
+    }
+}
+
+/// Reports the changes made by writes through a [`List`] which the source
+/// is responsible for, such as those made within a [`Transaction`].
+pub trait Report<EntityId> {
+    /// Whether anyone is interested in the changes.
+    fn subscribed(&self) -> bool {
+        false
+    }
+
+    /// Report changes which have been written successfully.
+    fn report(&mut self, changes: Vec<Change<EntityId>>) {
+        let _ = changes;
+    }
+}
+
+impl<DB: Database, EntityId> Report<EntityId> for Pool<DB> {}
+
+impl<DB: Database, EntityId> Report<EntityId> for &mut sqlx::Transaction<'_, DB> {}
+
+/// Fetch the components described by `cte` for a single entity.
+pub(crate) async fn fetch_single<'e, DB, EntityId, T, E>(
+    executor: E,
+    entity: &EntityId,
+    cte: Box<dyn CommonTableExpression>,
+) -> Result<T, Error>
+where
+    DB: Database + DatabasePlaceholder,
+    E: Executor<'e, Database = DB>,
+    T: Deserializeable<DB> + Unpin + Send,
+    for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+    for<'q> EntityId: sqlx::Encode<'q, DB> + sqlx::Decode<'q, DB> + sqlx::Type<DB> + Unpin + Send,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+    let mut placeholders = Placeholders::new::<DB>();
+    let sql = crate::cte::serialize(
+        &Single {
+            inner: [cte],
+            entity,
+        },
+        &mut placeholders,
+    )
+    .unwrap();
+
+    // Every placeholder in a single-entity query refers to the entity itself.
+    let mut query = sqlx::query_as(&sql);
+    for _ in 0..placeholders.count() {
+        query = query.bind(entity);
+    }
+
+    let result: Entity<EntityId, T> = query.fetch_one(executor).await?;
+
+    Ok(result.into_components())
+}
+
+/// Determine whether a single entity has the components described by `cte`,
+/// without fetching them.
+pub(crate) async fn has_single<'e, DB, EntityId, E>(
+    executor: E,
+    entity: &EntityId,
+    cte: Box<dyn CommonTableExpression>,
+) -> Result<bool, Error>
+where
+    DB: Database + DatabasePlaceholder,
+    E: Executor<'e, Database = DB>,
+    for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+    for<'r> i64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'q> EntityId: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+    let mut placeholders = Placeholders::new::<DB>();
+    let statement = crate::cte::serialize(
+        &Single {
+            inner: [cte],
+            entity,
+        },
+        &mut placeholders,
+    )
+    .unwrap();
+
+    let sql = format!("select count(*) from (\n{statement}\n) as __enorm_has");
+
+    let mut query = sqlx::query_scalar::<_, i64>(&sql);
+    for _ in 0..placeholders.count() {
+        query = query.bind(entity);
+    }
+
+    Ok(query.fetch_one(executor).await? > 0)
+}
+
+/// Direction in which [`List::order_by`] sorts results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// Ordering and paging options of a [`List`], along with the set of
+/// entities it is restricted to.
+pub(crate) struct Pagination<EntityId> {
+    entities: Option<Vec<EntityId>>,
+    order: Vec<(&'static str, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    after: Option<EntityId>,
+}
+
+impl<EntityId> Default for Pagination<EntityId> {
+    fn default() -> Self {
+        Pagination {
+            entities: None,
+            order: Vec::new(),
+            limit: None,
+            offset: None,
+            after: None,
+        }
+    }
+}
+
+impl<EntityId> Pagination<EntityId> {
+    fn is_paged(&self) -> bool {
+        !self.order.is_empty()
+            || self.limit.is_some()
+            || self.offset.is_some()
+            || self.after.is_some()
+    }
+
+    /// Paging after an entity relies on results being ordered by Entity ID only.
+    fn validate(&self) -> Result<(), Error> {
+        if self.after.is_some() && !self.order.is_empty() {
+            return Err(Error::InvalidQuery(
+                "paging after an entity can't be combined with ordering by a column",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Column whose reference is followed by [`List::join_ref`], and the
+/// expression of the referenced components.
+pub(crate) type Join = (&'static str, fn() -> Box<dyn CommonTableExpression>);
+
+/// Traversal of entity references performed by [`List::descendants`] and [`List::ancestors`].
+pub(crate) struct Traversal<EntityId> {
+    table: &'static str,
+    /// The column holding the references, unless it belongs to another component.
+    column: Option<&'static str>,
+    direction: Traverse,
+    root: EntityId,
+    max_depth: Option<u32>,
+    soft_delete: bool,
+    history: bool,
 }
 
 /// Configurable query for entities.
@@ -110,16 +515,25 @@ pub struct List<
     C = All,
     Out = Entity<EntityId, T>,
     Map = fn(Entity<EntityId, T>) -> Out,
+    S = Pool<DB>,
 > where
     DB: Database,
 {
-    pool: Pool<DB>,
-    _data: PhantomData<(EntityId, T, F, Out)>,
-    map: Map,
-    condition: C,
+    pub(crate) source: S,
+    pub(crate) _data: PhantomData<(DB, EntityId, T, F, Out)>,
+    pub(crate) map: Map,
+    pub(crate) condition: C,
+    pub(crate) pagination: Pagination<EntityId>,
+    pub(crate) joins: Vec<Join>,
+    pub(crate) traversal: Option<Traversal<EntityId>>,
+    pub(crate) include_deleted: bool,
+    pub(crate) as_of: Option<i64>,
+    /// Subscribers to notify of writes through the list, unless the source reports them.
+    pub(crate) changes: Option<Arc<Broadcast<EntityId>>>,
 }
 
-impl<DB, EntityId, T, F, C, Out, Map> List<DB, EntityId, T, F, C, Out, Map>
+#[allow(clippy::type_complexity)]
+impl<DB, EntityId, T, F, C, Out, Map, S> List<DB, EntityId, T, F, C, Out, Map, S>
 where
     DB: Database,
 {
@@ -131,12 +545,18 @@ where
     /// for the state of the entity.
     pub fn with<Include: Deserializeable<DB>>(
         self,
-    ) -> List<DB, EntityId, T, (With<Include>, F), C, Out, Map> {
+    ) -> List<DB, EntityId, T, (With<Include>, F), C, Out, Map, S> {
         List {
-            pool: self.pool,
+            source: self.source,
             _data: PhantomData,
             condition: self.condition,
             map: self.map,
+            pagination: self.pagination,
+            joins: self.joins,
+            traversal: self.traversal,
+            include_deleted: self.include_deleted,
+            as_of: self.as_of,
+            changes: self.changes,
         }
     }
 
@@ -147,12 +567,18 @@ where
     /// for the state of the entity.
     pub fn without<Exclude: Deserializeable<DB>>(
         self,
-    ) -> List<DB, EntityId, T, (Without<Exclude>, F), C, Out, Map> {
+    ) -> List<DB, EntityId, T, (Without<Exclude>, F), C, Out, Map, S> {
         List {
-            pool: self.pool,
+            source: self.source,
             _data: PhantomData,
             condition: self.condition,
             map: self.map,
+            pagination: self.pagination,
+            joins: self.joins,
+            traversal: self.traversal,
+            include_deleted: self.include_deleted,
+            as_of: self.as_of,
+            changes: self.changes,
         }
     }
 
@@ -163,12 +589,18 @@ where
     pub fn filter<'q, Cond: Condition<'q, DB>>(
         self,
         condition: Cond,
-    ) -> List<DB, EntityId, T, F, Cond, Out, Map> {
+    ) -> List<DB, EntityId, T, F, Cond, Out, Map, S> {
         List {
-            pool: self.pool,
+            source: self.source,
             _data: PhantomData,
             condition,
             map: self.map,
+            pagination: self.pagination,
+            joins: self.joins,
+            traversal: self.traversal,
+            include_deleted: self.include_deleted,
+            as_of: self.as_of,
+            changes: self.changes,
         }
     }
 
@@ -176,17 +608,103 @@ where
     pub fn map<M>(
         self,
         map: fn(Entity<EntityId, T>) -> M,
-    ) -> List<DB, EntityId, T, F, C, M, fn(Entity<EntityId, T>) -> M> {
+    ) -> List<DB, EntityId, T, F, C, M, fn(Entity<EntityId, T>) -> M, S> {
         List {
-            pool: self.pool,
+            source: self.source,
             _data: PhantomData,
             condition: self.condition,
             map,
+            pagination: self.pagination,
+            joins: self.joins,
+            traversal: self.traversal,
+            include_deleted: self.include_deleted,
+            as_of: self.as_of,
+            changes: self.changes,
+        }
+    }
+
+    /// Sort the results by the given column, which must belong to one of the
+    /// components returned by the query.
+    ///
+    /// Can be called multiple times, in which case results are sorted by the
+    /// first column, then the second, and so on. Entities which compare equal
+    /// on all columns are returned in the order of their Entity IDs.
+    pub fn order_by<Column>(mut self, column: ReflectedColumn<Column>, order: Order) -> Self {
+        self.pagination.order.push((column.column_name(), order));
+        self
+    }
+
+    /// Return at most `limit` results.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.pagination.limit = Some(limit);
+        self
+    }
+
+    /// Skip the first `offset` results.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.pagination.offset = Some(offset);
+        self
+    }
+
+    /// Return only the given entities, if they match the query.
+    ///
+    /// This loads the components of a known set of entities using a single
+    /// query. Calling this again replaces the previous set of entities.
+    pub fn entities(mut self, entities: &[EntityId]) -> Self
+    where
+        EntityId: Clone,
+    {
+        self.pagination.entities = Some(entities.to_vec());
+        self
+    }
+
+    /// Return only entities whose Entity IDs come after `entity`.
+    ///
+    /// Combined with [`List::limit`], this allows paging through large numbers
+    /// of entities in the order of their Entity IDs, by passing the last ID of
+    /// the previous page, without the cost of skipping rows with [`List::offset`].
+    ///
+    /// Since entities are only compared by their IDs, this can't be combined
+    /// with [`List::order_by`], and the query fails with
+    /// [`Error::InvalidQuery`] if it is.
+    pub fn after(mut self, entity: EntityId) -> Self {
+        self.pagination.after = Some(entity);
+        self
+    }
+
+    /// Stop following references beyond the given depth, when listing
+    /// [descendants](List::descendants) or [ancestors](List::ancestors).
+    ///
+    /// Without a limit, references forming a cycle are followed until the depth
+    /// reaches the number of components holding them.
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        if let Some(traversal) = &mut self.traversal {
+            traversal.max_depth = Some(depth);
         }
+        self
+    }
+
+    /// Include tombstoned components of soft-deleted tables, which are
+    /// otherwise treated as if they had been removed.
+    ///
+    /// This applies to all components of the query, including those used
+    /// to filter it and any joined references.
+    pub fn include_deleted(mut self) -> Self {
+        self.include_deleted = true;
+        self
+    }
+
+    /// Read the components as they were at the given time, from the history
+    /// tables of components declared using `#[enorm(history)]`, see [`Backend::get_at`].
+    pub fn as_of(mut self, at: SystemTime) -> Self {
+        self.as_of = Some(history::timestamp(at));
+        self
     }
 
     /// Return only the Entity IDs of the returned entities, discarding the components.
-    pub fn ids(self) -> List<DB, EntityId, T, F, C, EntityId, fn(Entity<EntityId, T>) -> EntityId> {
+    pub fn ids(
+        self,
+    ) -> List<DB, EntityId, T, F, C, EntityId, fn(Entity<EntityId, T>) -> EntityId, S> {
         fn ids<EntityId, T>(entity: Entity<EntityId, T>) -> EntityId {
             entity.into_id()
         }
@@ -195,7 +713,7 @@ where
     }
 
     /// Return only the components themselves, discarding the Entity IDs associated with each entity.
-    pub fn components(self) -> List<DB, EntityId, T, F, C, T, fn(Entity<EntityId, T>) -> T> {
+    pub fn components(self) -> List<DB, EntityId, T, F, C, T, fn(Entity<EntityId, T>) -> T, S> {
         fn components<EntityId, T>(entity: Entity<EntityId, T>) -> T {
             entity.into_components()
         }
@@ -204,10 +722,177 @@ where
     }
 }
 
-impl<DB, EntityId, T, F, Cond, Out, Map: Fn(Entity<EntityId, T>) -> Out>
-    List<DB, EntityId, T, F, Cond, Out, Map>
+#[allow(clippy::type_complexity)]
+impl<DB, EntityId, T, F, C, S>
+    List<
+        DB,
+        EntityId,
+        T,
+        F,
+        C,
+        Entity<EntityId, T>,
+        fn(Entity<EntityId, T>) -> Entity<EntityId, T>,
+        S,
+    >
 where
     DB: Database,
+{
+    /// Follow the entity reference held by `column`, returning the components `U`
+    /// of the referenced entity along with the components of each entity.
+    ///
+    /// The column must belong to one of the components `T`. Entities are returned
+    /// regardless of whether the reference could be followed. See [`Referencing`].
+    ///
+    /// ```ignore
+    /// let children = backend
+    ///     .list::<(Name, Parent)>()
+    ///     .join_ref::<Name>(Parent::FIELDS.self_0)
+    ///     .components()
+    ///     .fetch();
+    /// ```
+    pub fn join_ref<U: Deserializeable<DB>>(
+        mut self,
+        column: ReflectedColumn<impl Sized>,
+    ) -> List<
+        DB,
+        EntityId,
+        Referencing<T, U>,
+        F,
+        C,
+        Entity<EntityId, Referencing<T, U>>,
+        fn(Entity<EntityId, Referencing<T, U>>) -> Entity<EntityId, Referencing<T, U>>,
+        S,
+    > {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
+
+        self.joins
+            .push((column.column_name(), <U as Deserializeable<DB>>::cte));
+
+        List {
+            source: self.source,
+            _data: PhantomData,
+            condition: self.condition,
+            map: identity,
+            pagination: self.pagination,
+            joins: self.joins,
+            traversal: self.traversal,
+            include_deleted: self.include_deleted,
+            as_of: self.as_of,
+            changes: self.changes,
+        }
+    }
+
+    /// Return only entities which reference `root` through the `column` of the
+    /// component `P`, either directly or through other such entities, along with
+    /// the number of references between them and `root`. See [`Traversed`].
+    ///
+    /// Entities must still have the components `T`, and match any other filters.
+    /// The query fails with [`Error::InvalidQuery`] if `column` isn't a column of `P`.
+    ///
+    /// ```ignore
+    /// let subtree = backend
+    ///     .list::<Name>()
+    ///     .descendants::<Parent>(Parent::FIELDS.self_0, root)
+    ///     .filter(depth().le(2))
+    ///     .fetch();
+    /// ```
+    pub fn descendants<P: Component<DB>>(
+        self,
+        column: ReflectedColumn<impl Sized>,
+        root: EntityId,
+    ) -> List<
+        DB,
+        EntityId,
+        Traversed<T>,
+        F,
+        C,
+        Entity<EntityId, Traversed<T>>,
+        fn(Entity<EntityId, Traversed<T>>) -> Entity<EntityId, Traversed<T>>,
+        S,
+    > {
+        self.traverse::<P>(column, root, Traverse::Descendants)
+    }
+
+    /// Return only the entities referenced by `root` through the `column` of the
+    /// component `P`, the entities referenced by those in turn, and so on, along
+    /// with the number of references between them and `root`. See [`Traversed`].
+    ///
+    /// Entities must still have the components `T`, and match any other filters.
+    pub fn ancestors<P: Component<DB>>(
+        self,
+        column: ReflectedColumn<impl Sized>,
+        root: EntityId,
+    ) -> List<
+        DB,
+        EntityId,
+        Traversed<T>,
+        F,
+        C,
+        Entity<EntityId, Traversed<T>>,
+        fn(Entity<EntityId, Traversed<T>>) -> Entity<EntityId, Traversed<T>>,
+        S,
+    > {
+        self.traverse::<P>(column, root, Traverse::Ancestors)
+    }
+
+    fn traverse<P: Component<DB>>(
+        self,
+        column: ReflectedColumn<impl Sized>,
+        root: EntityId,
+        direction: Traverse,
+    ) -> List<
+        DB,
+        EntityId,
+        Traversed<T>,
+        F,
+        C,
+        Entity<EntityId, Traversed<T>>,
+        fn(Entity<EntityId, Traversed<T>>) -> Entity<EntityId, Traversed<T>>,
+        S,
+    > {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
+
+        let table = <P as Component<DB>>::table();
+
+        // Reflected columns are prefixed by the expression of their table.
+        let column = column
+            .column_name()
+            .strip_prefix("__cte_")
+            .and_then(|column| column.strip_prefix(table))
+            .and_then(|column| column.strip_prefix("__"));
+
+        List {
+            source: self.source,
+            _data: PhantomData,
+            condition: self.condition,
+            map: identity,
+            pagination: self.pagination,
+            joins: self.joins,
+            traversal: Some(Traversal {
+                table,
+                column,
+                direction,
+                root,
+                max_depth: None,
+                soft_delete: <P as Component<DB>>::SOFT_DELETE,
+                history: <P as Component<DB>>::HISTORY,
+            }),
+            include_deleted: self.include_deleted,
+            as_of: self.as_of,
+            changes: self.changes,
+        }
+    }
+}
+
+impl<DB, EntityId, T, F, Cond, Out, Map: Fn(Entity<EntityId, T>) -> Out, S>
+    List<DB, EntityId, T, F, Cond, Out, Map, S>
+where
+    DB: Database + DatabasePlaceholder,
+    S: Source<DB>,
     T: Deserializeable<DB> + Unpin + Send,
     F: Filter<DB>,
     Cond: for<'c> Condition<'c, DB>,
@@ -216,21 +901,495 @@ where
     for<'e> EntityId: sqlx::Decode<'e, DB> + sqlx::Encode<'e, DB> + sqlx::Type<DB> + Unpin + Send,
     usize: ColumnIndex<<DB as sqlx::Database>::Row>,
 {
+    /// Check that the options of the list can be combined.
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(Traversal { column: None, .. }) = &self.traversal {
+            return Err(Error::InvalidQuery(
+                "traversed column must belong to the component holding the references",
+            ));
+        }
+
+        self.pagination.validate()
+    }
+
+    /// Build the statement selecting all matching entities.
+    fn statement(&self) -> String {
+        self.serialize(&mut Placeholders::new::<DB>())
+    }
+
+    /// Write the statement selecting all matching entities, continuing the
+    /// placeholders of any enclosing statement.
+    fn serialize(&self, placeholders: &mut Placeholders) -> String {
+        let mut cte = <T as Deserializeable<DB>>::cte();
+
+        // The depth precedes the components, regardless of any joined references.
+        if let Some(traversal) = &self.traversal {
+            cte = Box::new(Merge {
+                tables: vec![
+                    Box::new(Nearest {
+                        inner: [Box::new(Recursive {
+                            table: traversal.table,
+                            column: traversal.column.unwrap_or_default(),
+                            direction: traversal.direction,
+                            max_depth: traversal.max_depth,
+                            exclude_deleted: traversal.soft_delete,
+                            history: traversal.history,
+                            as_of: None,
+                        })],
+                    }),
+                    cte,
+                ],
+            });
+        }
+
+        let mut cte = <F as Filter<DB>>::cte(cte);
+        for (column, referenced) in &self.joins {
+            cte = Box::new(JoinRef {
+                inner: [cte, referenced()],
+                column,
+            });
+        }
+
+        if self.include_deleted {
+            cte.include_deleted();
+        }
+
+        if let Some(timestamp) = self.as_of {
+            cte.as_of(timestamp);
+        }
+
+        let mut sql = crate::cte::serialize_list(cte.as_ref(), placeholders).unwrap();
+        sql.push_str(" where ");
+        self.condition.serialize(&mut sql, placeholders).unwrap();
+
+        if let Some(entities) = &self.pagination.entities {
+            sql.push_str(" and ");
+            if entities.is_empty() {
+                sql.push_str("1 = 0");
+            } else {
+                write!(sql, "{ENTITY_COLUMN} in (").unwrap();
+                for i in 0..entities.len() {
+                    if i > 0 {
+                        sql.push_str(", ");
+                    }
+                    placeholders.next(&mut sql).unwrap();
+                }
+                sql.push(')');
+            }
+        }
+
+        if self.pagination.after.is_some() {
+            write!(sql, " and {ENTITY_COLUMN} > ").unwrap();
+            placeholders.next(&mut sql).unwrap();
+        }
+
+        if self.pagination.is_paged() {
+            sql.push_str(" order by ");
+            for (column, order) in &self.pagination.order {
+                let order = match order {
+                    Order::Asc => "asc",
+                    Order::Desc => "desc",
+                };
+                write!(sql, "{column} {order}, ").unwrap();
+            }
+            write!(sql, "{ENTITY_COLUMN} asc").unwrap();
+        }
+
+        // Not all databases support offset without a limit.
+        if let Some(limit) = self
+            .pagination
+            .limit
+            .or(self.pagination.offset.map(|_| i64::MAX as u64))
+        {
+            write!(sql, " limit {limit}").unwrap();
+        }
+
+        if let Some(offset) = self.pagination.offset {
+            write!(sql, " offset {offset}").unwrap();
+        }
+
+        sql
+    }
+
+    /// Bind the parameters of the statement, in the order they appear in it.
+    fn bind<'q, O>(
+        self,
+        query: QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>,
+    ) -> (QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>, S, Map)
+    where
+        EntityId: 'q,
+        Cond: 'q,
+    {
+        let query = Self::bind_parameters(
+            query,
+            self.traversal.map(|traversal| traversal.root),
+            self.condition,
+            self.pagination.entities,
+            self.pagination.after,
+        );
+
+        (query, self.source, self.map)
+    }
+
+    /// Bind copies of the parameters of the statement, so that it can be
+    /// executed more than once.
+    fn bind_cloned<'q, O>(
+        &self,
+        query: QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>
+    where
+        EntityId: Clone + 'q,
+        Cond: Clone + 'q,
+    {
+        Self::bind_parameters(
+            query,
+            self.traversal
+                .as_ref()
+                .map(|traversal| traversal.root.clone()),
+            self.condition.clone(),
+            self.pagination.entities.clone(),
+            self.pagination.after.clone(),
+        )
+    }
+
+    fn bind_parameters<'q, O>(
+        query: QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>,
+        root: Option<EntityId>,
+        condition: Cond,
+        entities: Option<Vec<EntityId>>,
+        after: Option<EntityId>,
+    ) -> QueryAs<'q, DB, O, <DB as Database>::Arguments<'q>>
+    where
+        EntityId: 'q,
+        Cond: 'q,
+    {
+        // The root is bound within the common table expressions, ahead of the condition.
+        let query = match root {
+            Some(root) => query.bind(root),
+            None => query,
+        };
+
+        let mut query = condition.bind(query);
+
+        for entity in entities.into_iter().flatten() {
+            query = query.bind(entity);
+        }
+
+        if let Some(after) = after {
+            query = query.bind(after);
+        }
+
+        query
+    }
+
     /// Execute the query, returning a stream of results.
-    pub fn fetch(self) -> impl Stream<Item = Result<Out, sqlx::Error>> {
+    pub fn fetch(self) -> impl Stream<Item = Result<Out, Error>> {
         stream! {
-            let mut sql = crate::cte::serialize(<F as Filter<DB>>::cte(<T as Deserializeable<DB>>::cte()).as_ref()).unwrap();
-            sql.push_str(" where ");
-            self.condition.serialize(&mut sql).unwrap();
+            if let Err(err) = self.validate() {
+                yield Err(err);
+                return;
+            }
+
+            let sql = self.statement();
 
-            let query = self.condition.bind(sqlx::query_as::<DB, Entity<EntityId, T>>(&sql));
+            let (query, mut source, map) = self.bind(sqlx::query_as::<DB, Entity<EntityId, T>>(&sql));
 
-            for await result in query.fetch(&self.pool) {
+            for await result in query.fetch(source.executor()) {
                 yield match result {
-                    Ok(result) => Ok((self.map)(result)),
-                    Err(err) => Err(err)
+                    Ok(result) => Ok((map)(result)),
+                    Err(err) => Err(Error::from(err))
                 }
             }
         }
     }
+
+    /// Evaluate `expression` over all matching entities.
+    async fn aggregate<R>(self, expression: &str) -> Result<R, Error>
+    where
+        R: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+    {
+        self.validate()?;
+
+        let sql = format!(
+            "select {expression} from (\n{statement}\n) as __enorm_list",
+            statement = self.statement()
+        );
+
+        let (query, mut source, _) = self.bind(sqlx::query_as::<DB, (R,)>(&sql));
+
+        let (result,) = query.fetch_one(source.executor()).await?;
+
+        Ok(result)
+    }
+
+    /// Count the number of matching entities.
+    pub async fn count(self) -> Result<u64, Error>
+    where
+        i64: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        let count: i64 = self.aggregate("count(*)").await?;
+
+        Ok(count as u64)
+    }
+
+    /// Determine whether any entities match the query.
+    pub async fn exists(mut self) -> Result<bool, Error>
+    where
+        i64: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        // There's no need to count beyond the first match.
+        self.pagination.limit = Some(self.pagination.limit.unwrap_or(1).min(1));
+
+        Ok(self.count().await? > 0)
+    }
+
+    /// Sum of the column across all matching entities, or `None` if there are none.
+    pub async fn sum<Column>(self, column: ReflectedColumn<Column>) -> Result<Option<Column>, Error>
+    where
+        DB: DatabaseColumnType,
+        Column: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+    {
+        let mut expression = String::new();
+        <DB as DatabaseColumnType>::cast(
+            &mut expression,
+            &format!("sum({})", column.column_name()),
+            &<Column as sqlx::Type<DB>>::type_info(),
+        )
+        .unwrap();
+
+        self.aggregate(&expression).await
+    }
+
+    /// Average of the column across all matching entities, or `None` if there are none.
+    pub async fn avg<Column>(self, column: ReflectedColumn<Column>) -> Result<Option<f64>, Error>
+    where
+        DB: DatabaseColumnType,
+        f64: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        let mut expression = String::new();
+        <DB as DatabaseColumnType>::cast(
+            &mut expression,
+            &format!("avg({})", column.column_name()),
+            &<f64 as sqlx::Type<DB>>::type_info(),
+        )
+        .unwrap();
+
+        self.aggregate(&expression).await
+    }
+
+    /// Smallest value of the column across all matching entities, or `None` if there are none.
+    pub async fn min<Column>(self, column: ReflectedColumn<Column>) -> Result<Option<Column>, Error>
+    where
+        Column: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+    {
+        self.aggregate(&format!("min({})", column.column_name()))
+            .await
+    }
+
+    /// Largest value of the column across all matching entities, or `None` if there are none.
+    pub async fn max<Column>(self, column: ReflectedColumn<Column>) -> Result<Option<Column>, Error>
+    where
+        Column: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB> + Send + Unpin,
+    {
+        self.aggregate(&format!("max({})", column.column_name()))
+            .await
+    }
+
+    /// Restrict a write to the table of the component `T` to the matching entities,
+    /// leaving tombstones of soft-deleted components untouched.
+    fn matching(&self, sql: &mut String, placeholders: &mut Placeholders)
+    where
+        T: Component<DB>,
+    {
+        write!(
+            sql,
+            " where entity in (select {ENTITY_COLUMN} from (\n{list}\n) as __enorm_matches)",
+            list = self.serialize(placeholders),
+        )
+        .unwrap();
+
+        if <T as Component<DB>>::SOFT_DELETE {
+            write!(sql, " and {DELETED_AT_COLUMN} is null").unwrap();
+        }
+    }
+
+    /// Remove the component `T` from all matching entities using a single
+    /// statement, returning the number of removed components.
+    ///
+    /// Soft-deleted components are replaced by tombstones instead, as with
+    /// [`Backend::remove`]. Entities are never despawned, even if they are left
+    /// without any components.
+    ///
+    /// ```ignore
+    /// let removed = backend
+    ///     .list::<Session>()
+    ///     .filter(Session::FIELDS.expires_at.lt(now))
+    ///     .delete()
+    ///     .await?;
+    /// ```
+    pub async fn delete(self) -> Result<u64, Error>
+    where
+        T: Component<DB>,
+        S: Report<EntityId>,
+        Cond: Clone,
+        EntityId: Clone + Sync + 'static,
+        <DB as Database>::QueryResult: RowsAffected,
+    {
+        let table = <T as Component<DB>>::table();
+        let mut placeholders = Placeholders::new::<DB>();
+
+        let mut sql = if <T as Component<DB>>::SOFT_DELETE {
+            format!("update {table} set {DELETED_AT_COLUMN} = current_timestamp")
+        } else {
+            format!("delete from {table}")
+        };
+        self.matching(&mut sql, &mut placeholders);
+
+        self.write(&sql, ChangeKind::Remove, |query| query).await
+    }
+
+    /// Apply the `assignments` to the component `T` of all matching entities using
+    /// a single statement, returning the number of updated components.
+    ///
+    /// As with [`Backend::patch`], versions of versioned components are incremented
+    /// and tombstoned components are left unchanged.
+    ///
+    /// ```ignore
+    /// let updated = backend
+    ///     .list::<Player>()
+    ///     .filter(Player::FIELDS.team.eq("red".to_string()))
+    ///     .update_set(Assignments::new().increment(Player::FIELDS.score, 10))
+    ///     .await?;
+    /// ```
+    pub async fn update_set(self, assignments: Assignments<DB, T>) -> Result<u64, Error>
+    where
+        T: Component<DB>,
+        S: Report<EntityId>,
+        Cond: Clone,
+        EntityId: Clone + Sync + 'static,
+        <DB as Database>::QueryResult: RowsAffected,
+    {
+        if assignments.is_empty() {
+            return Ok(0);
+        }
+
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut sql = String::new();
+        assignments.serialize(&mut sql, &mut placeholders)?;
+        self.matching(&mut sql, &mut placeholders);
+
+        self.write(&sql, ChangeKind::Update, |query| assignments.bind(query))
+            .await
+    }
+
+    /// Execute a write to the component `T` of all matching entities, whose
+    /// parameters precede those of the list.
+    ///
+    /// The entities are only determined ahead of the write if they are needed to
+    /// record the history of the component, or to report the changes.
+    async fn write(
+        mut self,
+        sql: &str,
+        kind: ChangeKind,
+        bind: impl for<'q> FnOnce(
+            QueryAs<'q, DB, (), <DB as Database>::Arguments<'q>>,
+        ) -> QueryAs<'q, DB, (), <DB as Database>::Arguments<'q>>,
+    ) -> Result<u64, Error>
+    where
+        T: Component<DB>,
+        S: Report<EntityId>,
+        Cond: Clone,
+        EntityId: Clone + Sync + 'static,
+        <DB as Database>::QueryResult: RowsAffected,
+    {
+        self.validate()?;
+
+        let table = <T as Component<DB>>::table();
+
+        let subscribed = match &self.changes {
+            Some(changes) => changes.subscribed(),
+            None => self.source.subscribed(),
+        };
+
+        let mut select = None;
+        if <T as Component<DB>>::HISTORY || subscribed {
+            let mut placeholders = Placeholders::new::<DB>();
+            let mut sql = format!("select entity from {table}");
+            self.matching(&mut sql, &mut placeholders);
+            select = Some(sql);
+        }
+
+        let ids = select
+            .as_deref()
+            .map(|sql| self.bind_cloned(sqlx::query_as::<DB, (EntityId,)>(sql)));
+
+        let changes = self.changes.take();
+        let (query, mut source, _) = self.bind(bind(sqlx::query_as::<DB, ()>(sql)));
+
+        let mut tx = source.begin().await?;
+
+        let entities = match ids {
+            Some(ids) => ids
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .map(|(entity,)| entity)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // Removed components are recorded before they are gone, updated ones
+        // once they have been written.
+        if kind == ChangeKind::Remove {
+            Self::record_history(&mut tx, &entities, kind).await?;
+        }
+
+        let rows = tx.execute(query).await?.rows_affected();
+
+        if kind != ChangeKind::Remove {
+            Self::record_history(&mut tx, &entities, kind).await?;
+        }
+
+        tx.commit().await?;
+
+        if subscribed {
+            let written = entities
+                .into_iter()
+                .map(|entity| Change {
+                    entity,
+                    table,
+                    kind,
+                })
+                .collect();
+
+            match changes {
+                Some(changes) => changes.send(written),
+                None => source.report(written),
+            }
+        }
+
+        Ok(rows)
+    }
+
+    async fn record_history(
+        tx: &mut sqlx::Transaction<'_, DB>,
+        entities: &[EntityId],
+        kind: ChangeKind,
+    ) -> Result<(), Error>
+    where
+        T: Component<DB>,
+        EntityId: Clone + Sync + 'static,
+    {
+        if !<T as Component<DB>>::HISTORY {
+            return Ok(());
+        }
+
+        for entity in entities {
+            let mut history = EntityPrefixedQuery::new(entity);
+            <T as Component<DB>>::record_history(&mut history, kind);
+            history.execute(&mut *tx).await?;
+        }
+
+        Ok(())
+    }
 }
@@ -1,17 +1,31 @@
-use std::{future::Future, marker::PhantomData};
+use std::{future::Future, marker::PhantomData, sync::Arc, time::SystemTime};
 
+use futures::Stream;
 use sqlx::mysql::MySqlQueryResult;
 use sqlx::{MySql, Pool};
 
+use crate::archetype::Archetype;
+use crate::changes::{Broadcast, Change};
+use crate::condition::All;
+use crate::dynamic::{DynamicComponent, DynamicRow, Value};
+use crate::error::Error;
+use crate::history;
+use crate::migration::Migration;
+use crate::patch::Patch;
 use crate::prelude::{Component, Deserializeable, Serializable};
-use crate::row::Rowed;
+use crate::registry::{RegisteredTable, Registry};
+use crate::row::Entity;
+use crate::schema::Schema;
+use crate::soft_delete::SoftDeleteComponent;
 use crate::tables::Removable;
-use crate::{archetype::Archetype, condition::All};
+use crate::transaction::Transaction;
 
-use super::{Backend, List};
+use super::{Backend, GenerateNew, List, Pagination};
 
 pub struct MySqlBackend<EntityId> {
     pool: Pool<MySql>,
+    registry: Registry<MySql>,
+    changes: Arc<Broadcast<EntityId>>,
     _entity: PhantomData<EntityId>,
 }
 
@@ -19,6 +33,8 @@ impl<EntityId> MySqlBackend<EntityId> {
     pub fn new(pool: Pool<MySql>) -> Self {
         MySqlBackend {
             pool,
+            registry: Registry::new(),
+            changes: Arc::default(),
             _entity: PhantomData,
         }
     }
@@ -29,70 +45,242 @@ where
     EntityId: for<'q> sqlx::Encode<'q, MySql>
         + for<'r> sqlx::Decode<'r, MySql>
         + sqlx::Type<MySql>
+        + Clone
         + Unpin
         + Send
         + 'static,
     for<'entity> &'entity EntityId: Send,
 {
-    fn register<T>(&self) -> impl Future<Output = Result<MySqlQueryResult, sqlx::Error>>
+    async fn register<T>(&self) -> Result<MySqlQueryResult, Error>
     where
         T: Component<MySql>,
     {
-        <T as Component<MySql>>::create_component_table::<EntityId>(&self.pool)
+        let result =
+            <T as Component<MySql>>::create_component_table::<EntityId>(&self.pool).await?;
+        self.registry.add(RegisteredTable::of::<T>());
+
+        Ok(result)
+    }
+
+    async fn register_schema(&self, schema: &Schema<MySql, EntityId>) -> Result<(), Error> {
+        schema.create(&self.pool).await?;
+
+        for table in schema.components() {
+            self.registry.add(table);
+        }
+
+        Ok(())
+    }
+
+    async fn missing_tables(
+        &self,
+        schema: &Schema<MySql, EntityId>,
+    ) -> Result<Vec<&'static str>, Error> {
+        schema.missing_tables(&self.pool).await
+    }
+
+    async fn migration<T>(&self) -> Result<Migration<MySql>, Error>
+    where
+        T: Component<MySql>,
+    {
+        Migration::plan::<T, EntityId>(&self.pool).await
+    }
+
+    async fn migrate(&self, migration: &Migration<MySql>) -> Result<i64, Error> {
+        migration.apply(&self.pool).await
+    }
+
+    async fn schema_version<T>(&self) -> Result<Option<i64>, Error>
+    where
+        T: Component<MySql>,
+    {
+        crate::migration::schema_version(&self.pool, <T as Component<MySql>>::table()).await
     }
 
     fn list<T>(&self) -> List<MySql, EntityId, T, (), All> {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
         List {
-            pool: self.pool.clone(),
+            source: self.pool.clone(),
             _data: PhantomData,
             condition: All,
+            map: identity::<EntityId, T>,
+            pagination: Pagination::default(),
+            joins: Vec::new(),
+            traversal: None,
+            include_deleted: false,
+            as_of: None,
+            changes: Some(self.changes.clone()),
         }
     }
 
-    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
+    async fn get<T>(&self, entity: &EntityId) -> Result<T, Error>
     where
         T: Deserializeable<MySql> + Unpin + Send + 'static,
     {
-        async move {
-            let sql = crate::cte::serialize(<T as Deserializeable<MySql>>::cte().as_ref()).unwrap();
+        super::fetch_single(&self.pool, entity, <T as Deserializeable<MySql>>::cte()).await
+    }
 
-            let result: Rowed<EntityId, T> = sqlx::query_as(&sql)
-                .bind(entity)
-                .fetch_one(&self.pool)
-                .await?;
+    async fn get_at<T>(&self, entity: &EntityId, at: SystemTime) -> Result<T, Error>
+    where
+        T: Deserializeable<MySql> + Unpin + Send + 'static,
+    {
+        let mut cte = <T as Deserializeable<MySql>>::cte();
+        cte.as_of(history::timestamp(at));
 
-            Ok(result.inner)
-        }
+        super::fetch_single(&self.pool, entity, cte).await
     }
 
     fn insert<'a, 'b, 'c, T>(
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
         T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
     {
-        <T as Archetype<MySql>>::insert(&components, &self.pool, entity)
+        super::reported::insert_reported(&self.pool, &self.changes, entity, components)
+    }
+
+    async fn insert_many<T>(&self, entities: &[(EntityId, T)]) -> Result<(), Error>
+    where
+        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
+    {
+        let rows: Vec<_> = entities
+            .iter()
+            .map(|(entity, components)| (entity, components))
+            .collect();
+
+        super::reported::insert_many_reported(&self.pool, &self.changes, &rows).await
+    }
+
+    async fn spawn_many<T>(&self, components: &[T]) -> Result<Vec<EntityId>, Error>
+    where
+        EntityId: GenerateNew,
+        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
+    {
+        let entities: Vec<_> = components
+            .iter()
+            .map(|_| EntityId::generate_new())
+            .collect();
+
+        let rows: Vec<_> = entities.iter().zip(components).collect();
+        super::reported::insert_many_reported(&self.pool, &self.changes, &rows).await?;
+
+        Ok(entities)
     }
 
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), Error>> + 'a
+    where
+        T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
+    {
+        super::reported::update_reported(&self.pool, &self.changes, entity, components)
+    }
+
+    fn upsert<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        components: &'a T,
+    ) -> impl Future<Output = Result<(), Error>> + 'a
     where
         T: Archetype<MySql> + Serializable<MySql> + Unpin + Send + 'static,
     {
-        <T as Archetype<MySql>>::update(&components, &self.pool, entity)
+        super::reported::upsert_reported(&self.pool, &self.changes, entity, components)
     }
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = Result<(), Error>> + 'a
     where
         T: Archetype<MySql> + Removable<MySql> + Unpin + Send + 'static,
     {
-        <T as Archetype<MySql>>::remove(&self.pool, entity)
+        super::reported::remove_reported::<MySql, EntityId, T>(&self.pool, &self.changes, entity)
+    }
+
+    fn patch<'a, T>(&'a self, entity: &'a EntityId) -> Patch<'a, MySql, EntityId, T>
+    where
+        T: Component<MySql>,
+    {
+        Patch::new(&self.pool, entity, Some(self.changes.clone()))
+    }
+
+    async fn despawn(&self, entity: &EntityId) -> Result<Vec<&'static str>, Error> {
+        super::reported::despawn_reported(&self.pool, &self.registry, &self.changes, entity).await
+    }
+
+    async fn purge<T>(&self) -> Result<u64, Error>
+    where
+        T: SoftDeleteComponent<MySql>,
+    {
+        let result = sqlx::query(<T as SoftDeleteComponent<MySql>>::PURGE)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn components_of(&self, entity: &EntityId) -> Result<Vec<&'static str>, Error> {
+        self.registry.components_of(&self.pool, entity).await
+    }
+
+    fn component(&self, table: &str) -> Option<DynamicComponent<MySql>> {
+        self.registry.component(table)
+    }
+
+    async fn get_dynamic(
+        &self,
+        component: &DynamicComponent<MySql>,
+        entity: &EntityId,
+    ) -> Result<DynamicRow, Error> {
+        component.get(&self.pool, entity).await
+    }
+
+    async fn list_dynamic(
+        &self,
+        component: &DynamicComponent<MySql>,
+    ) -> Result<Vec<(EntityId, DynamicRow)>, Error> {
+        component.list(&self.pool).await
+    }
+
+    async fn insert_dynamic(
+        &self,
+        component: &DynamicComponent<MySql>,
+        entity: &EntityId,
+        row: &[(&str, Value)],
+    ) -> Result<(), Error> {
+        super::reported::insert_dynamic_reported(&self.pool, &self.changes, component, entity, row)
+            .await
+    }
+
+    async fn update_dynamic(
+        &self,
+        component: &DynamicComponent<MySql>,
+        entity: &EntityId,
+        row: &[(&str, Value)],
+    ) -> Result<(), Error> {
+        super::reported::update_dynamic_reported(&self.pool, &self.changes, component, entity, row)
+            .await
+    }
+
+    async fn has<T>(&self, entity: &EntityId) -> Result<bool, Error>
+    where
+        T: Deserializeable<MySql> + Unpin + Send + 'static,
+    {
+        super::has_single(&self.pool, entity, <T as Deserializeable<MySql>>::cte()).await
+    }
+
+    async fn transaction(&self) -> Result<Transaction<'static, MySql, EntityId>, Error> {
+        Ok(Transaction::new(self.pool.begin().await?).with_changes(self.changes.clone()))
+    }
+
+    async fn changes(
+        &self,
+    ) -> Result<impl Stream<Item = Result<Change<EntityId>, Error>> + Send + '_, Error> {
+        Ok(self.changes.subscribe())
     }
 }
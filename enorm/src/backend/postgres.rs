@@ -1,28 +1,138 @@
-use std::{future::Future, marker::PhantomData};
+use std::{future::Future, marker::PhantomData, time::SystemTime};
 
-use sqlx::postgres::PgQueryResult;
+use async_stream::stream;
+use futures::Stream;
+use sqlx::postgres::{PgListener, PgQueryResult};
 use sqlx::{Pool, Postgres};
 
 use crate::archetype::Archetype;
+use crate::changes::{Change, ChangeKind, DatabaseChanges};
 use crate::condition::All;
+use crate::dynamic::{DynamicComponent, DynamicRow, Value};
+use crate::error::Error;
+use crate::history;
+use crate::migration::Migration;
+use crate::patch::Patch;
 use crate::prelude::{Component, Deserializeable, Serializable};
-use crate::row::Rowed;
+use crate::registry::{RegisteredTable, Registry};
+use crate::row::Entity;
+use crate::schema::Schema;
+use crate::soft_delete::{SoftDeleteComponent, DELETED_AT_COLUMN};
 use crate::tables::Removable;
+use crate::transaction::Transaction;
 
-use super::{Backend, List};
+use super::{Backend, GenerateNew, List, Pagination};
 
 pub struct PostgresBackend<EntityId> {
     pool: Pool<Postgres>,
+    registry: Registry<Postgres>,
     _entity: PhantomData<EntityId>,
 }
 
+/// Channel on which changes of component tables are published.
+const CHANGES_CHANNEL: &str = "enorm_changes";
+
+/// Function publishing the kind of change, the table and the entity of every changed row.
+///
+/// Tombstoning a soft-deleted component is published as its removal, and
+/// reviving a tombstone as its insertion, like on other databases. Writes
+/// which leave a tombstone in place, such as purging it, aren't published.
+fn notify_function() -> String {
+    format!(
+        "create or replace function enorm_notify_change() returns trigger as $$
+begin
+  if tg_op = 'DELETE' then
+    if to_jsonb(old) ->> '{DELETED_AT_COLUMN}' is null then
+      perform pg_notify('{CHANGES_CHANNEL}', 'remove ' || tg_table_name || ' ' || old.entity::text);
+    end if;
+  elsif tg_op = 'INSERT' then
+    perform pg_notify('{CHANGES_CHANNEL}', 'insert ' || tg_table_name || ' ' || new.entity::text);
+  elsif to_jsonb(new) ->> '{DELETED_AT_COLUMN}' is not null then
+    if to_jsonb(old) ->> '{DELETED_AT_COLUMN}' is null then
+      perform pg_notify('{CHANGES_CHANNEL}', 'remove ' || tg_table_name || ' ' || new.entity::text);
+    end if;
+  elsif to_jsonb(old) ->> '{DELETED_AT_COLUMN}' is not null then
+    perform pg_notify('{CHANGES_CHANNEL}', 'insert ' || tg_table_name || ' ' || new.entity::text);
+  else
+    perform pg_notify('{CHANGES_CHANNEL}', 'update ' || tg_table_name || ' ' || new.entity::text);
+  end if;
+  return null;
+end
+$$ language plpgsql"
+    )
+}
+
 impl<EntityId> PostgresBackend<EntityId> {
     pub fn new(pool: Pool<Postgres>) -> Self {
         PostgresBackend {
             pool,
+            registry: Registry::new(),
             _entity: PhantomData,
         }
     }
+
+    /// Publish changes to the table on the [`CHANGES_CHANNEL`], replacing any
+    /// trigger created previously.
+    async fn notify_changes(&self, table: &str) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(&notify_function()).execute(&mut *tx).await?;
+        sqlx::query(&format!(
+            "drop trigger if exists enorm_notify_change on {table}"
+        ))
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "create trigger enorm_notify_change after insert or update or delete on {table} \
+             for each row execute function enorm_notify_change()"
+        ))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Parse a notification published by the trigger, whose entity is
+/// published as text and parsed using [`DatabaseChanges::parse_entity`].
+///
+/// Changes of tables which aren't registered are ignored.
+fn parse_change<EntityId>(
+    registry: &Registry<Postgres>,
+    payload: &str,
+) -> Result<Option<Change<EntityId>>, Error>
+where
+    Postgres: DatabaseChanges<EntityId>,
+{
+    let mut parts = payload.splitn(3, ' ');
+    let (Some(kind), Some(table), Some(entity)) = (parts.next(), parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+
+    let kind = match kind {
+        "insert" => ChangeKind::Insert,
+        "update" => ChangeKind::Update,
+        "remove" => ChangeKind::Remove,
+        _ => return Ok(None),
+    };
+
+    let Some(table) = registry.table(table) else {
+        return Ok(None);
+    };
+
+    let entity = Postgres::parse_entity(entity).ok_or_else(|| {
+        Error::Decode(sqlx::Error::Decode(
+            format!("invalid entity in change notification: {entity}").into(),
+        ))
+    })?;
+
+    Ok(Some(Change {
+        entity,
+        table,
+        kind,
+    }))
 }
 
 impl<EntityId> Backend<Postgres, EntityId> for PostgresBackend<EntityId>
@@ -35,66 +145,273 @@ where
         + 'static,
     for<'entity> &'entity EntityId: Send,
 {
-    fn register<T>(&self) -> impl Future<Output = Result<PgQueryResult, sqlx::Error>>
+    async fn register<T>(&self) -> Result<PgQueryResult, Error>
+    where
+        T: Component<Postgres>,
+    {
+        let result =
+            <T as Component<Postgres>>::create_component_table::<EntityId>(&self.pool).await?;
+        self.notify_changes(<T as Component<Postgres>>::table())
+            .await?;
+        self.registry.add(RegisteredTable::of::<T>());
+
+        Ok(result)
+    }
+
+    async fn register_schema(&self, schema: &Schema<Postgres, EntityId>) -> Result<(), Error> {
+        schema.create(&self.pool).await?;
+
+        for table in schema.components() {
+            self.notify_changes(table.name()).await?;
+            self.registry.add(table);
+        }
+
+        Ok(())
+    }
+
+    async fn missing_tables(
+        &self,
+        schema: &Schema<Postgres, EntityId>,
+    ) -> Result<Vec<&'static str>, Error> {
+        schema.missing_tables(&self.pool).await
+    }
+
+    async fn migration<T>(&self) -> Result<Migration<Postgres>, Error>
     where
         T: Component<Postgres>,
     {
-        <T as Component<Postgres>>::create_component_table::<EntityId>(&self.pool)
+        Migration::plan::<T, EntityId>(&self.pool).await
+    }
+
+    async fn migrate(&self, migration: &Migration<Postgres>) -> Result<i64, Error> {
+        migration.apply(&self.pool).await
+    }
+
+    async fn schema_version<T>(&self) -> Result<Option<i64>, Error>
+    where
+        T: Component<Postgres>,
+    {
+        crate::migration::schema_version(&self.pool, <T as Component<Postgres>>::table()).await
     }
 
     fn list<T>(&self) -> List<Postgres, EntityId, T, (), All> {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
         List {
-            pool: self.pool.clone(),
+            source: self.pool.clone(),
             _data: PhantomData,
             condition: All,
+            map: identity::<EntityId, T>,
+            pagination: Pagination::default(),
+            joins: Vec::new(),
+            traversal: None,
+            include_deleted: false,
+            as_of: None,
+            // Changes are reported by the triggers of the table.
+            changes: None,
         }
     }
 
-    fn get<T>(&self, entity: &EntityId) -> impl Future<Output = Result<T, sqlx::Error>>
+    async fn get<T>(&self, entity: &EntityId) -> Result<T, Error>
     where
         T: Deserializeable<Postgres> + Unpin + Send + 'static,
     {
-        async move {
-            let sql =
-                crate::cte::serialize(<T as Deserializeable<Postgres>>::cte().as_ref()).unwrap();
+        super::fetch_single(&self.pool, entity, <T as Deserializeable<Postgres>>::cte()).await
+    }
 
-            let result: Rowed<EntityId, T> = sqlx::query_as(&sql)
-                .bind(entity)
-                .fetch_one(&self.pool)
-                .await?;
+    async fn get_at<T>(&self, entity: &EntityId, at: SystemTime) -> Result<T, Error>
+    where
+        T: Deserializeable<Postgres> + Unpin + Send + 'static,
+    {
+        let mut cte = <T as Deserializeable<Postgres>>::cte();
+        cte.as_of(history::timestamp(at));
 
-            Ok(result.inner)
-        }
+        super::fetch_single(&self.pool, entity, cte).await
     }
 
     fn insert<'a, 'b, 'c, T>(
         &'a self,
         entity: &'b EntityId,
         components: &'c T,
-    ) -> impl Future<Output = ()> + Send + 'c
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'c
     where
         'a: 'b,
         'b: 'c,
         T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
     {
-        <T as Archetype<Postgres>>::insert(&components, &self.pool, entity)
+        <T as Archetype<Postgres>>::insert(components, &self.pool, entity)
+    }
+
+    async fn insert_many<T>(&self, entities: &[(EntityId, T)]) -> Result<(), Error>
+    where
+        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
+    {
+        let rows: Vec<_> = entities
+            .iter()
+            .map(|(entity, components)| (entity, components))
+            .collect();
+
+        <T as Archetype<Postgres>>::insert_many(&rows, &self.pool).await
+    }
+
+    async fn spawn_many<T>(&self, components: &[T]) -> Result<Vec<EntityId>, Error>
+    where
+        EntityId: GenerateNew,
+        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
+    {
+        let entities: Vec<_> = components
+            .iter()
+            .map(|_| EntityId::generate_new())
+            .collect();
+
+        let rows: Vec<_> = entities.iter().zip(components).collect();
+
+        <T as Archetype<Postgres>>::insert_many(&rows, &self.pool).await?;
+
+        Ok(entities)
     }
 
     fn update<'a, T>(
         &'a self,
         entity: &'a EntityId,
         components: &'a T,
-    ) -> impl Future<Output = ()> + 'a
+    ) -> impl Future<Output = Result<(), Error>> + 'a
     where
         T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
     {
-        <T as Archetype<Postgres>>::update(&components, &self.pool, entity)
+        <T as Archetype<Postgres>>::update(components, &self.pool, entity)
     }
 
-    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = ()> + 'a
+    fn upsert<'a, T>(
+        &'a self,
+        entity: &'a EntityId,
+        components: &'a T,
+    ) -> impl Future<Output = Result<(), Error>> + 'a
+    where
+        T: Archetype<Postgres> + Serializable<Postgres> + Unpin + Send + 'static,
+    {
+        <T as Archetype<Postgres>>::upsert(components, &self.pool, entity)
+    }
+
+    fn remove<'a, T>(&'a self, entity: &'a EntityId) -> impl Future<Output = Result<(), Error>> + 'a
     where
         T: Archetype<Postgres> + Removable<Postgres> + Unpin + Send + 'static,
     {
         <T as Archetype<Postgres>>::remove(&self.pool, entity)
     }
+
+    fn patch<'a, T>(&'a self, entity: &'a EntityId) -> Patch<'a, Postgres, EntityId, T>
+    where
+        T: Component<Postgres>,
+    {
+        // Changes are reported by the triggers of the table.
+        Patch::new(&self.pool, entity, None)
+    }
+
+    async fn despawn(&self, entity: &EntityId) -> Result<Vec<&'static str>, Error> {
+        self.registry.despawn(&self.pool, entity).await
+    }
+
+    async fn purge<T>(&self) -> Result<u64, Error>
+    where
+        T: SoftDeleteComponent<Postgres>,
+    {
+        let result = sqlx::query(<T as SoftDeleteComponent<Postgres>>::PURGE)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn components_of(&self, entity: &EntityId) -> Result<Vec<&'static str>, Error> {
+        self.registry.components_of(&self.pool, entity).await
+    }
+
+    fn component(&self, table: &str) -> Option<DynamicComponent<Postgres>> {
+        self.registry.component(table)
+    }
+
+    async fn get_dynamic(
+        &self,
+        component: &DynamicComponent<Postgres>,
+        entity: &EntityId,
+    ) -> Result<DynamicRow, Error> {
+        component.get(&self.pool, entity).await
+    }
+
+    async fn list_dynamic(
+        &self,
+        component: &DynamicComponent<Postgres>,
+    ) -> Result<Vec<(EntityId, DynamicRow)>, Error> {
+        component.list(&self.pool).await
+    }
+
+    async fn insert_dynamic(
+        &self,
+        component: &DynamicComponent<Postgres>,
+        entity: &EntityId,
+        row: &[(&str, Value)],
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        component.insert(&mut *tx, entity, row).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn update_dynamic(
+        &self,
+        component: &DynamicComponent<Postgres>,
+        entity: &EntityId,
+        row: &[(&str, Value)],
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        component.update(&mut *tx, entity, row).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn has<T>(&self, entity: &EntityId) -> Result<bool, Error>
+    where
+        T: Deserializeable<Postgres> + Unpin + Send + 'static,
+    {
+        super::has_single(&self.pool, entity, <T as Deserializeable<Postgres>>::cte()).await
+    }
+
+    async fn transaction(&self) -> Result<Transaction<'static, Postgres, EntityId>, Error> {
+        Ok(Transaction::new(self.pool.begin().await?))
+    }
+
+    async fn changes(
+        &self,
+    ) -> Result<impl Stream<Item = Result<Change<EntityId>, Error>> + Send + '_, Error>
+    where
+        Postgres: DatabaseChanges<EntityId>,
+    {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(CHANGES_CHANNEL).await?;
+
+        let registry = &self.registry;
+
+        // The stream ends after the first error, such as the connection being lost.
+        Ok(stream! {
+            loop {
+                let change = match listener.recv().await {
+                    Ok(notification) => parse_change(registry, notification.payload()),
+                    Err(err) => Err(Error::from(err)),
+                };
+
+                match change {
+                    Ok(Some(change)) => yield Ok(change),
+                    Ok(None) => {}
+                    Err(err) => {
+                        yield Err(err);
+                        break;
+                    }
+                }
+            }
+        })
+    }
 }
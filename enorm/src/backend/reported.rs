@@ -0,0 +1,269 @@
+use std::{future::Future, sync::Arc};
+
+use sqlx::{ColumnIndex, Database, Executor, IntoArguments, Pool};
+
+use crate::{
+    archetype::{component_tables, Archetype, DatabasePlaceholder},
+    bulk::DatabaseBulkInsert,
+    changes::{changed, upserted, Broadcast, Change, ChangeKind},
+    dynamic::{DynamicComponent, DynamicDatabase, Value},
+    entity::RowsAffected,
+    error::Error,
+    history::DatabaseClock,
+    prelude::{Deserializeable, Serializable},
+    registry::{present, Registry},
+    tables::Removable,
+};
+
+/// Insert the components of an entity, sending the change to the subscribers
+/// of `changes`.
+pub(crate) fn insert_reported<'c, DB, EntityId, T>(
+    pool: &'c Pool<DB>,
+    changes: &Arc<Broadcast<EntityId>>,
+    entity: &'c EntityId,
+    components: &'c T,
+) -> impl Future<Output = Result<(), Error>> + Send + 'c
+where
+    DB: Database + DatabasePlaceholder,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB> + Send,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone + Send + 'static,
+    for<'e> &'e EntityId: Send,
+{
+    let pending =
+        changes.pending(|| changed(entity, &components.component_tables(), ChangeKind::Insert));
+    let insert = <T as Archetype<DB>>::insert(components, pool, entity);
+
+    let changes = changes.clone();
+
+    async move {
+        insert.await?;
+        changes.send(pending);
+        Ok(())
+    }
+}
+
+/// Insert the components of many entities within a single transaction,
+/// sending the changes to the subscribers of `changes`.
+pub(crate) async fn insert_many_reported<DB, EntityId, T>(
+    pool: &Pool<DB>,
+    changes: &Broadcast<EntityId>,
+    rows: &[(&EntityId, &T)],
+) -> Result<(), Error>
+where
+    DB: Database + DatabaseBulkInsert,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB> + Send,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone + Send + 'static,
+    for<'e> &'e EntityId: Send,
+{
+    let pending = changes.pending(|| {
+        rows.iter()
+            .flat_map(|(entity, components)| {
+                changed(*entity, &components.component_tables(), ChangeKind::Insert)
+            })
+            .collect()
+    });
+
+    <T as Archetype<DB>>::insert_many(rows, pool).await?;
+    changes.send(pending);
+
+    Ok(())
+}
+
+/// Update the components of an entity, sending the changes of the components
+/// which it has to the subscribers of `changes`.
+pub(crate) async fn update_reported<DB, EntityId, T>(
+    pool: &Pool<DB>,
+    changes: &Broadcast<EntityId>,
+    entity: &EntityId,
+    components: &T,
+) -> Result<(), Error>
+where
+    DB: Database + DatabasePlaceholder,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB> + Send,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    <DB as Database>::QueryResult: RowsAffected,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone + Send + 'static,
+    for<'e> &'e EntityId: Send,
+{
+    let tables = changes.pending(|| components.component_tables());
+
+    let mut tx = pool.begin().await?;
+    <T as Archetype<DB>>::update_in(components, &mut tx, entity).await?;
+
+    // Components which the entity doesn't have aren't updated.
+    let updated = present(&mut *tx, entity, tables).await?;
+    tx.commit().await?;
+
+    changes.send(changed(entity, &updated, ChangeKind::Update));
+    Ok(())
+}
+
+/// Insert or update the components of an entity, sending the changes to the
+/// subscribers of `changes`.
+pub(crate) async fn upsert_reported<DB, EntityId, T>(
+    pool: &Pool<DB>,
+    changes: &Broadcast<EntityId>,
+    entity: &EntityId,
+    components: &T,
+) -> Result<(), Error>
+where
+    DB: Database + DatabasePlaceholder,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB> + Send,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone + Send + 'static,
+    for<'e> &'e EntityId: Send,
+{
+    let tables = changes.pending(|| components.component_tables());
+
+    let mut tx = pool.begin().await?;
+
+    // Components which the entity already has are updated, the others inserted.
+    let updated = present(&mut *tx, entity, tables.clone()).await?;
+    <T as Archetype<DB>>::upsert_in(components, &mut tx, entity).await?;
+    tx.commit().await?;
+
+    changes.send(upserted(entity, tables, &updated));
+    Ok(())
+}
+
+/// Remove the components of an entity, sending the changes of the components
+/// which it had to the subscribers of `changes`.
+pub(crate) async fn remove_reported<DB, EntityId, T>(
+    pool: &Pool<DB>,
+    changes: &Broadcast<EntityId>,
+    entity: &EntityId,
+) -> Result<(), Error>
+where
+    DB: Database + DatabasePlaceholder,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB> + Send,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    T: Archetype<DB> + Removable<DB> + Unpin + Send + 'static,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone + Send + 'static,
+    for<'e> &'e EntityId: Send,
+{
+    let tables = changes.pending(|| component_tables(<T as Deserializeable<DB>>::cte().as_ref()));
+
+    let mut tx = pool.begin().await?;
+
+    // Components which the entity doesn't have aren't removed.
+    let removed = present(&mut *tx, entity, tables).await?;
+    <T as Archetype<DB>>::remove_in(&mut tx, entity).await?;
+    tx.commit().await?;
+
+    changes.send(changed(entity, &removed, ChangeKind::Remove));
+    Ok(())
+}
+
+/// Remove every registered component of an entity, sending the changes to the
+/// subscribers of `changes`.
+pub(crate) async fn despawn_reported<DB, EntityId>(
+    pool: &Pool<DB>,
+    registry: &Registry<DB>,
+    changes: &Broadcast<EntityId>,
+    entity: &EntityId,
+) -> Result<Vec<&'static str>, Error>
+where
+    DB: Database + DatabasePlaceholder + DatabaseClock,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone,
+{
+    let removed = registry.despawn(pool, entity).await?;
+
+    changes.send(changes.pending(|| {
+        removed
+            .iter()
+            .map(|table| Change {
+                entity: entity.clone(),
+                table,
+                kind: ChangeKind::Remove,
+            })
+            .collect()
+    }));
+
+    Ok(removed)
+}
+
+/// Insert a dynamic component of an entity, sending the change to the
+/// subscribers of `changes`.
+pub(crate) async fn insert_dynamic_reported<DB, EntityId>(
+    pool: &Pool<DB>,
+    changes: &Broadcast<EntityId>,
+    component: &DynamicComponent<DB>,
+    entity: &EntityId,
+    row: &[(&str, Value)],
+) -> Result<(), Error>
+where
+    DB: DynamicDatabase,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone,
+{
+    let mut tx = pool.begin().await?;
+    component.insert(&mut *tx, entity, row).await?;
+    tx.commit().await?;
+
+    changes.send(changes.pending(|| {
+        vec![Change {
+            entity: entity.clone(),
+            table: component.table(),
+            kind: ChangeKind::Insert,
+        }]
+    }));
+
+    Ok(())
+}
+
+/// Update a dynamic component of an entity, sending the change to the
+/// subscribers of `changes` if the entity has the component.
+pub(crate) async fn update_dynamic_reported<DB, EntityId>(
+    pool: &Pool<DB>,
+    changes: &Broadcast<EntityId>,
+    component: &DynamicComponent<DB>,
+    entity: &EntityId,
+    row: &[(&str, Value)],
+) -> Result<(), Error>
+where
+    DB: DynamicDatabase,
+    for<'q> <DB as Database>::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> &'q mut <DB as Database>::Connection: Executor<'q, Database = DB>,
+    for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'r> i64: sqlx::Decode<'r, DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    <DB as Database>::QueryResult: RowsAffected,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone,
+{
+    let mut tx = pool.begin().await?;
+    let updated = component.update(&mut *tx, entity, row).await?;
+    tx.commit().await?;
+
+    if !updated {
+        return Ok(());
+    }
+
+    changes.send(changes.pending(|| {
+        vec![Change {
+            entity: entity.clone(),
+            table: component.table(),
+            kind: ChangeKind::Update,
+        }]
+    }));
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use std::future::Future;
+
+use sqlx::{query::Query, Database, Execute, Executor, IntoArguments};
+
+use crate::{
+    archetype::{DatabasePlaceholder, Placeholders},
+    error::Error,
+};
+
+/// Databases which can execute multi-row statements built for a single insert.
+///
+/// Queries borrow their statement for as long as the values bound to them, so
+/// statements built while inserting are executed along with the arguments
+/// taken from their query instead.
+pub trait DatabaseBulkInsert: DatabasePlaceholder + Database {
+    /// Execute `statement`, binding `arguments`.
+    fn execute_statement<'c, 'q: 'c>(
+        connection: &'c mut <Self as Database>::Connection,
+        statement: &'c str,
+        arguments: <Self as Database>::Arguments<'q>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'c;
+}
+
+#[cfg(feature = "sqlite")]
+impl DatabaseBulkInsert for sqlx::Sqlite {
+    fn execute_statement<'c, 'q: 'c>(
+        connection: &'c mut sqlx::SqliteConnection,
+        statement: &'c str,
+        arguments: sqlx::sqlite::SqliteArguments<'q>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send + 'c {
+        // The values bound outlive the statement.
+        let arguments: sqlx::sqlite::SqliteArguments<'c> = arguments;
+
+        async move {
+            sqlx::query_with(statement, arguments)
+                .execute(connection)
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl DatabaseBulkInsert for sqlx::MySql {
+    async fn execute_statement<'c, 'q: 'c>(
+        connection: &'c mut sqlx::MySqlConnection,
+        statement: &'c str,
+        arguments: sqlx::mysql::MySqlArguments,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query_with(statement, arguments)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl DatabaseBulkInsert for sqlx::Postgres {
+    async fn execute_statement<'c, 'q: 'c>(
+        connection: &'c mut sqlx::PgConnection,
+        statement: &'c str,
+        arguments: sqlx::postgres::PgArguments,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query_with(statement, arguments)
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A query of a [`BulkInsert`].
+enum Insert<'q, DB: Database> {
+    Query(Query<'q, DB, <DB as Database>::Arguments<'q>>),
+    /// A multi-row statement, along with a query holding the values bound to it.
+    Rows(String, Query<'q, DB, <DB as Database>::Arguments<'q>>),
+}
+
+/// Queries inserting the components of many entities at once.
+///
+/// Components with a table of their own are inserted using multi-row
+/// `insert` statements, each covering as many rows as the database allows
+/// parameters for. Postgres' `copy` isn't used, since it takes rows in its
+/// own text or binary format rather than as bound parameters.
+pub struct BulkInsert<'q, DB>
+where
+    DB: Database,
+{
+    inserts: Vec<Insert<'q, DB>>,
+}
+
+impl<'q, DB> Default for BulkInsert<'q, DB>
+where
+    DB: Database,
+{
+    fn default() -> Self {
+        BulkInsert {
+            inserts: Vec::new(),
+        }
+    }
+}
+
+impl<'q, DB> BulkInsert<'q, DB>
+where
+    DB: Database,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `rows` into a single table.
+    ///
+    /// `statement` is the start of the insert statement up to and including
+    /// `values`, and `columns` the number of parameters bound per row, including
+    /// the entity. `f` binds the remaining parameters of each row.
+    pub fn insert<EntityId, T>(
+        &mut self,
+        statement: &'static str,
+        columns: usize,
+        rows: &[(&'q EntityId, &'q T)],
+        f: impl Fn(
+            Query<'q, DB, <DB as Database>::Arguments<'q>>,
+            &'q T,
+        ) -> Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    ) where
+        DB: DatabasePlaceholder,
+        EntityId: sqlx::Encode<'q, DB> + sqlx::Type<DB> + 'q,
+    {
+        let max_rows = (DB::MAX_PARAMETERS / columns).max(1);
+
+        for chunk in rows.chunks(max_rows) {
+            // The statement is only known once the rows are, so the values
+            // are bound to a query without one.
+            let mut query = sqlx::query("");
+            for (entity, component) in chunk {
+                query = f(query.bind(*entity), component);
+            }
+
+            self.inserts.push(Insert::Rows(
+                multi_row_statement::<DB>(statement, columns, chunk.len()),
+                query,
+            ));
+        }
+    }
+
+    /// Queue `queries` in order.
+    pub fn extend(
+        &mut self,
+        queries: impl IntoIterator<Item = Query<'q, DB, <DB as Database>::Arguments<'q>>>,
+    ) {
+        self.inserts.extend(queries.into_iter().map(Insert::Query));
+    }
+
+    /// Queue `statement` once for each of the `rows`, binding the entity of the row.
+    pub fn query_each<EntityId, T>(
+        &mut self,
+        statement: &'static str,
+        rows: &[(&'q EntityId, &'q T)],
+    ) where
+        EntityId: sqlx::Encode<'q, DB> + sqlx::Type<DB> + 'q,
+    {
+        for (entity, _) in rows {
+            self.inserts
+                .push(Insert::Query(sqlx::query(statement).bind(*entity)));
+        }
+    }
+
+    /// Execute the queries in order using the given connection.
+    pub async fn execute(self, connection: &mut <DB as Database>::Connection) -> Result<(), Error>
+    where
+        DB: DatabaseBulkInsert,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    {
+        for insert in self.inserts {
+            match insert {
+                Insert::Query(query) => {
+                    query.execute(&mut *connection).await?;
+                }
+                Insert::Rows(statement, mut query) => {
+                    let arguments = query
+                        .take_arguments()
+                        .map_err(sqlx::Error::Encode)?
+                        .unwrap_or_default();
+
+                    DB::execute_statement(&mut *connection, &statement, arguments).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Multi-row `insert` statement binding `columns` parameters for each of `rows`.
+fn multi_row_statement<DB: DatabasePlaceholder>(
+    statement: &str,
+    columns: usize,
+    rows: usize,
+) -> String {
+    let mut placeholders = Placeholders::new::<DB>();
+    let mut sql = String::from(statement);
+
+    for row in 0..rows {
+        sql.push_str(if row == 0 { " (" } else { ", (" });
+        for column in 0..columns {
+            if column > 0 {
+                sql.push_str(", ");
+            }
+            placeholders.next(&mut sql).unwrap();
+        }
+        sql.push(')');
+    }
+
+    sql
+}
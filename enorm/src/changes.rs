@@ -0,0 +1,192 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::archetype::ComponentTable;
+use futures::channel::mpsc::Sender;
+
+/// The kind of write which changed a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Remove,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Insert => "insert",
+            ChangeKind::Update => "update",
+            ChangeKind::Remove => "remove",
+        }
+    }
+}
+
+/// A component of an entity was inserted, updated or removed.
+///
+/// Returned by the stream of [`Backend::changes`](crate::backend::Backend::changes).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<EntityId> {
+    pub entity: EntityId,
+    /// Table of the changed component.
+    pub table: &'static str,
+    pub kind: ChangeKind,
+}
+
+/// Databases whose backends can report changes to the entity IDs.
+///
+/// Postgres publishes the entities of changes as text, which are parsed using
+/// their [`FromStr`](std::str::FromStr) implementations, so only such entity IDs
+/// can be subscribed to. Other databases' changes are reported within the
+/// process, regardless of the entity IDs.
+pub trait DatabaseChanges<EntityId>: sqlx::Database {
+    /// Parse an entity published as text by the database itself.
+    fn parse_entity(_entity: &str) -> Option<EntityId> {
+        None
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<EntityId> DatabaseChanges<EntityId> for sqlx::Sqlite {}
+
+#[cfg(feature = "postgres")]
+impl<EntityId: std::str::FromStr> DatabaseChanges<EntityId> for sqlx::Postgres {
+    fn parse_entity(entity: &str) -> Option<EntityId> {
+        entity.parse().ok()
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl<EntityId> DatabaseChanges<EntityId> for sqlx::MySql {}
+
+/// Number of changes buffered for each subscriber of a backend which reports
+/// changes itself, before it is considered to have fallen behind.
+pub const CHANGES_BUFFER: usize = 1024;
+
+#[derive(Debug)]
+struct Subscriber<EntityId> {
+    sender: Sender<Change<EntityId>>,
+    /// Set once the subscriber fell behind, and its stream should end with [`Error::Lagged`](crate::error::Error::Lagged).
+    lagged: Arc<AtomicBool>,
+}
+
+/// Distributes changes to all subscribers within the process.
+///
+/// Used by backends whose databases can't notify clients about changes themselves.
+/// Subscribers which fall behind by more than [`CHANGES_BUFFER`] changes are
+/// dropped, rather than buffering changes for them indefinitely.
+#[derive(Debug)]
+pub(crate) struct Broadcast<EntityId> {
+    subscribers: Mutex<Vec<Subscriber<EntityId>>>,
+}
+
+impl<EntityId> Default for Broadcast<EntityId> {
+    fn default() -> Self {
+        Broadcast {
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<EntityId: Clone> Broadcast<EntityId> {
+    /// Subscribe to the changes sent from now on. The stream ends with
+    /// [`Error::Lagged`](crate::error::Error::Lagged) if the subscriber falls behind.
+    #[cfg(any(feature = "sqlite", feature = "mysql"))]
+    pub fn subscribe(
+        &self,
+    ) -> impl futures::Stream<Item = Result<Change<EntityId>, crate::error::Error>> {
+        use futures::StreamExt as _;
+
+        let (sender, mut receiver) = futures::channel::mpsc::channel(CHANGES_BUFFER);
+        let lagged = Arc::new(AtomicBool::new(false));
+        self.subscribers.lock().unwrap().push(Subscriber {
+            sender,
+            lagged: lagged.clone(),
+        });
+
+        async_stream::stream! {
+            while let Some(change) = receiver.next().await {
+                yield Ok(change);
+            }
+
+            if lagged.load(Ordering::Acquire) {
+                yield Err(crate::error::Error::Lagged);
+            }
+        }
+    }
+
+    /// Whether anyone is subscribed to the changes.
+    pub fn subscribed(&self) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| !subscriber.sender.is_closed());
+
+        !subscribers.is_empty()
+    }
+
+    /// Changes to be sent once the write succeeds, or what they are determined
+    /// from, which are only computed if anyone is subscribed.
+    pub fn pending<T>(&self, changes: impl FnOnce() -> Vec<T>) -> Vec<T> {
+        if self.subscribed() {
+            changes()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Send the changes to all subscribers, dropping those which fell behind.
+    pub fn send(&self, changes: Vec<Change<EntityId>>) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        for change in changes {
+            subscribers.retain_mut(
+                |subscriber| match subscriber.sender.try_send(change.clone()) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        // Subscribers which have gone away are simply removed.
+                        if err.is_full() {
+                            subscriber.lagged.store(true, Ordering::Release);
+                        }
+                        false
+                    }
+                },
+            );
+        }
+    }
+}
+
+/// Changes of the entity, one for each of the component tables.
+pub(crate) fn changed<EntityId: Clone>(
+    entity: &EntityId,
+    tables: &[ComponentTable],
+    kind: ChangeKind,
+) -> Vec<Change<EntityId>> {
+    tables
+        .iter()
+        .map(|table| Change {
+            entity: entity.clone(),
+            table: table.name,
+            kind,
+        })
+        .collect()
+}
+
+/// Changes of upserting the components of the entity, which are updates of
+/// the components it already had, and inserts of the others.
+pub(crate) fn upserted<EntityId: Clone>(
+    entity: &EntityId,
+    tables: Vec<ComponentTable>,
+    updated: &[ComponentTable],
+) -> Vec<Change<EntityId>> {
+    tables
+        .into_iter()
+        .map(|table| Change {
+            entity: entity.clone(),
+            table: table.name,
+            kind: if updated.contains(&table) {
+                ChangeKind::Update
+            } else {
+                ChangeKind::Insert
+            },
+        })
+        .collect()
+}
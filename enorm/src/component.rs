@@ -1,8 +1,10 @@
-use std::future::Future;
+use std::{fmt::Write, future::Future};
 
-use sqlx::{Database, Pool};
+use sqlx::{Database, Executor, Pool, TypeInfo as _};
 
 use crate::{
+    changes::ChangeKind,
+    dynamic::ValueKind,
     entity::EntityPrefixedQuery,
     serialization::{Deserializeable, Serializable},
 };
@@ -10,6 +12,14 @@ use crate::{
 pub struct ColumnDefinition<DB: Database> {
     pub name: &'static str,
     pub type_info: <DB as Database>::TypeInfo,
+    /// Whether the column holds nulls, such as for optional fields.
+    pub nullable: bool,
+    /// Whether the column has an index of its own.
+    pub indexed: bool,
+    /// Whether the values of the column are unique across entities.
+    pub unique: bool,
+    /// The component table whose entities the column refers to, if any.
+    pub references: Option<Reference>,
 }
 
 impl<DB: Database> ColumnDefinition<DB> {
@@ -20,6 +30,298 @@ impl<DB: Database> ColumnDefinition<DB> {
     pub fn type_info(&self) -> &<DB as Database>::TypeInfo {
         &self.type_info
     }
+
+    pub fn nullable(&self) -> bool {
+        self.nullable
+    }
+
+    pub fn indexed(&self) -> bool {
+        self.indexed
+    }
+
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+
+    pub fn references(&self) -> Option<&Reference> {
+        self.references.as_ref()
+    }
+
+    /// SQL type of the column within the component table.
+    ///
+    /// Columns referencing other entities use the type of the `entity` column,
+    /// since the two must be comparable.
+    pub fn sql_type(&self) -> String
+    where
+        DB: DatabaseColumnType,
+    {
+        if self.references.is_some() {
+            DB::key_type(&self.type_info)
+        } else {
+            DB::column_type(&self.type_info)
+        }
+    }
+}
+
+/// Function producing the column definitions of a component.
+pub(crate) type Columns<DB> = fn() -> Vec<ColumnDefinition<DB>>;
+
+/// Name of the hidden column holding the variant of an enum component.
+pub const TAG_COLUMN: &str = "__enorm_tag";
+
+/// Foreign key from a column to the entities of another component's table.
+///
+/// Only entities which have the referenced component can be referred to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    pub table: &'static str,
+    pub on_delete: OnDelete,
+}
+
+/// What happens to referencing components, when the referenced component is removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnDelete {
+    /// Prevent the removal, for as long as the component is referenced.
+    #[default]
+    Restrict,
+    /// Remove the referencing components as well.
+    Cascade,
+    /// Set the referencing columns to null.
+    SetNull,
+}
+
+impl OnDelete {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            OnDelete::Restrict => "restrict",
+            OnDelete::Cascade => "cascade",
+            OnDelete::SetNull => "set null",
+        }
+    }
+}
+
+/// Secondary index on one or more columns of a component table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexDefinition {
+    pub columns: &'static [&'static str],
+    pub unique: bool,
+}
+
+impl IndexDefinition {
+    /// Name of the index on the given table.
+    pub fn name(&self, table: &str) -> String {
+        format!(
+            "{table}_{columns}_{suffix}",
+            columns = self.columns.join("_"),
+            suffix = if self.unique { "key" } else { "idx" }
+        )
+    }
+}
+
+/// Database-specific names of column types, as used when creating component tables.
+pub trait DatabaseColumnType: Database {
+    /// SQL type of the `entity` column, which must be usable as a primary key.
+    fn key_type(type_info: &<Self as Database>::TypeInfo) -> String {
+        type_info.name().to_string()
+    }
+
+    /// SQL type of a component's field column.
+    fn column_type(type_info: &<Self as Database>::TypeInfo) -> String {
+        type_info.name().to_string()
+    }
+
+    /// Kind of [`Value`](crate::dynamic::Value) which columns of the type are
+    /// read and written as, if any.
+    fn value_kind(type_info: &<Self as Database>::TypeInfo) -> Option<ValueKind>;
+
+    /// Write `expression` converted to the given type.
+    ///
+    /// Used for expressions such as aggregates, whose result type differs between databases.
+    fn cast(
+        f: &mut dyn Write,
+        expression: &str,
+        type_info: &<Self as Database>::TypeInfo,
+    ) -> std::fmt::Result {
+        write!(f, "cast({expression} as {})", Self::column_type(type_info))
+    }
+
+    /// Index declarations to include within the `create table` statement,
+    /// each starting with a comma.
+    fn inline_indexes(
+        _table: &str,
+        _indexes: &[IndexDefinition],
+        _columns: &[ColumnDefinition<Self>],
+    ) -> String {
+        String::new()
+    }
+
+    /// Foreign key constraints to include within the `create table` statement,
+    /// each starting with a comma.
+    fn foreign_keys(columns: &[ColumnDefinition<Self>]) -> String {
+        columns
+            .iter()
+            .filter_map(|column| {
+                let reference = column.references()?;
+
+                Some(format!(
+                    ",\n  foreign key ({column}) references {table}(entity) on delete {action}",
+                    column = column.name(),
+                    table = reference.table,
+                    action = reference.on_delete.as_sql()
+                ))
+            })
+            .collect()
+    }
+
+    /// Statements creating the indexes, unless they already exist.
+    fn index_statements(table: &str, indexes: &[IndexDefinition]) -> Vec<String> {
+        indexes
+            .iter()
+            .map(|index| {
+                format!(
+                    "create {unique}index if not exists {name} on {table}({columns})",
+                    unique = if index.unique { "unique " } else { "" },
+                    name = index.name(table),
+                    columns = index.columns.join(", ")
+                )
+            })
+            .collect()
+    }
+}
+
+/// Sqlite stores dates and times as text.
+#[cfg(feature = "sqlite")]
+impl DatabaseColumnType for sqlx::Sqlite {
+    fn value_kind(type_info: &<Self as Database>::TypeInfo) -> Option<ValueKind> {
+        match type_info.name() {
+            "BOOLEAN" => Some(ValueKind::Bool),
+            "INTEGER" => Some(ValueKind::Integer),
+            "REAL" => Some(ValueKind::Real),
+            "TEXT" | "DATE" | "TIME" | "DATETIME" => Some(ValueKind::Text),
+            "BLOB" => Some(ValueKind::Blob),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl DatabaseColumnType for sqlx::Postgres {
+    fn value_kind(type_info: &<Self as Database>::TypeInfo) -> Option<ValueKind> {
+        match type_info.name() {
+            "BOOL" => Some(ValueKind::Bool),
+            "INT2" | "INT4" | "INT8" => Some(ValueKind::Integer),
+            "FLOAT4" | "FLOAT8" => Some(ValueKind::Real),
+            "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" => Some(ValueKind::Text),
+            "BYTEA" => Some(ValueKind::Blob),
+            _ => None,
+        }
+    }
+}
+
+/// MySQL can't use unbounded text or blob columns as keys, and requires
+/// lengths for (var)char and binary columns.
+///
+/// Indexes are declared as part of the table, since MySQL has no
+/// `create index if not exists`.
+#[cfg(feature = "mysql")]
+impl DatabaseColumnType for sqlx::MySql {
+    fn value_kind(type_info: &<Self as Database>::TypeInfo) -> Option<ValueKind> {
+        match type_info.name() {
+            "BOOLEAN" => Some(ValueKind::Bool),
+            "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => Some(ValueKind::Integer),
+            "FLOAT" | "DOUBLE" => Some(ValueKind::Real),
+            "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" => {
+                Some(ValueKind::Text)
+            }
+            "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+                Some(ValueKind::Blob)
+            }
+            _ => None,
+        }
+    }
+
+    fn key_type(type_info: &<Self as Database>::TypeInfo) -> String {
+        match type_info.name() {
+            "CHAR" | "VARCHAR" | "TEXT" => "VARCHAR(255)".to_string(),
+            // Uuids are the only fixed length binary type, stored as their 16 raw bytes.
+            "BINARY" => "BINARY(16)".to_string(),
+            "VARBINARY" | "BLOB" => "VARBINARY(255)".to_string(),
+            name => name.to_string(),
+        }
+    }
+
+    fn column_type(type_info: &<Self as Database>::TypeInfo) -> String {
+        match type_info.name() {
+            "CHAR" | "VARCHAR" => "TEXT".to_string(),
+            "BINARY" | "VARBINARY" => "BLOB".to_string(),
+            name => name.to_string(),
+        }
+    }
+
+    /// MySQL only accepts a handful of type names in casts.
+    fn cast(
+        f: &mut dyn Write,
+        expression: &str,
+        type_info: &<Self as Database>::TypeInfo,
+    ) -> std::fmt::Result {
+        match type_info.name() {
+            "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+                write!(f, "cast({expression} as signed)")
+            }
+            "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+            | "BIGINT UNSIGNED" => write!(f, "cast({expression} as unsigned)"),
+            "FLOAT" | "DOUBLE" => write!(f, "cast({expression} as double)"),
+            _ => write!(f, "{expression}"),
+        }
+    }
+
+    fn inline_indexes(
+        table: &str,
+        indexes: &[IndexDefinition],
+        columns: &[ColumnDefinition<Self>],
+    ) -> String {
+        indexes
+            .iter()
+            .map(|index| {
+                format!(
+                    ",\n  {unique}index {name}({columns})",
+                    unique = if index.unique { "unique " } else { "" },
+                    name = index.name(table),
+                    columns = mysql_key_parts(index, columns)
+                )
+            })
+            .collect()
+    }
+
+    fn index_statements(_table: &str, _indexes: &[IndexDefinition]) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Columns of the index as listed by MySQL, where text and blob columns can
+/// only be indexed by a prefix.
+#[cfg(feature = "mysql")]
+pub(crate) fn mysql_key_parts(
+    index: &IndexDefinition,
+    columns: &[ColumnDefinition<sqlx::MySql>],
+) -> String {
+    index
+        .columns
+        .iter()
+        .map(|column| {
+            let prefixed = columns.iter().any(|definition| {
+                definition.name() == *column
+                    && matches!(definition.sql_type().as_str(), "TEXT" | "BLOB")
+            });
+
+            if prefixed {
+                format!("{column}(255)")
+            } else {
+                column.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Describes reading and writing from a Component-specific Table.
@@ -27,22 +329,111 @@ pub trait Component<DB: Database>: Serializable<DB> + Deserializeable<DB> + Size
     const JOIN: &'static str = "inner";
     const INSERT: &'static str;
     const UPDATE: &'static str;
+    /// Inserts the component, replacing it if the entity already has one.
+    const UPSERT: &'static str;
+    /// Removes the component, or tombstones it if the component is soft-deleted.
     const DELETE: &'static str;
+    /// Whether the component is declared using `#[enorm(soft_delete)]`, see
+    /// [`SoftDeleteComponent`](crate::soft_delete::SoftDeleteComponent).
+    const SOFT_DELETE: bool = false;
+    /// Whether the component is declared using `#[enorm(versioned)]`, see
+    /// [`VersionedComponent`](crate::versioned::VersionedComponent).
+    const VERSIONED: bool = false;
+    /// Whether the component is declared using `#[enorm(history)]`, see
+    /// [`HISTORY_SUFFIX`](crate::history::HISTORY_SUFFIX).
+    const HISTORY: bool = false;
+    /// Whether the component is an enum, whose variant is held by the
+    /// [`TAG_COLUMN`] of its table.
+    const TAGGED: bool = false;
 
     fn table() -> &'static str;
 
     fn columns() -> Vec<ColumnDefinition<DB>>;
 
+    /// Secondary indexes of the component table.
+    fn indexes() -> Vec<IndexDefinition> {
+        Vec::new()
+    }
+
     fn remove_component<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
     where
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
     {
+        Self::record_history(query, ChangeKind::Remove);
+
         query.query(Self::DELETE, |query| query)
     }
 
-    fn create_component_table<'pool, EntityId>(
-        pool: &'pool Pool<DB>,
-    ) -> impl Future<Output = Result<<DB as Database>::QueryResult, sqlx::Error>> + Send
+    /// Queue the upsert of the component, recording whether it was inserted
+    /// or updated in its history table, if it keeps one.
+    fn upsert_component<'query, EntityId>(
+        &'query self,
+        query: &mut EntityPrefixedQuery<'query, DB, EntityId>,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        query.query(Self::UPSERT, move |query| {
+            <Self as Serializable<DB>>::serialize(self, query)
+        })
+    }
+
+    /// Queue recording the current state of the component in its history
+    /// table, if it keeps one.
+    ///
+    /// Changes are recorded after inserting or updating the component, but
+    /// before removing it.
+    fn record_history<'query, EntityId>(
+        _query: &mut EntityPrefixedQuery<'query, DB, EntityId>,
+        _kind: ChangeKind,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+    }
+
+    /// Statement creating the table of the component, unless it already exists.
+    fn create_table_statement<EntityId>() -> String
     where
         EntityId: sqlx::Type<DB>;
+
+    /// Statements creating the secondary indexes of the component table,
+    /// unless they already exist.
+    fn create_index_statements() -> Vec<String>
+    where
+        DB: DatabaseColumnType,
+    {
+        DB::index_statements(Self::table(), &Self::indexes())
+    }
+
+    /// Statements creating the history table of the component and its
+    /// indexes, unless they already exist or the component keeps no history.
+    fn create_history_statements<EntityId>() -> Vec<String>
+    where
+        DB: DatabaseColumnType,
+        EntityId: sqlx::Type<DB>,
+    {
+        Vec::new()
+    }
+
+    fn create_component_table<EntityId>(
+        pool: &Pool<DB>,
+    ) -> impl Future<Output = Result<<DB as Database>::QueryResult, sqlx::Error>> + Send
+    where
+        DB: DatabaseColumnType,
+        EntityId: sqlx::Type<DB>,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    {
+        let sql = Self::create_table_statement::<EntityId>();
+        let indexes = Self::create_index_statements();
+        let history = Self::create_history_statements::<EntityId>();
+
+        async move {
+            let result = pool.execute(sql.as_str()).await?;
+
+            for statement in indexes.into_iter().chain(history) {
+                pool.execute(statement.as_str()).await?;
+            }
+
+            Ok(result)
+        }
+    }
 }
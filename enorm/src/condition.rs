@@ -2,13 +2,13 @@ use std::fmt::Write;
 
 use sqlx::{query::QueryAs, Database};
 
-use crate::archetype::DatabasePlaceholder;
+use crate::archetype::Placeholders;
 
 pub trait Condition<'q, DB>: Sized
 where
     DB: Database,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result;
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result;
     fn bind<T>(
         self,
         query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
@@ -21,8 +21,13 @@ where
     fn or<B: Condition<'q, DB>>(self, other: B) -> Or<Self, B> {
         Or::new(self, other)
     }
+
+    fn not(self) -> Not<Self> {
+        Not::new(self)
+    }
 }
 
+#[derive(Clone)]
 pub struct All;
 
 impl<'q, DB: Database> Condition<'q, DB> for All {
@@ -36,11 +41,12 @@ impl<'q, DB: Database> Condition<'q, DB> for All {
         query
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> std::fmt::Result {
         write!(f, "1 = 1")
     }
 }
 
+#[derive(Clone)]
 pub struct Equality<Parameter> {
     column: &'static str,
     parameter: Parameter,
@@ -55,17 +61,13 @@ impl<Parameter> Equality<Parameter> {
     }
 }
 
-impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB> for Equality<Parameter>
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for Equality<Parameter>
 where
     Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
-        write!(
-            f,
-            "{} == {}",
-            self.column,
-            <DB as DatabasePlaceholder>::PLACEHOLDER
-        )
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} = ", self.column)?;
+        placeholders.next(f)
     }
 
     fn bind<T>(
@@ -79,6 +81,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct Inequality<Parameter> {
     column: &'static str,
     parameter: Parameter,
@@ -93,17 +96,13 @@ impl<Parameter> Inequality<Parameter> {
     }
 }
 
-impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB> for Inequality<Parameter>
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for Inequality<Parameter>
 where
     Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
-        write!(
-            f,
-            "{} <> {}",
-            self.column,
-            <DB as DatabasePlaceholder>::PLACEHOLDER
-        )
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} <> ", self.column)?;
+        placeholders.next(f)
     }
 
     fn bind<T>(
@@ -117,6 +116,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct GreaterThan<Parameter> {
     column: &'static str,
     parameter: Parameter,
@@ -131,17 +131,13 @@ impl<Parameter> GreaterThan<Parameter> {
     }
 }
 
-impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB> for GreaterThan<Parameter>
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for GreaterThan<Parameter>
 where
     Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
-        write!(
-            f,
-            "{} > {}",
-            self.column,
-            <DB as DatabasePlaceholder>::PLACEHOLDER
-        )
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} > ", self.column)?;
+        placeholders.next(f)
     }
 
     fn bind<T>(
@@ -155,6 +151,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct LessThan<Parameter> {
     column: &'static str,
     parameter: Parameter,
@@ -169,17 +166,13 @@ impl<Parameter> LessThan<Parameter> {
     }
 }
 
-impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB> for LessThan<Parameter>
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for LessThan<Parameter>
 where
     Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
-        write!(
-            f,
-            "{} < {}",
-            self.column,
-            <DB as DatabasePlaceholder>::PLACEHOLDER
-        )
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} < ", self.column)?;
+        placeholders.next(f)
     }
 
     fn bind<T>(
@@ -193,6 +186,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct GreaterThanOrEqual<Parameter> {
     column: &'static str,
     parameter: Parameter,
@@ -207,18 +201,13 @@ impl<Parameter> GreaterThanOrEqual<Parameter> {
     }
 }
 
-impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB>
-    for GreaterThanOrEqual<Parameter>
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for GreaterThanOrEqual<Parameter>
 where
     Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
-        write!(
-            f,
-            "{} >= {}",
-            self.column,
-            <DB as DatabasePlaceholder>::PLACEHOLDER
-        )
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} >= ", self.column)?;
+        placeholders.next(f)
     }
 
     fn bind<T>(
@@ -232,6 +221,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct LessThanOrEqual<Parameter> {
     column: &'static str,
     parameter: Parameter,
@@ -246,18 +236,222 @@ impl<Parameter> LessThanOrEqual<Parameter> {
     }
 }
 
-impl<'q, DB: Database + DatabasePlaceholder, Parameter> Condition<'q, DB>
-    for LessThanOrEqual<Parameter>
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for LessThanOrEqual<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} <= ", self.column)?;
+        placeholders.next(f)
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query.bind(self.parameter)
+    }
+}
+
+#[derive(Clone)]
+pub struct In<Parameter> {
+    column: &'static str,
+    parameters: Vec<Parameter>,
+}
+
+impl<Parameter> In<Parameter> {
+    pub const fn new(column: &'static str, values: Vec<Parameter>) -> Self {
+        Self {
+            column,
+            parameters: values,
+        }
+    }
+}
+
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for In<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        // Not all databases accept an empty list.
+        if self.parameters.is_empty() {
+            return write!(f, "1 = 0");
+        }
+
+        write!(f, "{} in (", self.column)?;
+        serialize_list(f, placeholders, self.parameters.len())?;
+        write!(f, ")")
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        self.parameters
+            .into_iter()
+            .fold(query, |query, parameter| query.bind(parameter))
+    }
+}
+
+#[derive(Clone)]
+pub struct NotIn<Parameter> {
+    column: &'static str,
+    parameters: Vec<Parameter>,
+}
+
+impl<Parameter> NotIn<Parameter> {
+    pub const fn new(column: &'static str, values: Vec<Parameter>) -> Self {
+        Self {
+            column,
+            parameters: values,
+        }
+    }
+}
+
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for NotIn<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        if self.parameters.is_empty() {
+            return write!(f, "1 = 1");
+        }
+
+        write!(f, "{} not in (", self.column)?;
+        serialize_list(f, placeholders, self.parameters.len())?;
+        write!(f, ")")
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        self.parameters
+            .into_iter()
+            .fold(query, |query, parameter| query.bind(parameter))
+    }
+}
+
+fn serialize_list(
+    f: &mut dyn Write,
+    placeholders: &mut Placeholders,
+    count: usize,
+) -> std::fmt::Result {
+    for i in 0..count {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        placeholders.next(f)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct Between<Parameter> {
+    column: &'static str,
+    low: Parameter,
+    high: Parameter,
+}
+
+impl<Parameter> Between<Parameter> {
+    pub const fn new(column: &'static str, low: Parameter, high: Parameter) -> Self {
+        Self { column, low, high }
+    }
+}
+
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for Between<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} between ", self.column)?;
+        placeholders.next(f)?;
+        write!(f, " and ")?;
+        placeholders.next(f)
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query.bind(self.low).bind(self.high)
+    }
+}
+
+#[derive(Clone)]
+pub struct Like<Parameter> {
+    column: &'static str,
+    parameter: Parameter,
+}
+
+impl<Parameter> Like<Parameter> {
+    pub const fn new(column: &'static str, pattern: Parameter) -> Self {
+        Self {
+            column,
+            parameter: pattern,
+        }
+    }
+}
+
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for Like<Parameter>
+where
+    Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
+{
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} like ", self.column)?;
+        placeholders.next(f)
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query.bind(self.parameter)
+    }
+}
+
+/// Case-insensitive [`Like`].
+#[derive(Clone)]
+pub struct ILike<Parameter> {
+    column: &'static str,
+    parameter: Parameter,
+}
+
+impl<Parameter> ILike<Parameter> {
+    pub const fn new(column: &'static str, pattern: Parameter) -> Self {
+        Self {
+            column,
+            parameter: pattern,
+        }
+    }
+}
+
+impl<'q, DB: Database, Parameter> Condition<'q, DB> for ILike<Parameter>
 where
     Parameter: sqlx::Type<DB> + sqlx::Encode<'q, DB> + 'q,
 {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
-        write!(
-            f,
-            "{} <= {}",
-            self.column,
-            <DB as DatabasePlaceholder>::PLACEHOLDER
-        )
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        // Only Postgres has `ilike`, and whether `like` is case-sensitive
+        // differs between the others.
+        write!(f, "lower({}) like lower(", self.column)?;
+        placeholders.next(f)?;
+        write!(f, ")")
     }
 
     fn bind<T>(
@@ -271,6 +465,90 @@ where
     }
 }
 
+#[derive(Clone)]
+pub struct IsNull {
+    column: &'static str,
+}
+
+impl IsNull {
+    pub const fn new(column: &'static str) -> Self {
+        Self { column }
+    }
+}
+
+impl<'q, DB: Database> Condition<'q, DB> for IsNull {
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} is null", self.column)
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query
+    }
+}
+
+#[derive(Clone)]
+pub struct IsNotNull {
+    column: &'static str,
+}
+
+impl IsNotNull {
+    pub const fn new(column: &'static str) -> Self {
+        Self { column }
+    }
+}
+
+impl<'q, DB: Database> Condition<'q, DB> for IsNotNull {
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "{} is not null", self.column)
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        query
+    }
+}
+
+#[derive(Clone)]
+pub struct Not<C> {
+    inner: C,
+}
+
+impl<C> Not<C> {
+    pub const fn new(inner: C) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'q, DB: Database, C: Condition<'q, DB>> Condition<'q, DB> for Not<C> {
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
+        write!(f, "not (")?;
+        self.inner.serialize(f, placeholders)?;
+        write!(f, ")")
+    }
+
+    fn bind<T>(
+        self,
+        query: QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, T, <DB as Database>::Arguments<'q>>
+    where
+        DB: Database,
+    {
+        self.inner.bind(query)
+    }
+}
+
+#[derive(Clone)]
 pub struct And<A, B> {
     a: A,
     b: B,
@@ -283,11 +561,11 @@ impl<A, B> And<A, B> {
 }
 
 impl<'q, DB: Database, A: Condition<'q, DB>, B: Condition<'q, DB>> Condition<'q, DB> for And<A, B> {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
         write!(f, "(")?;
-        self.a.serialize(f)?;
+        self.a.serialize(f, placeholders)?;
         write!(f, " and ")?;
-        self.b.serialize(f)?;
+        self.b.serialize(f, placeholders)?;
         write!(f, ")")
     }
 
@@ -304,6 +582,7 @@ impl<'q, DB: Database, A: Condition<'q, DB>, B: Condition<'q, DB>> Condition<'q,
         query
     }
 }
+#[derive(Clone)]
 pub struct Or<A, B> {
     a: A,
     b: B,
@@ -316,11 +595,11 @@ impl<A, B> Or<A, B> {
 }
 
 impl<'q, DB: Database, A: Condition<'q, DB>, B: Condition<'q, DB>> Condition<'q, DB> for Or<A, B> {
-    fn serialize(&self, f: &mut dyn Write) -> std::fmt::Result {
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> std::fmt::Result {
         write!(f, "(")?;
-        self.a.serialize(f)?;
+        self.a.serialize(f, placeholders)?;
         write!(f, " or ")?;
-        self.b.serialize(f)?;
+        self.b.serialize(f, placeholders)?;
         write!(f, ")")
     }
 
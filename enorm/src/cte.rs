@@ -1,26 +1,65 @@
 use std::marker::PhantomData;
-use std::{collections::BTreeSet, fmt::Write};
+use std::{collections::HashSet, fmt::Write};
 
 use std::fmt::Result;
 
 use sqlx::Database;
 
+use crate::archetype::{ComponentTable, Placeholders};
+use crate::history::{CHANGED_AT_COLUMN, HISTORY_SUFFIX, OPERATION_COLUMN};
 use crate::prelude::Deserializeable;
+use crate::soft_delete::DELETED_AT_COLUMN;
+use crate::versioned::VERSION_COLUMN;
 
 pub trait CommonTableExpression: std::fmt::Debug {
     fn table_name(&self, f: &mut dyn Write) -> Result;
     fn columns(&self, f: &mut dyn Write) -> Result;
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>];
-    fn serialize(&self, f: &mut dyn Write) -> Result;
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> Result;
     fn optional(&self) -> bool {
         false
     }
+
+    /// Whether the expression refers to itself, which requires `with recursive`.
+    fn recursive(&self) -> bool {
+        false
+    }
+
+    /// Stop excluding tombstoned components of soft-deleted tables,
+    /// throughout the expression and its dependencies.
+    fn include_deleted(&mut self) {}
+
+    /// Read components which keep a history as they were at the given
+    /// [timestamp](crate::history::timestamp), throughout the expression
+    /// and its dependencies.
+    fn as_of(&mut self, _timestamp: i64) {}
+
+    /// Collect the tables of the components read by the expression and its
+    /// dependencies.
+    fn component_tables(&self, tables: &mut Vec<ComponentTable>) {
+        for dependency in self.dependencies() {
+            dependency.component_tables(tables);
+        }
+    }
+
+    /// Write the name of the column holding the Entity ID.
+    fn entity_column(&self, f: &mut dyn Write) -> Result {
+        write!(f, "__cte_")?;
+        self.table_name(f)?;
+        write!(f, "__entity")
+    }
 }
 
 #[derive(Debug)]
 pub struct Extract {
     pub table: &'static str,
     pub columns: &'static [&'static str],
+    /// Exclude rows tombstoned by removing a soft-deleted component.
+    pub exclude_deleted: bool,
+    /// Whether the component keeps a history table.
+    pub history: bool,
+    /// Read the component from its history table, as it was at this timestamp.
+    pub as_of: Option<i64>,
 }
 
 impl CommonTableExpression for Extract {
@@ -29,29 +68,106 @@ impl CommonTableExpression for Extract {
     }
 
     fn columns(&self, f: &mut dyn Write) -> Result {
-        for column in self.columns {
-            write!(f, ",\n      __cte_{}__{}", self.table, column)?
+        extract_columns(f, self.table, self.columns)
+    }
+
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
+        if let Some(timestamp) = self.as_of {
+            return snapshot(f, self.table, self.columns, timestamp);
+        }
+
+        extract(f, self.table, self.columns)?;
+
+        if self.exclude_deleted {
+            write!(f, "\n    where\n      {DELETED_AT_COLUMN} is null")?;
+        }
+
+        Ok(())
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &[]
+    }
+
+    /// Until [`CommonTableExpression::include_deleted`] is called, exactly the
+    /// tombstones of soft-deleted components are excluded.
+    fn component_tables(&self, tables: &mut Vec<ComponentTable>) {
+        let table = ComponentTable {
+            name: self.table,
+            soft_delete: self.exclude_deleted,
+        };
+
+        if !tables.contains(&table) {
+            tables.push(table);
+        }
+    }
+
+    fn include_deleted(&mut self) {
+        self.exclude_deleted = false;
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        if self.history {
+            self.as_of = Some(timestamp);
+        }
+    }
+}
+
+/// Like [`Extract`], but for columns which are only known at runtime.
+#[derive(Debug)]
+pub struct ExtractColumns {
+    pub table: &'static str,
+    pub columns: Vec<&'static str>,
+    /// Exclude rows tombstoned by removing a soft-deleted component.
+    pub exclude_deleted: bool,
+}
+
+impl CommonTableExpression for ExtractColumns {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        write!(f, "{}", self.table)
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        extract_columns(f, self.table, &self.columns)
+    }
+
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
+        extract(f, self.table, &self.columns)?;
+
+        if self.exclude_deleted {
+            write!(f, "\n    where\n      {DELETED_AT_COLUMN} is null")?;
         }
 
         Ok(())
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &[]
+    }
+}
+
+/// Extracts the hidden version column of a versioned component's table.
+#[derive(Debug)]
+pub struct Version {
+    pub table: &'static str,
+}
+
+impl CommonTableExpression for Version {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        write!(f, "{}_version", self.table)
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        write!(f, ",\n      __cte_{}_version__version", self.table)
+    }
+
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
         write!(
             f,
-            "    select\n      entity as __cte_{table}__entity",
+            "    select\n      entity as __cte_{table}_version__entity,\n      \
+             {VERSION_COLUMN} as __cte_{table}_version__version\n    from\n      {table}",
             table = self.table
-        )?;
-        for column in self.columns {
-            write!(
-                f,
-                ",\n      {column} as __cte_{table}__{column}",
-                table = self.table,
-                column = column
-            )?
-        }
-        write!(f, "\n    from\n      ")?;
-        self.table_name(f)
+        )
     }
 
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
@@ -59,6 +175,47 @@ impl CommonTableExpression for Extract {
     }
 }
 
+fn extract_columns(f: &mut dyn Write, table: &str, columns: &[&str]) -> Result {
+    for column in columns {
+        write!(f, ",\n      __cte_{}__{}", table, column)?
+    }
+
+    Ok(())
+}
+
+/// Selects the latest state recorded in the history table at the timestamp,
+/// unless the component had been removed by then.
+fn snapshot(f: &mut dyn Write, table: &str, columns: &[&str], timestamp: i64) -> Result {
+    write!(f, "    select\n      entity as __cte_{table}__entity")?;
+    for column in columns {
+        write!(f, ",\n      {column} as __cte_{table}__{column}")?
+    }
+    write!(f, "\n    from\n      ")?;
+    snapshot_rows(f, table, timestamp)
+}
+
+/// Rows of the history table holding the latest state recorded at the
+/// timestamp, unless the component had been removed by then.
+fn snapshot_rows(f: &mut dyn Write, table: &str, timestamp: i64) -> Result {
+    write!(
+        f,
+        "{table}{HISTORY_SUFFIX} as __enorm_history\n    where\n      \
+         {CHANGED_AT_COLUMN} = (\n        select max(__enorm_previous.{CHANGED_AT_COLUMN})\n        \
+         from {table}{HISTORY_SUFFIX} as __enorm_previous\n        \
+         where __enorm_previous.entity = __enorm_history.entity\n        \
+         and __enorm_previous.{CHANGED_AT_COLUMN} <= {timestamp}\n      )\n      \
+         and {OPERATION_COLUMN} <> 'remove'"
+    )
+}
+
+fn extract(f: &mut dyn Write, table: &str, columns: &[&str]) -> Result {
+    write!(f, "    select\n      entity as __cte_{table}__entity")?;
+    for column in columns {
+        write!(f, ",\n      {column} as __cte_{table}__{column}")?
+    }
+    write!(f, "\n    from\n      {table}")
+}
+
 pub struct Single<EntityId> {
     pub inner: [Box<dyn CommonTableExpression>; 1],
     pub entity: EntityId,
@@ -82,7 +239,7 @@ impl<EntityId> CommonTableExpression for Single<EntityId> {
         self.inner[0].columns(f)
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> Result {
         write!(f, "    select\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "__entity")?;
@@ -91,16 +248,33 @@ impl<EntityId> CommonTableExpression for Single<EntityId> {
         self.inner[0].table_name(f)?;
         write!(f, "\n    where __cte_")?;
         self.inner[0].table_name(f)?;
-        write!(f, "__entity = ?1")
+        write!(f, "__entity = ")?;
+        placeholders.next(f)
     }
 
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         self.inner.as_slice()
     }
 
+    fn include_deleted(&mut self) {
+        for inner in self.inner.iter_mut() {
+            inner.include_deleted();
+        }
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        for inner in self.inner.iter_mut() {
+            inner.as_of(timestamp);
+        }
+    }
+
     fn optional(&self) -> bool {
         true
     }
+
+    fn entity_column(&self, f: &mut dyn Write) -> Result {
+        self.inner[0].entity_column(f)
+    }
 }
 
 #[derive(Debug)]
@@ -117,14 +291,26 @@ impl CommonTableExpression for Optional {
         self.inner.columns(f)
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
-        self.inner.serialize(f)
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> Result {
+        self.inner.serialize(f, placeholders)
     }
 
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         self.inner.dependencies()
     }
 
+    fn component_tables(&self, tables: &mut Vec<ComponentTable>) {
+        self.inner.component_tables(tables)
+    }
+
+    fn include_deleted(&mut self) {
+        self.inner.include_deleted()
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        self.inner.as_of(timestamp)
+    }
+
     fn optional(&self) -> bool {
         true
     }
@@ -159,17 +345,17 @@ impl CommonTableExpression for Merge {
         Ok(())
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
         let mut tables = self.tables.iter();
         let first = tables.next().unwrap();
 
-        write!(f, "{}", "    select\n      __cte_")?;
+        write!(f, "    select\n      __cte_")?;
         first.table_name(f)?;
         write!(f, "__entity as __cte_")?;
         self.table_name(f)?;
         write!(f, "__entity")?;
         self.columns(f)?;
-        write!(f, "{}", "\n    from\n      __cte_")?;
+        write!(f, "\n    from\n      __cte_")?;
         first.table_name(f)?;
 
         for table in tables {
@@ -180,7 +366,7 @@ impl CommonTableExpression for Merge {
             }
             table.table_name(f)?;
             write!(f, "\n    on\n      __cte_")?;
-            self.table_name(f)?;
+            first.table_name(f)?;
             write!(f, "__entity = __cte_")?;
             table.table_name(f)?;
             write!(f, "__entity")?;
@@ -192,6 +378,18 @@ impl CommonTableExpression for Merge {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.tables
     }
+
+    fn include_deleted(&mut self) {
+        for inner in self.tables.iter_mut() {
+            inner.include_deleted();
+        }
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        for inner in self.tables.iter_mut() {
+            inner.as_of(timestamp);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -221,13 +419,15 @@ impl CommonTableExpression for Union {
         Ok(())
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> Result {
         write!(f, "    select\n      ")?;
         write!(f, "__cte_")?;
         self.table_name(f)?;
         write!(f, "__entity")?;
         self.columns(f)?;
-        write!(f, "\n    from\n      (select ?1 as __cte_")?;
+        write!(f, "\n    from\n      (select ")?;
+        placeholders.next(f)?;
+        write!(f, " as __cte_")?;
         self.table_name(f)?;
         write!(f, "__entity)\n    as\n      __cte_root")?;
 
@@ -247,6 +447,18 @@ impl CommonTableExpression for Union {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.tables
     }
+
+    fn include_deleted(&mut self) {
+        for inner in self.tables.iter_mut() {
+            inner.include_deleted();
+        }
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        for inner in self.tables.iter_mut() {
+            inner.as_of(timestamp);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -265,7 +477,7 @@ impl CommonTableExpression for Include {
         self.inner[0].columns(f)
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
         write!(f, "    select\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "__entity as __cte_")?;
@@ -277,7 +489,7 @@ impl CommonTableExpression for Include {
         write!(f, "\n    inner join\n      __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "\n    on\n      __cte_")?;
-        self.table_name(f)?;
+        self.inner[0].table_name(f)?;
         write!(f, "__entity = __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "__entity")
@@ -286,6 +498,18 @@ impl CommonTableExpression for Include {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.inner
     }
+
+    fn include_deleted(&mut self) {
+        for inner in self.inner.iter_mut() {
+            inner.include_deleted();
+        }
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        for inner in self.inner.iter_mut() {
+            inner.as_of(timestamp);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -304,8 +528,8 @@ impl CommonTableExpression for Exclude {
         self.inner[0].columns(f)
     }
 
-    fn serialize(&self, f: &mut dyn Write) -> Result {
-        write!(f, "{}", "    select\n      __cte_")?;
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
+        write!(f, "    select\n      __cte_")?;
         self.inner[0].table_name(f)?;
         write!(f, "__entity as __cte_")?;
         self.table_name(f)?;
@@ -316,7 +540,7 @@ impl CommonTableExpression for Exclude {
         write!(f, "\n    left join\n      __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "\n    on\n      __cte_")?;
-        self.table_name(f)?;
+        self.inner[0].table_name(f)?;
         write!(f, "__entity = __cte_")?;
         self.inner[1].table_name(f)?;
         write!(f, "__entity\n    where __cte_")?;
@@ -327,74 +551,380 @@ impl CommonTableExpression for Exclude {
     fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
         &self.inner
     }
+
+    fn include_deleted(&mut self) {
+        for inner in self.inner.iter_mut() {
+            inner.include_deleted();
+        }
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        for inner in self.inner.iter_mut() {
+            inner.as_of(timestamp);
+        }
+    }
 }
 
-pub(crate) fn serialize(
-    cte: &dyn CommonTableExpression,
-) -> ::core::result::Result<String, std::fmt::Error> {
-    let mut ctes = BTreeSet::new();
+/// Joins the components of the entity referenced by a column of the first
+/// expression, which is kept whether or not the reference can be followed.
+///
+/// The columns of the referenced expression are renamed, since the same
+/// components may appear on both sides of the join.
+#[derive(Debug)]
+pub struct JoinRef {
+    pub inner: [Box<dyn CommonTableExpression>; 2],
+    pub column: &'static str,
+}
+
+impl JoinRef {
+    /// Columns of the referenced expression, starting with its entity column.
+    fn referenced_columns(&self) -> ::core::result::Result<Vec<String>, std::fmt::Error> {
+        let mut entity = String::new();
+        self.inner[1].entity_column(&mut entity)?;
+
+        let mut columns = column_names(self.inner[1].as_ref())?;
+        columns.insert(0, entity);
+
+        Ok(columns)
+    }
+
+    fn alias(&self, column: &str) -> String {
+        format!(
+            "{reference}__{column}",
+            reference = self.column,
+            column = column.trim_start_matches("__cte_")
+        )
+    }
+}
+
+impl CommonTableExpression for JoinRef {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        self.inner[0].table_name(f)?;
+        write!(f, "_ref_{}_", self.column.trim_start_matches("__cte_"))?;
+        self.inner[1].table_name(f)
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        self.inner[0].columns(f)?;
+
+        for column in self.referenced_columns()? {
+            write!(f, ",\n      {}", self.alias(&column))?;
+        }
+
+        Ok(())
+    }
+
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
+        // Both sides are aliased, since they may be the same expression.
+        write!(f, "    select\n      __enorm_referencing.*")?;
 
-    #[derive(Eq, Ord)]
-    struct SerializedExpression {
-        name: String,
-        contents: String,
+        for column in self.referenced_columns()? {
+            write!(
+                f,
+                ",\n      __enorm_referenced.{column} as {alias}",
+                alias = self.alias(&column)
+            )?;
+        }
+
+        write!(f, "\n    from\n      __cte_")?;
+        self.inner[0].table_name(f)?;
+        write!(f, " as __enorm_referencing\n    left join\n      __cte_")?;
+        self.inner[1].table_name(f)?;
+        write!(
+            f,
+            " as __enorm_referenced\n    on\n      __enorm_referenced."
+        )?;
+        self.inner[1].entity_column(f)?;
+        write!(f, " = __enorm_referencing.{}", self.column)
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &self.inner
     }
 
-    impl PartialEq for SerializedExpression {
-        fn eq(&self, other: &Self) -> bool {
-            self.name == other.name
+    fn include_deleted(&mut self) {
+        for inner in self.inner.iter_mut() {
+            inner.include_deleted();
         }
     }
 
-    impl PartialOrd for SerializedExpression {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-            self.name.partial_cmp(&other.name)
+    fn as_of(&mut self, timestamp: i64) {
+        for inner in self.inner.iter_mut() {
+            inner.as_of(timestamp);
         }
     }
 
-    fn serialize_into(
-        cte: &dyn CommonTableExpression,
-        ctes: &mut BTreeSet<SerializedExpression>,
-    ) -> Result {
-        let mut serialized = SerializedExpression {
-            name: String::new(),
-            contents: String::new(),
+    fn entity_column(&self, f: &mut dyn Write) -> Result {
+        self.inner[0].entity_column(f)
+    }
+}
+
+/// Direction in which a [`Recursive`] expression follows references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Traverse {
+    /// Entities referencing the root, the entities referencing those, and so on.
+    Descendants,
+    /// The entity referenced by the root, the entity referenced by that, and so on.
+    Ancestors,
+}
+
+/// Entities reachable from a root entity by repeatedly following the entity
+/// references held by a column of a component table, along with their depth.
+///
+/// The root entity is bound as the only parameter, and isn't included itself.
+/// Entities are listed once for every path leading to them, see [`Nearest`].
+///
+/// References forming a cycle are followed until the depth reaches the number
+/// of rows of the table, which is the length of the longest path without one,
+/// unless limited further by `max_depth`.
+#[derive(Debug)]
+pub struct Recursive {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub direction: Traverse,
+    pub max_depth: Option<u32>,
+    /// Don't follow references of tombstoned soft-deleted components.
+    pub exclude_deleted: bool,
+    /// Whether the component holding the references keeps a history table.
+    pub history: bool,
+    /// Follow the references as they were at this timestamp.
+    pub as_of: Option<i64>,
+}
+
+impl CommonTableExpression for Recursive {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        let direction = match self.direction {
+            Traverse::Descendants => "descendants",
+            Traverse::Ancestors => "ancestors",
         };
 
-        cte.table_name(&mut serialized.name)?;
+        write!(f, "{direction}_{}_{}", self.table, self.column)
+    }
 
-        if !ctes.contains(&serialized) {
-            for dependency in cte.dependencies() {
-                serialize_into(dependency.as_ref(), ctes)?;
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        write!(f, ",\n      {DEPTH_COLUMN}")
+    }
+
+    fn serialize(&self, f: &mut dyn Write, placeholders: &mut Placeholders) -> Result {
+        let mut name = String::new();
+        self.table_name(&mut name)?;
+
+        let (table, column) = (self.table, self.column);
+
+        // Past references are read from the history table, which holds no tombstones.
+        let mut source = String::new();
+        let mut deleted = String::new();
+        match self.as_of {
+            Some(timestamp) => {
+                write!(source, "(\n      select entity, {column} from ")?;
+                snapshot_rows(&mut source, table, timestamp)?;
+                write!(source, "\n    ) as {table}")?;
             }
+            None => {
+                source.push_str(table);
 
-            write!(
-                serialized.contents,
-                "  __cte_{table_name} as (\n",
-                table_name = serialized.name
-            )
-            .unwrap();
-
-            cte.serialize(&mut serialized.contents)?;
-            serialized.contents.push_str("\n  )");
-            ctes.insert(serialized);
+                if self.exclude_deleted {
+                    write!(deleted, " and {table}.{DELETED_AT_COLUMN} is null")?;
+                }
+            }
         }
+
+        // Descendants are found by their references to the previous level,
+        // while ancestors are the references of the previous level.
+        let (selected, matched, anchor) = match self.direction {
+            Traverse::Descendants => ("entity", column, column),
+            Traverse::Ancestors => (column, "entity", "entity"),
+        };
+
+        write!(
+            f,
+            "    select\n      {table}.{selected} as __cte_{name}__entity,\n      1 as {DEPTH_COLUMN}\n    from\n      {source}\n    where\n      {table}.{selected} is not null{deleted} and {table}.{anchor} = "
+        )?;
+        placeholders.next(f)?;
+
+        write!(
+            f,
+            "\n    union all\n    select\n      {table}.{selected},\n      __cte_{name}.{DEPTH_COLUMN} + 1\n    from\n      {source}\n    inner join\n      __cte_{name}\n    on\n      {table}.{matched} = __cte_{name}.__cte_{name}__entity\n    where\n      {table}.{selected} is not null{deleted} and __cte_{name}.{DEPTH_COLUMN} < (select count(*) from {source})"
+        )?;
+
+        if let Some(max_depth) = self.max_depth {
+            write!(f, " and __cte_{name}.{DEPTH_COLUMN} < {max_depth}")?;
+        }
+
         Ok(())
     }
 
-    serialize_into(cte, &mut ctes)?;
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &[]
+    }
+
+    fn recursive(&self) -> bool {
+        true
+    }
+
+    fn include_deleted(&mut self) {
+        self.exclude_deleted = false;
+    }
 
-    let mut statement = String::from("with\n");
-    for (index, serialized_cte) in ctes.into_iter().enumerate() {
-        if index != 0 {
-            statement.push_str(",\n");
+    fn as_of(&mut self, timestamp: i64) {
+        if self.history {
+            self.as_of = Some(timestamp);
         }
-        statement.push_str(&serialized_cte.contents);
     }
+}
+
+/// Entities found by a [`Recursive`] expression, listed once along with the
+/// depth of the shortest path leading to them.
+#[derive(Debug)]
+pub struct Nearest {
+    pub inner: [Box<dyn CommonTableExpression>; 1],
+}
+
+impl CommonTableExpression for Nearest {
+    fn table_name(&self, f: &mut dyn Write) -> Result {
+        write!(f, "nearest_")?;
+        self.inner[0].table_name(f)
+    }
+
+    fn columns(&self, f: &mut dyn Write) -> Result {
+        self.inner[0].columns(f)
+    }
+
+    fn serialize(&self, f: &mut dyn Write, _: &mut Placeholders) -> Result {
+        let mut inner = String::new();
+        self.inner[0].table_name(&mut inner)?;
+
+        write!(f, "    select\n      __cte_{inner}__entity as ")?;
+        self.entity_column(f)?;
+        write!(
+            f,
+            ",\n      min({DEPTH_COLUMN}) as {DEPTH_COLUMN}\n    from\n      __cte_{inner}\n    group by\n      __cte_{inner}__entity"
+        )
+    }
+
+    fn dependencies(&self) -> &[Box<dyn CommonTableExpression>] {
+        &self.inner
+    }
+
+    fn include_deleted(&mut self) {
+        self.inner[0].include_deleted();
+    }
+
+    fn as_of(&mut self, timestamp: i64) {
+        self.inner[0].as_of(timestamp);
+    }
+}
+
+/// Names of the columns of the expression, excluding its entity column.
+pub(crate) fn column_names(
+    cte: &dyn CommonTableExpression,
+) -> ::core::result::Result<Vec<String>, std::fmt::Error> {
+    let mut columns = String::new();
+    cte.columns(&mut columns)?;
+
+    Ok(columns
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Name of the pseudo-column holding the Entity ID in statements produced
+/// by [`serialize_list`].
+pub(crate) const ENTITY_COLUMN: &str = "__enorm_entity";
+
+/// Name of the column holding the depth of entities found by [`Recursive`].
+pub(crate) const DEPTH_COLUMN: &str = "__enorm_depth";
+
+/// Serialize the expression and all its dependencies into a single `with` statement.
+pub(crate) fn serialize(
+    cte: &dyn CommonTableExpression,
+    placeholders: &mut Placeholders,
+) -> ::core::result::Result<String, std::fmt::Error> {
+    let mut statement = serialize_expressions(cte, placeholders)?;
 
     statement.push_str("\nselect * from __cte_");
     cte.table_name(&mut statement)?;
-    statement.push_str("\n");
+    statement.push('\n');
+
+    Ok(statement)
+}
+
+/// Like [`serialize`], but additionally exposes the Entity ID as the trailing
+/// [`ENTITY_COLUMN`], regardless of which expression it originates from.
+pub(crate) fn serialize_list(
+    cte: &dyn CommonTableExpression,
+    placeholders: &mut Placeholders,
+) -> ::core::result::Result<String, std::fmt::Error> {
+    let mut statement = serialize_expressions(cte, placeholders)?;
+
+    statement.push_str("\nselect * from (\n  select __cte_");
+    cte.table_name(&mut statement)?;
+    statement.push_str(".*, ");
+    cte.entity_column(&mut statement)?;
+    write!(statement, " as {ENTITY_COLUMN} from __cte_")?;
+    cte.table_name(&mut statement)?;
+    statement.push_str("\n) as __enorm_entities\n");
+
+    Ok(statement)
+}
+
+/// Serialize the `with` clause defining the expression and its dependencies.
+///
+/// Expressions are emitted after all of their dependencies, since databases
+/// like Postgres and MySQL only allow references to preceding expressions.
+fn serialize_expressions(
+    cte: &dyn CommonTableExpression,
+    placeholders: &mut Placeholders,
+) -> ::core::result::Result<String, std::fmt::Error> {
+    struct SerializedExpressions {
+        names: HashSet<String>,
+        contents: Vec<String>,
+        recursive: bool,
+    }
+
+    fn serialize_into(
+        cte: &dyn CommonTableExpression,
+        ctes: &mut SerializedExpressions,
+        placeholders: &mut Placeholders,
+    ) -> Result {
+        let mut name = String::new();
+        cte.table_name(&mut name)?;
+
+        if !ctes.names.contains(&name) {
+            for dependency in cte.dependencies() {
+                serialize_into(dependency.as_ref(), ctes, placeholders)?;
+            }
+
+            let mut contents = String::new();
+            writeln!(contents, "  __cte_{table_name} as (", table_name = name)?;
+
+            cte.serialize(&mut contents, placeholders)?;
+            contents.push_str("\n  )");
+
+            ctes.names.insert(name);
+            ctes.contents.push(contents);
+            ctes.recursive |= cte.recursive();
+        }
+        Ok(())
+    }
+
+    let mut ctes = SerializedExpressions {
+        names: HashSet::new(),
+        contents: Vec::new(),
+        recursive: false,
+    };
+
+    serialize_into(cte, &mut ctes, placeholders)?;
+
+    // The keyword applies to the whole clause, rather than individual expressions.
+    let mut statement = String::from(if ctes.recursive {
+        "with recursive\n"
+    } else {
+        "with\n"
+    });
+    statement.push_str(&ctes.contents.join(",\n"));
 
     Ok(statement)
 }
@@ -464,11 +994,17 @@ fn test_build() {
     let positions = Extract {
         table: "positions",
         columns: &["x", "y"],
+        exclude_deleted: false,
+        history: false,
+        as_of: None,
     };
 
     let names = Extract {
         table: "named",
         columns: &["first", "last"],
+        exclude_deleted: false,
+        history: false,
+        as_of: None,
     };
 
     let merge = Merge {
@@ -481,6 +1017,9 @@ fn test_build() {
             Box::new(Extract {
                 table: "address",
                 columns: &[],
+                exclude_deleted: false,
+                history: false,
+                as_of: None,
             }),
         ],
     };
@@ -488,11 +1027,217 @@ fn test_build() {
     let parents = Extract {
         table: "parents",
         columns: &[],
+        exclude_deleted: false,
+        history: false,
+        as_of: None,
     };
 
     let include = Include {
         inner: [Box::new(exclude), Box::new(parents)],
     };
 
-    println!("{}", serialize(&include).unwrap());
+    struct Numbered;
+    impl crate::archetype::DatabasePlaceholder for Numbered {}
+
+    println!(
+        "{}",
+        serialize(&include, &mut Placeholders::new::<Numbered>()).unwrap()
+    );
+}
+
+#[test]
+fn test_dependency_order() {
+    struct Dollar;
+    impl crate::archetype::DatabasePlaceholder for Dollar {
+        const PLACEHOLDER: char = '$';
+    }
+
+    // Lowercase table names sort after "merge_", so dependencies must
+    // be emitted in order of use, rather than alphabetically.
+    let merge = Merge {
+        tables: vec![
+            Box::new(Extract {
+                table: "positions",
+                columns: &["x", "y"],
+                exclude_deleted: false,
+                history: false,
+                as_of: None,
+            }),
+            Box::new(Extract {
+                table: "named",
+                columns: &["first"],
+                exclude_deleted: false,
+                history: false,
+                as_of: None,
+            }),
+        ],
+    };
+
+    let single = Single {
+        inner: [Box::new(merge)],
+        entity: (),
+    };
+
+    let mut placeholders = Placeholders::new::<Dollar>();
+    let sql = serialize(&single, &mut placeholders).unwrap();
+
+    let positions = sql.find("__cte_positions as").unwrap();
+    let named = sql.find("__cte_named as").unwrap();
+    let merged = sql.find("__cte_merge_positions_named as").unwrap();
+    let single = sql.find("__cte_single_merge_positions_named as").unwrap();
+
+    assert!(positions < merged && named < merged && merged < single);
+    assert!(sql.contains("__cte_merge_positions_named__entity = $1"));
+    assert_eq!(placeholders.count(), 1);
+}
+
+#[test]
+fn test_recursive() {
+    struct Numbered;
+    impl crate::archetype::DatabasePlaceholder for Numbered {}
+
+    let names = Extract {
+        table: "names",
+        columns: &["name"],
+        exclude_deleted: false,
+        history: false,
+        as_of: None,
+    };
+
+    let merge = Merge {
+        tables: vec![
+            Box::new(Nearest {
+                inner: [Box::new(Recursive {
+                    table: "parents",
+                    column: "parent",
+                    direction: Traverse::Descendants,
+                    max_depth: Some(3),
+                    exclude_deleted: true,
+                    history: true,
+                    as_of: None,
+                })],
+            }),
+            Box::new(names),
+        ],
+    };
+
+    let mut placeholders = Placeholders::new::<Numbered>();
+    let sql = serialize(&merge, &mut placeholders).unwrap();
+
+    // Databases require the keyword once, at the start of the clause.
+    assert!(sql.starts_with("with recursive\n"));
+    assert!(sql.contains("__cte_descendants_parents_parent.__enorm_depth < 3"));
+    assert_eq!(placeholders.count(), 1);
+
+    // Cycles end once the depth exceeds the length of any path without one,
+    // and entities found along several paths are listed once.
+    assert!(sql.contains(
+        "__cte_descendants_parents_parent.__enorm_depth < (select count(*) from parents)"
+    ));
+    assert!(sql.contains("group by\n      __cte_descendants_parents_parent__entity"));
+    assert!(
+        sql.find("__cte_descendants_parents_parent as").unwrap()
+            < sql
+                .find("__cte_nearest_descendants_parents_parent as")
+                .unwrap()
+    );
+
+    let plain = serialize(
+        merge.tables[1].as_ref(),
+        &mut Placeholders::new::<Numbered>(),
+    )
+    .unwrap();
+    assert!(plain.starts_with("with\n"));
+
+    // Tombstoned references aren't followed, unless asked to.
+    let mut recursive = Recursive {
+        table: "parents",
+        column: "parent",
+        direction: Traverse::Ancestors,
+        max_depth: None,
+        exclude_deleted: true,
+        history: true,
+        as_of: None,
+    };
+    let deleted = format!("parents.{DELETED_AT_COLUMN} is null");
+
+    let sql = serialize(&recursive, &mut Placeholders::new::<Numbered>()).unwrap();
+    assert_eq!(sql.matches(&deleted).count(), 2);
+
+    recursive.include_deleted();
+    let sql = serialize(&recursive, &mut Placeholders::new::<Numbered>()).unwrap();
+    assert!(!sql.contains(&deleted));
+
+    // Past references are read from the history table.
+    recursive.as_of(1);
+    let sql = serialize(&recursive, &mut Placeholders::new::<Numbered>()).unwrap();
+    assert_eq!(sql.matches("parents_history as __enorm_history").count(), 3);
+}
+
+#[test]
+fn test_include_deleted() {
+    struct Numbered;
+    impl crate::archetype::DatabasePlaceholder for Numbered {}
+
+    let mut exclude = Exclude {
+        inner: [
+            Box::new(Extract {
+                table: "accounts",
+                columns: &["owner"],
+                exclude_deleted: true,
+                history: false,
+                as_of: None,
+            }),
+            Box::new(Extract {
+                table: "frozen",
+                columns: &[],
+                exclude_deleted: true,
+                history: false,
+                as_of: None,
+            }),
+        ],
+    };
+
+    let sql = serialize(&exclude, &mut Placeholders::new::<Numbered>()).unwrap();
+    assert_eq!(sql.matches("deleted_at is null").count(), 2);
+
+    // Tombstones are included throughout the expression, not just at its root.
+    exclude.include_deleted();
+
+    let sql = serialize(&exclude, &mut Placeholders::new::<Numbered>()).unwrap();
+    assert!(!sql.contains("deleted_at"));
+}
+
+#[test]
+fn test_as_of() {
+    struct Numbered;
+    impl crate::archetype::DatabasePlaceholder for Numbered {}
+
+    let mut merge = Merge {
+        tables: vec![
+            Box::new(Extract {
+                table: "scores",
+                columns: &["points"],
+                exclude_deleted: false,
+                history: true,
+                as_of: None,
+            }),
+            Box::new(Extract {
+                table: "names",
+                columns: &["name"],
+                exclude_deleted: false,
+                history: false,
+                as_of: None,
+            }),
+        ],
+    };
+
+    merge.as_of(1_000);
+
+    // Only components keeping a history are read from their history table.
+    let sql = serialize(&merge, &mut Placeholders::new::<Numbered>()).unwrap();
+    assert!(sql.contains("from\n      scores_history"));
+    assert!(sql.contains("__enorm_changed_at <= 1000"));
+    assert!(sql.contains("from\n      names"));
+    assert!(!sql.contains("names_history"));
 }
@@ -0,0 +1,595 @@
+use std::fmt::Write as _;
+
+use sqlx::{
+    query::Query, ColumnIndex, Database, Executor, IntoArguments, Row as _, TypeInfo as _,
+    ValueRef as _,
+};
+
+use crate::{
+    archetype::{DatabasePlaceholder, Placeholders},
+    component::{ColumnDefinition, Component, DatabaseColumnType, TAG_COLUMN},
+    cte::{ExtractColumns, Single},
+    entity::RowsAffected,
+    error::Error,
+    history::{self, DatabaseClock, CHANGED_AT_COLUMN, HISTORY_SUFFIX, OPERATION_COLUMN},
+    registry::RegisteredTable,
+    soft_delete::DELETED_AT_COLUMN,
+    versioned::VERSION_COLUMN,
+};
+
+/// Value of a column of a [`DynamicComponent`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// Kind of [`Value`] held by the columns of a type, see
+/// [`DatabaseColumnType::value_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    Integer,
+    Real,
+    Text,
+    Blob,
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Blob(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Value::Null)
+    }
+}
+
+/// Columns of a single component, along with their values.
+pub type DynamicRow = Vec<(&'static str, Value)>;
+
+/// Databases whose columns can be read and written as [`Value`]s.
+pub trait DynamicDatabase: Database + DatabasePlaceholder + DatabaseClock {
+    /// Bind the value to the next placeholder of the query.
+    fn bind_value<'q>(
+        query: Query<'q, Self, <Self as Database>::Arguments<'q>>,
+        value: &Value,
+    ) -> Query<'q, Self, <Self as Database>::Arguments<'q>>;
+
+    /// Decode the column at `index` of the row, according to the type of its definition.
+    ///
+    /// Columns whose types have no [`ValueKind`], such as Postgres' `UUID` or
+    /// `TIMESTAMPTZ`, fail to decode.
+    fn decode_value(
+        row: &<Self as Database>::Row,
+        index: usize,
+        column: &ColumnDefinition<Self>,
+    ) -> Result<Value, sqlx::Error>;
+}
+
+impl<DB> DynamicDatabase for DB
+where
+    DB: Database + DatabasePlaceholder + DatabaseColumnType + DatabaseClock,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    for<'q> bool: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'q> f64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'q> String: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'q> Vec<u8>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'r> bool: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> i64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> i32: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> i16: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> f64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> f32: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    for<'r> Vec<u8>: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+{
+    fn bind_value<'q>(
+        query: Query<'q, Self, <Self as Database>::Arguments<'q>>,
+        value: &Value,
+    ) -> Query<'q, Self, <Self as Database>::Arguments<'q>> {
+        match value {
+            // Nulls are written as literals, since their type can't be inferred.
+            Value::Null => query,
+            Value::Bool(value) => query.bind(*value),
+            Value::Integer(value) => query.bind(*value),
+            Value::Real(value) => query.bind(*value),
+            Value::Text(value) => query.bind(value.clone()),
+            Value::Blob(value) => query.bind(value.clone()),
+        }
+    }
+
+    fn decode_value(
+        row: &<Self as Database>::Row,
+        index: usize,
+        column: &ColumnDefinition<Self>,
+    ) -> Result<Value, sqlx::Error> {
+        if row.try_get_raw(index)?.is_null() {
+            return Ok(Value::Null);
+        }
+
+        let Some(kind) = DB::value_kind(column.type_info()) else {
+            return Err(sqlx::Error::Decode(
+                format!(
+                    "column {} has type {}, which can't be read as a value",
+                    column.name(),
+                    column.type_info().name()
+                )
+                .into(),
+            ));
+        };
+
+        // Integers and floats are decoded using the narrower types as well,
+        // since Postgres doesn't widen them implicitly.
+        match kind {
+            ValueKind::Bool => row.try_get::<bool, _>(index).map(Value::Bool),
+            ValueKind::Integer => row
+                .try_get::<i64, _>(index)
+                .or_else(|_| row.try_get::<i32, _>(index).map(i64::from))
+                .or_else(|_| row.try_get::<i16, _>(index).map(i64::from))
+                .map(Value::Integer),
+            ValueKind::Real => row
+                .try_get::<f64, _>(index)
+                .or_else(|_| row.try_get::<f32, _>(index).map(f64::from))
+                .map(Value::Real),
+            ValueKind::Text => row.try_get::<String, _>(index).map(Value::Text),
+            ValueKind::Blob => row.try_get::<Vec<u8>, _>(index).map(Value::Blob),
+        }
+    }
+}
+
+/// Component whose table and columns are only known at runtime.
+///
+/// Rows are read and written as lists of column names and [`Value`]s, which
+/// makes it possible to access components by table name, for instance from a
+/// scripting language or an administrative interface.
+///
+/// Components described using [`DynamicComponent::of`] are written like their
+/// typed counterparts: tombstones of soft-deleted components are neither read
+/// nor updated, and changes are recorded in the history table of components
+/// keeping one. The variant of enum components is held by their [`TAG_COLUMN`].
+///
+/// Versioned components are read along with their [`VERSION_COLUMN`], which
+/// updates must include. Like updates of [`Versioned`](crate::prelude::Versioned)
+/// components, they fail with [`Error::Conflict`] if the version changed since,
+/// and increment it otherwise.
+///
+/// ```ignore
+/// let name = backend.component("Name").unwrap();
+///
+/// backend.insert_dynamic(&name, &1, &[("column0", "Jimothy".into())]).await?;
+/// let row = backend.get_dynamic(&name, &1).await?;
+/// ```
+pub struct DynamicComponent<DB: Database> {
+    table: &'static str,
+    columns: Vec<ColumnDefinition<DB>>,
+    soft_delete: bool,
+    /// The version column of versioned components.
+    version: Option<ColumnDefinition<DB>>,
+    history: bool,
+}
+
+impl<DB: Database> std::fmt::Debug for DynamicComponent<DB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicComponent")
+            .field("table", &self.table)
+            .field(
+                "columns",
+                &self
+                    .columns
+                    .iter()
+                    .map(ColumnDefinition::name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<DB: Database> DynamicComponent<DB> {
+    pub fn new(table: &'static str, columns: Vec<ColumnDefinition<DB>>) -> Self {
+        DynamicComponent {
+            table,
+            columns,
+            soft_delete: false,
+            version: None,
+            history: false,
+        }
+    }
+
+    /// Describe the component `T`, using its table and column definitions.
+    pub fn of<T: Component<DB>>() -> Self
+    where
+        String: sqlx::Type<DB>,
+        i64: sqlx::Type<DB>,
+    {
+        Self::registered(&RegisteredTable::of::<T>())
+    }
+
+    /// Describe the component stored in the registered table, including the
+    /// tag column of enum components.
+    pub(crate) fn registered(table: &RegisteredTable<DB>) -> Self
+    where
+        String: sqlx::Type<DB>,
+        i64: sqlx::Type<DB>,
+    {
+        let mut columns = (table.columns)();
+
+        if table.tagged {
+            columns.insert(
+                0,
+                ColumnDefinition {
+                    name: TAG_COLUMN,
+                    type_info: <String as sqlx::Type<DB>>::type_info(),
+                    nullable: false,
+                    indexed: false,
+                    unique: false,
+                    references: None,
+                },
+            );
+        }
+
+        DynamicComponent {
+            table: table.name,
+            columns,
+            soft_delete: table.soft_delete,
+            version: table.versioned.then(|| ColumnDefinition {
+                name: VERSION_COLUMN,
+                type_info: <i64 as sqlx::Type<DB>>::type_info(),
+                nullable: false,
+                indexed: false,
+                unique: false,
+                references: None,
+            }),
+            history: table.history,
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        self.table
+    }
+
+    pub fn columns(&self) -> &[ColumnDefinition<DB>] {
+        &self.columns
+    }
+
+    /// Look up the definition of the named column.
+    fn column(&self, name: &str) -> Result<&ColumnDefinition<DB>, Error> {
+        self.columns
+            .iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| Error::UnknownColumn(name.to_string()))
+    }
+
+    /// Columns which are read, followed by the version of versioned components.
+    fn read_columns(&self) -> impl Iterator<Item = &ColumnDefinition<DB>> {
+        self.columns.iter().chain(&self.version)
+    }
+
+    fn extract(&self) -> ExtractColumns {
+        ExtractColumns {
+            table: self.table,
+            columns: self.read_columns().map(ColumnDefinition::name).collect(),
+            exclude_deleted: self.soft_delete,
+        }
+    }
+
+    /// Decode the columns following the entity column of the row.
+    fn decode(&self, row: &<DB as Database>::Row) -> Result<DynamicRow, Error>
+    where
+        DB: DynamicDatabase,
+    {
+        self.read_columns()
+            .enumerate()
+            .map(|(index, column)| Ok((column.name(), DB::decode_value(row, index + 1, column)?)))
+            .collect()
+    }
+
+    pub(crate) async fn get<'e, EntityId, E>(
+        &self,
+        executor: E,
+        entity: &EntityId,
+    ) -> Result<DynamicRow, Error>
+    where
+        DB: DynamicDatabase,
+        E: Executor<'e, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        let mut placeholders = Placeholders::new::<DB>();
+        let sql = crate::cte::serialize(
+            &Single {
+                inner: [Box::new(self.extract())],
+                entity,
+            },
+            &mut placeholders,
+        )
+        .unwrap();
+
+        let mut query = sqlx::query(&sql);
+        for _ in 0..placeholders.count() {
+            query = query.bind(entity);
+        }
+
+        let row = query.fetch_one(executor).await?;
+
+        self.decode(&row)
+    }
+
+    pub(crate) async fn list<'e, EntityId, E>(
+        &self,
+        executor: E,
+    ) -> Result<Vec<(EntityId, DynamicRow)>, Error>
+    where
+        DB: DynamicDatabase,
+        E: Executor<'e, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        usize: ColumnIndex<<DB as Database>::Row>,
+        EntityId: for<'r> sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        let sql = crate::cte::serialize(&self.extract(), &mut Placeholders::new::<DB>()).unwrap();
+
+        sqlx::query(&sql)
+            .fetch_all(executor)
+            .await?
+            .iter()
+            .map(|row| Ok((row.try_get::<EntityId, _>(0)?, self.decode(row)?)))
+            .collect()
+    }
+
+    pub(crate) async fn insert<EntityId>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        entity: &EntityId,
+        row: &[(&str, Value)],
+    ) -> Result<(), Error>
+    where
+        DB: DynamicDatabase,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        // New components always start at version 1.
+        let row: Vec<_> = row
+            .iter()
+            .filter(|(name, _)| self.version.is_none() || *name != VERSION_COLUMN)
+            .collect();
+
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut columns = String::from("entity");
+        let mut values = String::new();
+        placeholders.next(&mut values).unwrap();
+
+        for (name, value) in &row {
+            write!(columns, ", {}", self.column(name)?.name()).unwrap();
+            values.push_str(", ");
+            write_value(&mut values, value, &mut placeholders);
+        }
+
+        let sql = format!("insert into {}({columns}) values({values})", self.table);
+
+        let mut query = sqlx::query(&sql).bind(entity);
+        for (_, value) in &row {
+            query = DB::bind_value(query, value);
+        }
+
+        query.execute(&mut *connection).await?;
+
+        self.record(connection, entity, "insert").await
+    }
+
+    /// Update the given columns, returning whether a component was updated.
+    ///
+    /// Tombstones of soft-deleted components aren't updated. Versioned
+    /// components are only updated if their version matches the one given
+    /// along with the columns.
+    pub(crate) async fn update<EntityId>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        entity: &EntityId,
+        row: &[(&str, Value)],
+    ) -> Result<bool, Error>
+    where
+        DB: DynamicDatabase,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        for<'r> i64: sqlx::Decode<'r, DB>,
+        usize: ColumnIndex<<DB as Database>::Row>,
+        <DB as Database>::QueryResult: RowsAffected,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        let version = match &self.version {
+            Some(_) => match row.iter().find(|(name, _)| *name == VERSION_COLUMN) {
+                Some((_, Value::Integer(version))) => Some(*version),
+                _ => return Err(Error::InvalidQuery(
+                    "updates of versioned components must include the version they were read at",
+                )),
+            },
+            None => None,
+        };
+
+        let row: Vec<_> = row
+            .iter()
+            .filter(|(name, _)| version.is_none() || *name != VERSION_COLUMN)
+            .collect();
+
+        if row.is_empty() {
+            return Ok(false);
+        }
+
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut sql = format!("update {} set ", self.table);
+
+        for (i, (name, value)) in row.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            write!(sql, "{} = ", self.column(name)?.name()).unwrap();
+            write_value(&mut sql, value, &mut placeholders);
+        }
+
+        if version.is_some() {
+            write!(sql, ", {VERSION_COLUMN} = {VERSION_COLUMN} + 1").unwrap();
+        }
+
+        sql.push_str(" where entity = ");
+        placeholders.next(&mut sql).unwrap();
+
+        if version.is_some() {
+            write!(sql, " and {VERSION_COLUMN} = ").unwrap();
+            placeholders.next(&mut sql).unwrap();
+        }
+
+        if self.soft_delete {
+            write!(sql, " and {DELETED_AT_COLUMN} is null").unwrap();
+        }
+
+        let mut query = sqlx::query(&sql);
+        for (_, value) in &row {
+            query = DB::bind_value(query, value);
+        }
+
+        query = query.bind(entity);
+        if let Some(version) = version {
+            query = query.bind(version);
+        }
+
+        if query.execute(&mut *connection).await?.rows_affected() == 0 {
+            // The version only conflicts if there is a component to update.
+            if version.is_some() && self.exists(connection, entity).await? {
+                return Err(Error::Conflict);
+            }
+
+            return Ok(false);
+        }
+
+        self.record(connection, entity, "update").await?;
+
+        Ok(true)
+    }
+
+    /// Whether the entity has the component, excluding tombstones.
+    async fn exists<EntityId>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        entity: &EntityId,
+    ) -> Result<bool, Error>
+    where
+        DB: DatabasePlaceholder,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'r> i64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+        usize: ColumnIndex<<DB as Database>::Row>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut sql = format!("select count(*) from {} where entity = ", self.table);
+        placeholders.next(&mut sql).unwrap();
+
+        if self.soft_delete {
+            write!(sql, " and {DELETED_AT_COLUMN} is null").unwrap();
+        }
+
+        let count = sqlx::query_scalar::<DB, i64>(&sql)
+            .bind(entity)
+            .fetch_one(connection)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Append the current state of the component to its history table, if it keeps one.
+    async fn record<EntityId>(
+        &self,
+        connection: &mut <DB as Database>::Connection,
+        entity: &EntityId,
+        operation: &str,
+    ) -> Result<(), Error>
+    where
+        DB: DatabasePlaceholder + DatabaseClock,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        if !self.history {
+            return Ok(());
+        }
+
+        let table = self.table;
+        let columns: String = self
+            .columns
+            .iter()
+            .map(|column| format!(", {}", column.name()))
+            .collect();
+
+        let qualified: String = self
+            .columns
+            .iter()
+            .map(|column| format!(", {table}.{}", column.name()))
+            .collect();
+
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut placeholder = String::new();
+        placeholders.next(&mut placeholder).unwrap();
+
+        let mut sql = format!(
+            "insert into {table}{HISTORY_SUFFIX}(entity{columns}, {OPERATION_COLUMN}, {CHANGED_AT_COLUMN}) select {table}.entity{qualified}, '{operation}', {} from {table} inner join {} using (entity)",
+            history::changed_at::<DB>(),
+            history::target(table, &placeholder),
+        );
+
+        if self.soft_delete {
+            write!(sql, " where {table}.{DELETED_AT_COLUMN} is null").unwrap();
+        }
+
+        sqlx::query(&sql).bind(entity).execute(connection).await?;
+
+        Ok(())
+    }
+}
+
+/// Write a placeholder for the value, or a literal if it is null.
+fn write_value(f: &mut String, value: &Value, placeholders: &mut Placeholders) {
+    match value {
+        Value::Null => f.push_str("null"),
+        _ => placeholders.next(f).unwrap(),
+    }
+}
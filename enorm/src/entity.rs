@@ -1,4 +1,6 @@
-use sqlx::{query::Query, Database};
+use sqlx::{query::Query, Database, Executor, IntoArguments};
+
+use crate::{error::Error, versioned::VersionedComponent};
 
 pub struct EntityPrefixedQuery<'q, DB, EntityId>
 where
@@ -6,6 +8,35 @@ where
 {
     pub queries: Vec<Query<'q, DB, <DB as Database>::Arguments<'q>>>,
     pub entity: EntityId,
+    /// Indices of the queries which must affect a row, along with the statement
+    /// checking whether their component exists, see [`EntityPrefixedQuery::guarded`].
+    pub guards: Vec<(usize, &'static str)>,
+}
+
+/// Number of rows affected by a statement, which sqlx only exposes per database.
+pub trait RowsAffected {
+    fn rows_affected(&self) -> u64;
+}
+
+#[cfg(feature = "sqlite")]
+impl RowsAffected for sqlx::sqlite::SqliteQueryResult {
+    fn rows_affected(&self) -> u64 {
+        self.rows_affected()
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl RowsAffected for sqlx::postgres::PgQueryResult {
+    fn rows_affected(&self) -> u64 {
+        self.rows_affected()
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl RowsAffected for sqlx::mysql::MySqlQueryResult {
+    fn rows_affected(&self) -> u64 {
+        self.rows_affected()
+    }
 }
 
 impl<'query, DB, EntityId> EntityPrefixedQuery<'query, DB, EntityId>
@@ -17,9 +48,27 @@ where
         EntityPrefixedQuery {
             queries: Vec::new(),
             entity,
+            guards: Vec::new(),
         }
     }
 
+    /// Queue a query writing to the table of the component `C`, which fails
+    /// with [`Error::Conflict`] unless it affects a row, or with
+    /// [`Error::NotFound`] if the entity doesn't have the component.
+    ///
+    /// Only honored by updates, where it is used to detect concurrent changes.
+    pub fn guarded<C: VersionedComponent<DB>>(
+        &mut self,
+        sql: &'static str,
+        f: impl Fn(
+            Query<'query, DB, <DB as Database>::Arguments<'query>>,
+        ) -> Query<'query, DB, <DB as Database>::Arguments<'query>>,
+    ) {
+        self.guards
+            .push((self.queries.len(), <C as VersionedComponent<DB>>::EXISTS));
+        self.query(sql, f)
+    }
+
     pub fn query(
         &mut self,
         sql: &'static str,
@@ -31,6 +80,51 @@ where
 
         self.queries.push(f(query));
     }
+
+    /// Execute the queries in order using the given connection.
+    pub async fn execute(self, connection: &mut <DB as Database>::Connection) -> Result<(), Error>
+    where
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    {
+        for query in self.queries {
+            query.execute(&mut *connection).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Execute the queries in order, failing if any guarded query affects no rows.
+    pub(crate) async fn execute_guarded(
+        self,
+        connection: &mut <DB as Database>::Connection,
+    ) -> Result<(), Error>
+    where
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        <DB as Database>::QueryResult: RowsAffected,
+    {
+        for (index, query) in self.queries.into_iter().enumerate() {
+            let result = query.execute(&mut *connection).await?;
+            if result.rows_affected() > 0 {
+                continue;
+            }
+
+            if let Some((_, exists)) = self.guards.iter().find(|(guard, _)| *guard == index) {
+                let component = sqlx::query(exists)
+                    .bind(self.entity.clone())
+                    .fetch_optional(&mut *connection)
+                    .await?;
+
+                return Err(match component {
+                    Some(_) => Error::Conflict,
+                    None => Error::NotFound,
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +138,7 @@ mod tests {
         let mut insert = EntityPrefixedQuery::<'_, Sqlite, _> {
             queries: vec![],
             entity: 12345,
+            guards: vec![],
         };
 
         insert.query("a", |query| query.bind(1));
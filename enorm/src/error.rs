@@ -0,0 +1,185 @@
+use sqlx::error::ErrorKind;
+
+/// Errors returned by [`Backend`](crate::backend::Backend) operations.
+///
+/// Errors returned by [`sqlx`] are sorted into broad categories, so callers can
+/// react to the likely cause of the failure without having to inspect
+/// database-specific error codes themselves. The original [`sqlx::Error`] is
+/// retained where available.
+#[derive(Debug)]
+pub enum Error {
+    /// A unique, foreign key, not-null or check constraint was violated.
+    ///
+    /// This is returned when inserting a component which the entity already has.
+    ConstraintViolation(sqlx::Error),
+    /// The table backing a component does not exist. Has the component been registered?
+    MissingComponentTable(sqlx::Error),
+    /// The entity does not have the requested components.
+    NotFound,
+    /// A column returned by the database could not be decoded.
+    Decode(sqlx::Error),
+    /// The connection to the database failed or could not be established.
+    Connection(sqlx::Error),
+    /// Any other error returned by the database.
+    Database(sqlx::Error),
+    /// A versioned component was changed after its version was read, so the
+    /// update was rejected.
+    Conflict,
+    /// A [`DynamicComponent`](crate::dynamic::DynamicComponent) has no column of the given name.
+    UnknownColumn(String),
+    /// The options of a [`List`](crate::backend::List) can't be combined, such
+    /// as paging [after](crate::backend::List::after) an entity while ordering
+    /// by a column, or a [`Migration`](crate::migration::Migration) can't be
+    /// applied to the table.
+    InvalidQuery(&'static str),
+    /// A stream of [changes](crate::backend::Backend::changes) fell behind by
+    /// more than [`CHANGES_BUFFER`](crate::changes::CHANGES_BUFFER) changes,
+    /// and ended rather than buffering any more of them.
+    Lagged,
+}
+
+impl Error {
+    /// Borrow the underlying [`sqlx::Error`], if any.
+    pub fn as_sqlx(&self) -> Option<&sqlx::Error> {
+        match self {
+            Error::ConstraintViolation(err)
+            | Error::MissingComponentTable(err)
+            | Error::Decode(err)
+            | Error::Connection(err)
+            | Error::Database(err) => Some(err),
+            Error::NotFound
+            | Error::Conflict
+            | Error::UnknownColumn(_)
+            | Error::InvalidQuery(_)
+            | Error::Lagged => None,
+        }
+    }
+}
+
+/// Detects errors caused by a table not existing, which the databases report
+/// using vendor-specific codes or messages.
+fn is_missing_table(err: &dyn sqlx::error::DatabaseError) -> bool {
+    match err.code().as_deref() {
+        // Postgres: undefined_table, MySQL: ER_NO_SUCH_TABLE
+        Some("42P01") | Some("42S02") => true,
+        // Sqlite reports all of these as the generic SQLITE_ERROR.
+        _ => err.message().starts_with("no such table"),
+    }
+}
+
+/// Detects constraint violations which sqlx doesn't classify, such as those
+/// raised by `on delete restrict` foreign keys.
+fn is_constraint_violation(err: &dyn sqlx::error::DatabaseError) -> bool {
+    match err.code().as_deref() {
+        // Postgres and MySQL report five character SQLSTATEs, where class 23
+        // covers integrity constraint violations.
+        Some(code) if code.len() == 5 && code.starts_with("23") => true,
+        // Sqlite extended result codes share the primary SQLITE_CONSTRAINT code.
+        Some(code) => code.parse::<i32>().is_ok_and(|code| code & 0xff == 19),
+        None => false,
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => Error::NotFound,
+            sqlx::Error::Database(ref database) => match database.kind() {
+                ErrorKind::UniqueViolation
+                | ErrorKind::ForeignKeyViolation
+                | ErrorKind::NotNullViolation
+                | ErrorKind::CheckViolation => Error::ConstraintViolation(err),
+                _ if is_constraint_violation(database.as_ref()) => Error::ConstraintViolation(err),
+                _ if is_missing_table(database.as_ref()) => Error::MissingComponentTable(err),
+                _ => Error::Database(err),
+            },
+            sqlx::Error::TypeNotFound { .. }
+            | sqlx::Error::ColumnIndexOutOfBounds { .. }
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::Decode(_) => Error::Decode(err),
+            sqlx::Error::Configuration(_)
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => Error::Connection(err),
+            err => Error::Database(err),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ConstraintViolation(err) => write!(f, "constraint violation: {err}"),
+            Error::MissingComponentTable(err) => write!(f, "missing component table: {err}"),
+            Error::NotFound => write!(f, "entity not found"),
+            Error::Decode(err) => write!(f, "failed to decode component: {err}"),
+            Error::Connection(err) => write!(f, "connection error: {err}"),
+            Error::Database(err) => write!(f, "database error: {err}"),
+            Error::Conflict => write!(f, "component was changed concurrently"),
+            Error::UnknownColumn(column) => write!(f, "unknown column: {column}"),
+            Error::InvalidQuery(reason) => write!(f, "invalid query: {reason}"),
+            Error::Lagged => write!(f, "changes were missed by a subscriber which fell behind"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.as_sqlx()
+            .map(|err| err as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::Error;
+
+    #[tokio::test]
+    async fn test_classification() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        let missing = sqlx::query("insert into Name(entity) values(1)")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            Error::from(missing),
+            Error::MissingComponentTable(_)
+        ));
+
+        sqlx::query("create table Name(entity integer primary key)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        sqlx::query("insert into Name(entity) values(1)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let duplicate = sqlx::query("insert into Name(entity) values(1)")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            Error::from(duplicate),
+            Error::ConstraintViolation(_)
+        ));
+
+        let not_found = sqlx::query_scalar::<_, i64>("select entity from Name where entity = 2")
+            .fetch_one(&pool)
+            .await
+            .unwrap_err();
+        assert!(matches!(Error::from(not_found), Error::NotFound));
+    }
+}
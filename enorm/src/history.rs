@@ -0,0 +1,68 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the history table column holding the kind of change, which is
+/// one of `insert`, `update` or `remove`.
+pub const OPERATION_COLUMN: &str = "__enorm_operation";
+
+/// Name of the history table column holding the time of the change, see [`timestamp`].
+pub const CHANGED_AT_COLUMN: &str = "__enorm_changed_at";
+
+/// Suffix of the history table of a component declared using `#[enorm(history)]`,
+/// to which its state is appended whenever it is inserted, updated or removed.
+pub const HISTORY_SUFFIX: &str = "_history";
+
+/// Time as stored in history tables, in microseconds since the Unix epoch.
+pub fn timestamp(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_micros() as i64,
+        Err(before) => -(before.duration().as_micros() as i64),
+    }
+}
+
+/// Databases stamping changes recorded in history tables using their own
+/// clock, the one stamping tombstones of soft-deleted components. Changes
+/// made by different processes are thus ordered consistently.
+pub trait DatabaseClock {
+    /// Expression evaluating to the current time of the database, as stored
+    /// in history tables.
+    const NOW: &'static str;
+}
+
+#[cfg(feature = "sqlite")]
+impl DatabaseClock for sqlx::Sqlite {
+    const NOW: &'static str = "cast((julianday('now') - 2440587.5) * 86400000000 as integer)";
+}
+
+/// Postgres' `current_timestamp` is the start of the transaction.
+#[cfg(feature = "postgres")]
+impl DatabaseClock for sqlx::Postgres {
+    const NOW: &'static str = "cast(extract(epoch from current_timestamp) * 1000000 as bigint)";
+}
+
+#[cfg(feature = "mysql")]
+impl DatabaseClock for sqlx::MySql {
+    const NOW: &'static str = "cast(unix_timestamp(now(6)) * 1000000 as signed)";
+}
+
+/// Derived table `__enorm_target` holding the entity bound to `placeholder`,
+/// along with the time of its latest change recorded in the history table.
+pub(crate) fn target(table: &str, placeholder: &str) -> String {
+    format!(
+        "(select __enorm_entity.entity, max({table}{HISTORY_SUFFIX}.{CHANGED_AT_COLUMN}) as changed_at \
+         from (select {placeholder} as entity) as __enorm_entity \
+         left join {table}{HISTORY_SUFFIX} on {table}{HISTORY_SUFFIX}.entity = __enorm_entity.entity \
+         group by __enorm_entity.entity) as __enorm_target"
+    )
+}
+
+/// Expression evaluating to the time of a change of the entity of [`target`],
+/// which is the current time of the database, unless a change of the entity
+/// was recorded since. Changes made in quick succession, or within a single
+/// transaction, are thus still ordered.
+pub(crate) fn changed_at<DB: DatabaseClock>() -> String {
+    let now = DB::NOW;
+
+    format!(
+        "case when __enorm_target.changed_at >= {now} then __enorm_target.changed_at + 1 else {now} end"
+    )
+}
@@ -34,7 +34,7 @@
 //!         x: 100,
 //!         y: 200
 //!     }
-//! )).await;
+//! )).await.unwrap();
 //!
 //! // Spawn a second named position
 //! let pos2 = backend.spawn(&(
@@ -45,7 +45,7 @@
 //!         x: -10000,
 //!         y: -5
 //!     }
-//! )).await;
+//! )).await.unwrap();
 //!
 //! # use futures::stream::StreamExt as _;
 //! // Construct an iterator over all components with a DisplayName & Position
@@ -62,14 +62,14 @@
 //! // name: Position 2 at -10000,-5
 //!
 //! // Remove the DisplayName component from our pos2 entity.
-//! backend.remove::<DisplayName>(&pos2).await;
+//! backend.remove::<DisplayName>(&pos2).await.unwrap();
 //!
 //! // Fetch the name of our first position.
 //! let pos1_name = backend.get::<DisplayName>(&pos1).await.unwrap();
 //! assert_eq!(pos1_name.name, "Position 1");
 //!
 //! // Update the name of our second position.
-//! backend.update(&pos2, &DisplayName { name: "Second Position".to_string() }).await;
+//! backend.update(&pos2, &DisplayName { name: "Second Position".to_string() }).await.unwrap();
 //!
 //! # }
 //! ```
@@ -78,14 +78,28 @@
 
 pub mod archetype;
 pub mod backend;
+pub mod bulk;
+pub mod changes;
 pub mod component;
 pub mod condition;
 pub mod cte;
+pub mod dynamic;
 pub mod entity;
+pub mod error;
+pub mod history;
+pub mod migration;
+pub mod patch;
 pub mod reflect;
+pub mod registry;
 pub mod row;
+pub mod schema;
 pub mod serialization;
+pub mod soft_delete;
 pub mod tables;
+pub mod transaction;
+pub mod versioned;
+
+pub use error::Error;
 
 #[cfg(feature = "bundled")]
 pub use ::sqlx;
@@ -96,10 +110,17 @@ pub mod prelude {
 
     pub use crate::archetype::Archetype;
     pub use crate::backend::*;
+    pub use crate::changes::{Change, ChangeKind};
     pub use crate::component::{ColumnDefinition, Component};
     pub use crate::condition;
     pub use crate::cte::*;
-    pub use crate::reflect::Reflect;
+    pub use crate::dynamic::{DynamicComponent, Value};
+    pub use crate::patch::{Assignments, Patch};
+    pub use crate::reflect::{depth, entity, Reflect};
+    pub use crate::schema::Schema;
     pub use crate::serialization::{Deserializeable, Serializable};
-    pub use crate::tables::Removable;
+    pub use crate::soft_delete::SoftDeleteComponent;
+    pub use crate::tables::{Removable, Tables};
+    pub use crate::transaction::Transaction;
+    pub use crate::versioned::Versioned;
 }
@@ -0,0 +1,942 @@
+use std::{fmt::Display, future::Future, marker::PhantomData};
+
+use sqlx::{Database, Executor, IntoArguments, Pool};
+
+use crate::{
+    archetype::{DatabasePlaceholder, Placeholders},
+    component::{ColumnDefinition, Component, DatabaseColumnType, IndexDefinition},
+    error::Error,
+    history::HISTORY_SUFFIX,
+    soft_delete::DELETED_AT_COLUMN,
+};
+
+/// Table recording the schema version of each component table.
+const SCHEMA_TABLE: &str = "__enorm_schema";
+
+/// Database-specific introspection and alteration of component tables.
+pub trait DatabaseMigration: DatabaseColumnType + DatabasePlaceholder {
+    /// Names, SQL types and nullability of the columns of `table`, in order.
+    ///
+    /// Returns an empty list if the table does not exist.
+    fn table_columns<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<(String, String, bool)>, sqlx::Error>> + Send + 'a;
+
+    /// Names of the secondary indexes of `table`, along with each column they cover.
+    fn table_indexes<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, sqlx::Error>> + Send + 'a;
+
+    /// Columns of `table` which can't be dropped, since foreign key constraints
+    /// of the table use them.
+    ///
+    /// Databases which drop such constraints along with their columns report none.
+    fn foreign_key_columns<'a>(
+        _pool: &'a Pool<Self>,
+        _table: &'a str,
+    ) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send + 'a {
+        async { Ok(Vec::new()) }
+    }
+
+    /// Whether [`DatabaseMigration::retype_column`] replaces the column by a
+    /// new one, rather than altering it in place.
+    const REPLACES_RETYPED_COLUMNS: bool = false;
+
+    /// Whether the type reported by [`DatabaseMigration::table_columns`] is the
+    /// one the column would be created with.
+    fn same_type(live: &str, expected: &str) -> bool {
+        live.eq_ignore_ascii_case(expected)
+    }
+
+    /// Statements adding `column` to the table.
+    ///
+    /// Existing rows have no values for the column, so columns which aren't
+    /// nullable are only added to tables without any rows.
+    fn add_column(table: &str, column: &str, column_type: &str, nullable: bool) -> Vec<String> {
+        vec![format!(
+            "alter table {table} add column {column} {column_type} {}",
+            null(nullable)
+        )]
+    }
+
+    /// Statements changing the type and nullability of `column`, converting
+    /// the existing values.
+    fn retype_column(table: &str, column: &str, column_type: &str, nullable: bool) -> Vec<String>;
+
+    /// Statement dropping the index of the table.
+    fn drop_index(_table: &str, index: &str) -> String {
+        format!("drop index {index}")
+    }
+
+    /// Statement creating the index on an existing table.
+    fn create_index(
+        table: &str,
+        index: &IndexDefinition,
+        _columns: &[ColumnDefinition<Self>],
+    ) -> String {
+        format!(
+            "create {unique}index {name} on {table}({columns})",
+            unique = if index.unique { "unique " } else { "" },
+            name = index.name(table),
+            columns = index.columns.join(", ")
+        )
+    }
+}
+
+fn null(nullable: bool) -> &'static str {
+    if nullable {
+        "null"
+    } else {
+        "not null"
+    }
+}
+
+/// Sqlite can only add columns which aren't nullable along with a default,
+/// which is never used since such columns are only added to tables without
+/// any rows, like on other databases.
+///
+/// Sqlite can't alter the type of a column in place, so the values are copied
+/// into a new column which then replaces the old one. Columns used by foreign
+/// key constraints can't be dropped, and thus can't be retyped either.
+#[cfg(feature = "sqlite")]
+impl DatabaseMigration for sqlx::Sqlite {
+    const REPLACES_RETYPED_COLUMNS: bool = true;
+
+    async fn table_columns(
+        pool: &Pool<Self>,
+        table: &str,
+    ) -> Result<Vec<(String, String, bool)>, sqlx::Error> {
+        let columns: Vec<(String, String, i64)> =
+            sqlx::query_as("select name, type, \"notnull\" from pragma_table_info(?) order by cid")
+                .bind(table)
+                .fetch_all(pool)
+                .await?;
+
+        Ok(columns
+            .into_iter()
+            .map(|(name, column_type, not_null)| (name, column_type, not_null == 0))
+            .collect())
+    }
+
+    /// Indexes backing constraints can't be dropped, and aren't created by enorm.
+    fn table_indexes<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, sqlx::Error>> + Send + 'a {
+        sqlx::query_as(
+            "select list.name, info.name from pragma_index_list(?) as list, \
+             pragma_index_info(list.name) as info where list.origin = 'c'",
+        )
+        .bind(table)
+        .fetch_all(pool)
+    }
+
+    fn foreign_key_columns<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send + 'a {
+        sqlx::query_scalar("select \"from\" from pragma_foreign_key_list(?)")
+            .bind(table)
+            .fetch_all(pool)
+    }
+
+    fn add_column(table: &str, column: &str, column_type: &str, nullable: bool) -> Vec<String> {
+        vec![format!(
+            "alter table {table} add column {column} {column_type} {}",
+            sqlite_definition(column_type, nullable)
+        )]
+    }
+
+    fn retype_column(table: &str, column: &str, column_type: &str, nullable: bool) -> Vec<String> {
+        let temporary = format!("__enorm_migrate_{column}");
+
+        vec![
+            format!(
+                "alter table {table} add column {temporary} {column_type} {}",
+                sqlite_definition(column_type, nullable)
+            ),
+            format!("update {table} set {temporary} = cast({column} as {column_type})"),
+            format!("alter table {table} drop column {column}"),
+            format!("alter table {table} rename column {temporary} to {column}"),
+        ]
+    }
+}
+
+/// Nullability of a column added by Sqlite, along with a default of its type
+/// if it isn't nullable. Types are matched following Sqlite's rules for
+/// determining the affinity of columns.
+#[cfg(feature = "sqlite")]
+fn sqlite_definition(column_type: &str, nullable: bool) -> &'static str {
+    if nullable {
+        return "null";
+    }
+
+    let column_type = column_type.to_ascii_uppercase();
+    if column_type.contains("INT") {
+        "not null default 0"
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|kind| column_type.contains(kind))
+    {
+        "not null default ''"
+    } else if column_type.contains("BLOB") || column_type.is_empty() {
+        "not null default x''"
+    } else {
+        "not null default 0"
+    }
+}
+
+/// Unquoted identifiers are folded to lowercase by Postgres.
+#[cfg(feature = "postgres")]
+impl DatabaseMigration for sqlx::Postgres {
+    fn table_columns<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<(String, String, bool)>, sqlx::Error>> + Send + 'a {
+        sqlx::query_as(
+            "select column_name::text, udt_name::text, is_nullable = 'YES' \
+             from information_schema.columns \
+             where table_schema = current_schema() and table_name = lower($1) \
+             order by ordinal_position",
+        )
+        .bind(table)
+        .fetch_all(pool)
+    }
+
+    fn table_indexes<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, sqlx::Error>> + Send + 'a {
+        sqlx::query_as(
+            "select index_class.relname::text, attribute.attname::text from pg_index \
+             inner join pg_class as index_class on index_class.oid = pg_index.indexrelid \
+             inner join pg_class as table_class on table_class.oid = pg_index.indrelid \
+             inner join pg_attribute as attribute on attribute.attrelid = table_class.oid \
+             and attribute.attnum = any(pg_index.indkey) \
+             where table_class.relname = lower($1) \
+             and table_class.relnamespace = current_schema()::regnamespace \
+             and not pg_index.indisprimary",
+        )
+        .bind(table)
+        .fetch_all(pool)
+    }
+
+    fn retype_column(table: &str, column: &str, column_type: &str, nullable: bool) -> Vec<String> {
+        vec![format!(
+            "alter table {table} alter column {column} type {column_type} using {column}::{column_type}, \
+             alter column {column} {}",
+            if nullable { "drop not null" } else { "set not null" }
+        )]
+    }
+}
+
+/// MySQL can't drop columns used by foreign key constraints.
+#[cfg(feature = "mysql")]
+impl DatabaseMigration for sqlx::MySql {
+    async fn table_columns(
+        pool: &Pool<Self>,
+        table: &str,
+    ) -> Result<Vec<(String, String, bool)>, sqlx::Error> {
+        let columns: Vec<(String, String, String)> = sqlx::query_as(
+            "select cast(column_name as char), cast(column_type as char), cast(is_nullable as char) \
+             from information_schema.columns \
+             where table_schema = database() and table_name = ? \
+             order by ordinal_position",
+        )
+        .bind(table)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(columns
+            .into_iter()
+            .map(|(name, column_type, nullable)| (name, column_type, nullable == "YES"))
+            .collect())
+    }
+
+    fn table_indexes<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<(String, String)>, sqlx::Error>> + Send + 'a {
+        sqlx::query_as(
+            "select cast(index_name as char), cast(column_name as char) \
+             from information_schema.statistics \
+             where table_schema = database() and table_name = ? and index_name <> 'PRIMARY'",
+        )
+        .bind(table)
+        .fetch_all(pool)
+    }
+
+    /// MySQL reports display widths as part of the type, and booleans as `tinyint(1)`.
+    fn same_type(live: &str, expected: &str) -> bool {
+        if live.eq_ignore_ascii_case("tinyint(1)") {
+            return expected.eq_ignore_ascii_case("BOOLEAN");
+        }
+
+        let live = match (live.find('('), live.find(')')) {
+            (Some(start), Some(end)) if start < end => {
+                format!("{}{}", &live[..start], &live[end + 1..])
+            }
+            _ => live.to_string(),
+        };
+
+        live.eq_ignore_ascii_case(expected)
+    }
+
+    fn foreign_key_columns<'a>(
+        pool: &'a Pool<Self>,
+        table: &'a str,
+    ) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send + 'a {
+        sqlx::query_scalar(
+            "select cast(column_name as char) from information_schema.key_column_usage \
+             where table_schema = database() and table_name = ? \
+             and referenced_table_name is not null",
+        )
+        .bind(table)
+        .fetch_all(pool)
+    }
+
+    fn retype_column(table: &str, column: &str, column_type: &str, nullable: bool) -> Vec<String> {
+        vec![format!(
+            "alter table {table} modify column {column} {column_type} {}",
+            null(nullable)
+        )]
+    }
+
+    fn drop_index(table: &str, index: &str) -> String {
+        format!("drop index {index} on {table}")
+    }
+
+    fn create_index(
+        table: &str,
+        index: &IndexDefinition,
+        columns: &[ColumnDefinition<Self>],
+    ) -> String {
+        format!(
+            "create {unique}index {name} on {table}({columns})",
+            unique = if index.unique { "unique " } else { "" },
+            name = index.name(table),
+            columns = crate::component::mysql_key_parts(index, columns)
+        )
+    }
+}
+
+/// Difference between a component's columns and those of its table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnChange {
+    /// The component has a column which the table lacks.
+    Add {
+        column: String,
+        column_type: String,
+        nullable: bool,
+    },
+    /// The table has a column which the component lacks. It is only dropped
+    /// if asked to, see [`Migration::drop_removed`].
+    Drop {
+        column: String,
+        column_type: String,
+        nullable: bool,
+    },
+    /// The column was renamed, see [`Migration::rename`].
+    Rename { from: String, to: String },
+    /// The type or nullability of the column differs from the component's field.
+    Retype {
+        column: String,
+        from: String,
+        to: String,
+        nullable: bool,
+    },
+}
+
+/// Changes required to bring the table of a component in line with its definition.
+///
+/// Migrations are computed by comparing [`Component::columns`] against the
+/// columns of the live table, and can be inspected, printed or applied using
+/// [`Backend::migrate`](crate::backend::Backend::migrate). Applying a migration
+/// increments the schema version recorded for the table.
+///
+/// Columns are added and retyped along with their nullability. Existing rows
+/// have no values for added columns, so those which aren't nullable can only
+/// be added to tables without any rows.
+///
+/// Columns which the component no longer has are kept, along with their
+/// values, unless [`Migration::drop_removed`] is called. Leftover columns are
+/// made nullable, so that components can still be inserted, and are listed by
+/// [`Migration::leftover_columns`].
+///
+/// Indexes of the component missing from the table are created, and those
+/// covering altered columns are recreated.
+///
+/// The history tables of components declared using `#[enorm(history)]` are
+/// created if missing, and columns added, renamed or retyped in them as well.
+/// Dropped columns are kept in the history table, along with their past values.
+pub struct Migration<DB: Database> {
+    table: &'static str,
+    create: Option<Vec<String>>,
+    changes: Vec<ColumnChange>,
+    /// Whether columns which the component no longer has are dropped.
+    drop_removed: bool,
+    /// Names of the indexes of the component, along with the statements creating them.
+    indexes: Vec<(String, String)>,
+    /// Names of the indexes of the table, along with each column they cover.
+    live_indexes: Vec<(String, String)>,
+    /// Columns of the table which can't be dropped, see
+    /// [`DatabaseMigration::foreign_key_columns`].
+    foreign_keys: Vec<String>,
+    /// Name of the history table, if the component keeps one.
+    history: Option<String>,
+    /// Statements creating the history table of an existing component table.
+    create_history: Vec<String>,
+    _db: PhantomData<DB>,
+}
+
+impl<DB: DatabaseMigration> Migration<DB> {
+    /// Compare the columns of the component `T` with those of its table.
+    pub async fn plan<T, EntityId>(pool: &Pool<DB>) -> Result<Self, Error>
+    where
+        T: Component<DB>,
+        EntityId: sqlx::Type<DB>,
+    {
+        let table = <T as Component<DB>>::table();
+        let live = DB::table_columns(pool, table).await?;
+        let history = <T as Component<DB>>::HISTORY.then(|| format!("{table}{HISTORY_SUFFIX}"));
+
+        if live.is_empty() {
+            return Ok(Migration {
+                table,
+                create: Some(
+                    std::iter::once(<T as Component<DB>>::create_table_statement::<EntityId>())
+                        .chain(<T as Component<DB>>::create_index_statements())
+                        .chain(<T as Component<DB>>::create_history_statements::<EntityId>())
+                        .collect(),
+                ),
+                changes: Vec::new(),
+                drop_removed: false,
+                indexes: Vec::new(),
+                live_indexes: Vec::new(),
+                foreign_keys: Vec::new(),
+                history,
+                create_history: Vec::new(),
+                _db: PhantomData,
+            });
+        }
+
+        let create_history = match &history {
+            Some(history) if DB::table_columns(pool, history).await?.is_empty() => {
+                <T as Component<DB>>::create_history_statements::<EntityId>()
+            }
+            _ => Vec::new(),
+        };
+
+        // The entity and any columns managed by enorm itself aren't part of the component.
+        let managed = |column: &str| {
+            column.eq_ignore_ascii_case("entity")
+                || column.to_ascii_lowercase().starts_with("__enorm")
+                || (<T as Component<DB>>::SOFT_DELETE
+                    && column.eq_ignore_ascii_case(DELETED_AT_COLUMN))
+        };
+
+        let live: Vec<_> = live
+            .into_iter()
+            .filter(|(column, _, _)| !managed(column))
+            .collect();
+
+        let columns = <T as Component<DB>>::columns();
+        let expected: Vec<_> = columns
+            .iter()
+            .map(|column| (column.name(), column.sql_type(), column.nullable()))
+            .collect();
+
+        let mut changes = Vec::new();
+
+        for (column, column_type, nullable) in &expected {
+            match live
+                .iter()
+                .find(|(name, _, _)| name.eq_ignore_ascii_case(column))
+            {
+                None => changes.push(ColumnChange::Add {
+                    column: column.to_string(),
+                    column_type: column_type.clone(),
+                    nullable: *nullable,
+                }),
+                Some((_, live_type, live_nullable))
+                    if !DB::same_type(live_type, column_type) || live_nullable != nullable =>
+                {
+                    changes.push(ColumnChange::Retype {
+                        column: column.to_string(),
+                        from: live_type.clone(),
+                        to: column_type.clone(),
+                        nullable: *nullable,
+                    })
+                }
+                Some(_) => (),
+            }
+        }
+
+        for (column, column_type, nullable) in live {
+            if !expected
+                .iter()
+                .any(|(name, _, _)| name.eq_ignore_ascii_case(&column))
+            {
+                changes.push(ColumnChange::Drop {
+                    column,
+                    column_type,
+                    nullable,
+                });
+            }
+        }
+
+        let indexes = <T as Component<DB>>::indexes()
+            .iter()
+            .map(|index| (index.name(table), DB::create_index(table, index, &columns)))
+            .collect();
+
+        Ok(Migration {
+            table,
+            create: None,
+            changes,
+            drop_removed: false,
+            indexes,
+            live_indexes: DB::table_indexes(pool, table).await?,
+            foreign_keys: DB::foreign_key_columns(pool, table).await?,
+            history,
+            create_history,
+            _db: PhantomData,
+        })
+    }
+
+    /// Treat the dropped column `from` and the added column `to` as a rename,
+    /// preserving its values.
+    ///
+    /// Does nothing unless the migration drops `from` and adds `to`.
+    pub fn rename(mut self, from: &str, to: &str) -> Self {
+        let dropped = self.changes.iter().position(
+            |change| matches!(change, ColumnChange::Drop { column, .. } if column.eq_ignore_ascii_case(from)),
+        );
+        let added = self.changes.iter().position(
+            |change| matches!(change, ColumnChange::Add { column, .. } if column.eq_ignore_ascii_case(to)),
+        );
+
+        let (Some(dropped), Some(added)) = (dropped, added) else {
+            return self;
+        };
+
+        let (
+            ColumnChange::Drop {
+                column: from,
+                column_type: from_type,
+                nullable: from_nullable,
+            },
+            ColumnChange::Add {
+                column: to,
+                column_type: to_type,
+                nullable,
+            },
+        ) = (self.changes[dropped].clone(), self.changes[added].clone())
+        else {
+            unreachable!()
+        };
+
+        self.changes[added] = ColumnChange::Rename {
+            from,
+            to: to.clone(),
+        };
+        self.changes.remove(dropped);
+
+        if !DB::same_type(&from_type, &to_type) || from_nullable != nullable {
+            self.changes.push(ColumnChange::Retype {
+                column: to,
+                from: from_type,
+                to: to_type,
+                nullable,
+            });
+        }
+
+        self
+    }
+}
+
+impl<DB: Database> Migration<DB> {
+    /// Name of the component table.
+    pub fn table(&self) -> &'static str {
+        self.table
+    }
+
+    /// Whether the table does not exist yet, and will be created.
+    pub fn creates_table(&self) -> bool {
+        self.create.is_some()
+    }
+
+    pub fn changes(&self) -> &[ColumnChange] {
+        &self.changes
+    }
+
+    /// Drop the columns which the component no longer has, along with their values.
+    pub fn drop_removed(mut self) -> Self {
+        self.drop_removed = true;
+        self
+    }
+
+    /// Columns which the component no longer has, but which are kept since
+    /// [`Migration::drop_removed`] wasn't called.
+    pub fn leftover_columns(&self) -> Vec<&str> {
+        if self.drop_removed {
+            return Vec::new();
+        }
+
+        self.changes
+            .iter()
+            .filter_map(|change| match change {
+                ColumnChange::Drop { column, .. } => Some(column.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether the change alters the table. Leftover columns are only made
+    /// nullable, unless they already are.
+    fn alters(&self, change: &ColumnChange) -> bool {
+        match change {
+            ColumnChange::Drop { nullable, .. } => self.drop_removed || !nullable,
+            _ => true,
+        }
+    }
+
+    /// Names of the indexes of the table covering columns which are altered.
+    fn altered_indexes(&self) -> Vec<&str> {
+        let altered: Vec<&str> = self
+            .changes
+            .iter()
+            .filter(|change| self.alters(change))
+            .map(|change| match change {
+                ColumnChange::Add { column, .. }
+                | ColumnChange::Drop { column, .. }
+                | ColumnChange::Retype { column, .. } => column.as_str(),
+                ColumnChange::Rename { from, .. } => from.as_str(),
+            })
+            .collect();
+
+        let mut indexes: Vec<&str> = Vec::new();
+        for (index, column) in &self.live_indexes {
+            if altered
+                .iter()
+                .any(|altered| altered.eq_ignore_ascii_case(column))
+                && !indexes.contains(&index.as_str())
+            {
+                indexes.push(index);
+            }
+        }
+
+        indexes
+    }
+
+    /// Statements creating the indexes of the component which the table lacks,
+    /// or which are dropped along with the columns they cover.
+    fn missing_indexes(&self) -> impl Iterator<Item = &String> {
+        let dropped = self.altered_indexes();
+
+        self.indexes
+            .iter()
+            .filter(move |(name, _)| {
+                dropped.iter().any(|index| index.eq_ignore_ascii_case(name))
+                    || !self
+                        .live_indexes
+                        .iter()
+                        .any(|(index, _)| index.eq_ignore_ascii_case(name))
+            })
+            .map(|(_, statement)| statement)
+    }
+
+    /// Whether the table already matches the component.
+    pub fn is_empty(&self) -> bool {
+        self.create.is_none()
+            && !self.changes.iter().any(|change| self.alters(change))
+            && self.missing_indexes().next().is_none()
+            && self.create_history.is_empty()
+    }
+}
+
+impl<DB: DatabaseMigration> Migration<DB> {
+    /// The statements creating or altering the table, in order.
+    ///
+    /// Indexes covering altered columns are dropped beforehand, since not all
+    /// databases can alter indexed columns, and created again afterwards.
+    pub fn statements(&self) -> Vec<String> {
+        let alter = |table: &str, change: &ColumnChange, history: bool| match change {
+            // History tables hold past states, whose columns are always nullable.
+            ColumnChange::Add {
+                column,
+                column_type,
+                nullable,
+            } => DB::add_column(table, column, column_type, *nullable || history),
+            ColumnChange::Drop {
+                column,
+                column_type,
+                ..
+            } => {
+                if self.drop_removed {
+                    vec![format!("alter table {table} drop column {column}")]
+                } else {
+                    DB::retype_column(table, column, column_type, true)
+                }
+            }
+            ColumnChange::Rename { from, to } => {
+                vec![format!("alter table {table} rename column {from} to {to}")]
+            }
+            ColumnChange::Retype {
+                column,
+                to,
+                nullable,
+                ..
+            } => DB::retype_column(table, column, to, *nullable || history),
+        };
+
+        // A newly created history table already matches the component.
+        let history = self
+            .history
+            .iter()
+            .filter(|_| self.create_history.is_empty())
+            .flat_map(|history| {
+                self.changes
+                    .iter()
+                    .filter(|change| !matches!(change, ColumnChange::Drop { .. }))
+                    .filter(|change| {
+                        // Only the nullability of the column changed.
+                        !matches!(change, ColumnChange::Retype { from, to, .. } if DB::same_type(from, to))
+                    })
+                    .flat_map(move |change| alter(history, change, true))
+            });
+
+        self.create
+            .iter()
+            .flatten()
+            .cloned()
+            .chain(
+                self.altered_indexes()
+                    .into_iter()
+                    .map(|index| DB::drop_index(self.table, index)),
+            )
+            .chain(
+                self.changes
+                    .iter()
+                    .filter(|change| self.alters(change))
+                    .flat_map(|change| alter(self.table, change, false)),
+            )
+            .chain(self.missing_indexes().cloned())
+            .chain(self.create_history.iter().cloned())
+            .chain(history)
+            .collect()
+    }
+
+    /// Check that the database can drop or replace the columns which the
+    /// migration drops or retypes.
+    fn validate(&self) -> Result<(), Error> {
+        // Renamed columns are retyped under their new name.
+        let original = |column: &str| {
+            self.changes
+                .iter()
+                .find_map(|change| match change {
+                    ColumnChange::Rename { from, to } if to.eq_ignore_ascii_case(column) => {
+                        Some(from.clone())
+                    }
+                    _ => None,
+                })
+                .unwrap_or_else(|| column.to_string())
+        };
+
+        for change in self.changes.iter().filter(|change| self.alters(change)) {
+            let column = match change {
+                ColumnChange::Drop { column, .. }
+                    if self.drop_removed || DB::REPLACES_RETYPED_COLUMNS =>
+                {
+                    column
+                }
+                ColumnChange::Retype { column, .. } if DB::REPLACES_RETYPED_COLUMNS => column,
+                _ => continue,
+            };
+
+            let column = original(column);
+            if self
+                .foreign_keys
+                .iter()
+                .any(|foreign_key| foreign_key.eq_ignore_ascii_case(&column))
+            {
+                return Err(Error::InvalidQuery(
+                    "columns used by foreign key constraints can't be dropped or retyped on this database",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the migration adds columns which aren't nullable to the table.
+    fn adds_required_columns(&self) -> bool {
+        self.changes.iter().any(|change| {
+            matches!(
+                change,
+                ColumnChange::Add {
+                    nullable: false,
+                    ..
+                }
+            )
+        })
+    }
+
+    /// Apply the migration, returning the new schema version of the table.
+    ///
+    /// Columns which aren't nullable can only be added to tables without any
+    /// rows, and columns used by foreign key constraints can't be dropped on
+    /// all databases. Such migrations fail with [`Error::InvalidQuery`]
+    /// before altering the table.
+    pub async fn apply(&self, pool: &Pool<DB>) -> Result<i64, Error>
+    where
+        for<'q> String: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Decode<'q, DB> + sqlx::Type<DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        usize: sqlx::ColumnIndex<<DB as Database>::Row>,
+    {
+        self.validate()?;
+
+        create_schema_table(pool).await?;
+
+        let mut tx = pool.begin().await?;
+
+        if self.create.is_none() && self.adds_required_columns() {
+            let (rows,) =
+                sqlx::query_as::<DB, (i64,)>(&format!("select count(*) from {}", self.table))
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+            if rows > 0 {
+                return Err(Error::InvalidQuery(
+                    "columns which aren't nullable can only be added to tables without any rows",
+                ));
+            }
+        }
+
+        let altered = !self.is_empty();
+
+        // The version is bumped before altering the table, so that concurrent
+        // migrations of the table wait for each other rather than recording
+        // the same version.
+        if altered {
+            let mut placeholders = Placeholders::new::<DB>();
+            let mut update =
+                format!("update {SCHEMA_TABLE} set version = version + 1 where table_name = ");
+            placeholders.next(&mut update).unwrap();
+
+            sqlx::query(&update)
+                .bind(self.table.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut select = format!("select version from {SCHEMA_TABLE} where table_name = ");
+        placeholders.next(&mut select).unwrap();
+
+        let current = sqlx::query_as::<DB, (i64,)>(&select)
+            .bind(self.table.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let version = match current {
+            Some((version,)) => version,
+            None => {
+                // Tables created before versions were recorded start at 1.
+                let version = if altered && self.create.is_none() {
+                    2
+                } else {
+                    1
+                };
+
+                let mut placeholders = Placeholders::new::<DB>();
+                let mut insert = format!("insert into {SCHEMA_TABLE}(table_name, version) values(");
+                placeholders.next(&mut insert).unwrap();
+                insert.push_str(", ");
+                placeholders.next(&mut insert).unwrap();
+                insert.push(')');
+
+                sqlx::query(&insert)
+                    .bind(self.table.to_string())
+                    .bind(version)
+                    .execute(&mut *tx)
+                    .await?;
+
+                version
+            }
+        };
+
+        for statement in self.statements() {
+            sqlx::query(&statement).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(version)
+    }
+}
+
+impl<DB: DatabaseMigration> Display for Migration<DB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for statement in self.statements() {
+            writeln!(f, "{};", statement.trim_end_matches(';'))?;
+        }
+
+        for column in self.leftover_columns() {
+            writeln!(
+                f,
+                "-- {}.{column} is no longer part of the component",
+                self.table
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn create_schema_table<DB>(pool: &Pool<DB>) -> Result<(), Error>
+where
+    DB: DatabaseMigration,
+    String: sqlx::Type<DB>,
+    i64: sqlx::Type<DB>,
+    for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+{
+    let sql = format!(
+        "create table if not exists {SCHEMA_TABLE}(table_name {} primary key, version {} not null)",
+        DB::key_type(&<String as sqlx::Type<DB>>::type_info()),
+        DB::column_type(&<i64 as sqlx::Type<DB>>::type_info()),
+    );
+
+    sqlx::query(&sql).execute(pool).await?;
+
+    Ok(())
+}
+
+/// Schema version recorded for `table`, if any.
+pub async fn schema_version<DB>(pool: &Pool<DB>, table: &str) -> Result<Option<i64>, Error>
+where
+    DB: DatabaseMigration,
+    for<'q> String: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    for<'q> i64: sqlx::Decode<'q, DB> + sqlx::Type<DB>,
+    for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+    for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    usize: sqlx::ColumnIndex<<DB as Database>::Row>,
+{
+    create_schema_table(pool).await?;
+
+    let mut placeholders = Placeholders::new::<DB>();
+    let mut sql = format!("select version from {SCHEMA_TABLE} where table_name = ");
+    placeholders.next(&mut sql).unwrap();
+
+    let version = sqlx::query_as::<DB, (i64,)>(&sql)
+        .bind(table.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(version.map(|(version,)| version))
+}
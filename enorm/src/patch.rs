@@ -0,0 +1,265 @@
+use std::{fmt::Write as _, marker::PhantomData, ops::Add, sync::Arc};
+
+use sqlx::{query::QueryAs, Database, Executor, IntoArguments, Pool};
+
+use crate::{
+    archetype::{DatabasePlaceholder, Placeholders},
+    changes::{Broadcast, Change, ChangeKind},
+    component::Component,
+    entity::{EntityPrefixedQuery, RowsAffected},
+    error::Error,
+    reflect::ReflectedColumn,
+    soft_delete::DELETED_AT_COLUMN,
+    versioned::VERSION_COLUMN,
+};
+
+/// Binds the value of an assignment, regardless of the lifetime of the statement.
+type Bind<DB> = Box<
+    dyn for<'q> FnOnce(
+            QueryAs<'q, DB, (), <DB as Database>::Arguments<'q>>,
+        ) -> QueryAs<'q, DB, (), <DB as Database>::Arguments<'q>>
+        + Send,
+>;
+
+struct Assignment<DB: Database> {
+    column: &'static str,
+    increment: bool,
+    bind: Bind<DB>,
+}
+
+/// Values assigned to individual columns of the component `T`, see
+/// [`Patch`] and [`List::update_set`](crate::backend::List::update_set).
+///
+/// Columns are referred to by their reflection, such as `Player::FIELDS.score`.
+pub struct Assignments<DB: Database, T> {
+    assignments: Vec<Assignment<DB>>,
+    _component: PhantomData<T>,
+}
+
+impl<DB: Database, T> Default for Assignments<DB, T> {
+    fn default() -> Self {
+        Assignments {
+            assignments: Vec::new(),
+            _component: PhantomData,
+        }
+    }
+}
+
+impl<DB: Database, T> Assignments<DB, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the column to the given value.
+    pub fn set<Value>(self, column: ReflectedColumn<Value>, value: Value) -> Self
+    where
+        Value: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Send + 'static,
+    {
+        self.assign(column, false, value)
+    }
+
+    /// Add `by` to the current value of the column, within the database.
+    ///
+    /// Unlike reading the component and writing back the sum, concurrent
+    /// increments are never lost.
+    pub fn increment<Value>(self, column: ReflectedColumn<Value>, by: Value) -> Self
+    where
+        Value: Add<Output = Value> + for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Send + 'static,
+    {
+        self.assign(column, true, by)
+    }
+
+    fn assign<Value>(
+        mut self,
+        column: ReflectedColumn<Value>,
+        increment: bool,
+        value: Value,
+    ) -> Self
+    where
+        Value: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Send + 'static,
+    {
+        self.assignments.push(Assignment {
+            column: column.column_name(),
+            increment,
+            bind: Box::new(move |query| query.bind(value)),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    /// Bind the values, in the order they were assigned.
+    pub(crate) fn bind<'q>(
+        self,
+        query: QueryAs<'q, DB, (), <DB as Database>::Arguments<'q>>,
+    ) -> QueryAs<'q, DB, (), <DB as Database>::Arguments<'q>> {
+        self.assignments
+            .into_iter()
+            .fold(query, |query, assignment| (assignment.bind)(query))
+    }
+}
+
+impl<DB, T> Assignments<DB, T>
+where
+    DB: Database + DatabasePlaceholder,
+    T: Component<DB>,
+{
+    /// Name of the column within the component table, given its reflected name.
+    fn table_column(column: &'static str) -> Result<&'static str, Error> {
+        column
+            .strip_prefix("__cte_")
+            .and_then(|column| column.strip_prefix(<T as Component<DB>>::table()))
+            .and_then(|column| column.strip_prefix("__"))
+            // Columns managed by enorm itself, such as the tag of enum
+            // components, can't be assigned.
+            .filter(|column| !column.starts_with("__enorm"))
+            .ok_or_else(|| Error::UnknownColumn(column.to_string()))
+    }
+
+    /// Write the `update` statement up to its `where` clause, incrementing
+    /// the version of versioned components.
+    pub(crate) fn serialize(
+        &self,
+        f: &mut String,
+        placeholders: &mut Placeholders,
+    ) -> Result<(), Error> {
+        write!(f, "update {} set ", <T as Component<DB>>::table()).unwrap();
+
+        for (i, assignment) in self.assignments.iter().enumerate() {
+            let column = Self::table_column(assignment.column)?;
+
+            if i > 0 {
+                f.push_str(", ");
+            }
+
+            if assignment.increment {
+                write!(f, "{column} = {column} + ").unwrap();
+            } else {
+                write!(f, "{column} = ").unwrap();
+            }
+            placeholders.next(f).unwrap();
+        }
+
+        if <T as Component<DB>>::VERSIONED {
+            write!(f, ", {VERSION_COLUMN} = {VERSION_COLUMN} + 1").unwrap();
+        }
+
+        Ok(())
+    }
+}
+
+/// Update of individual fields of the component `T`, leaving its other
+/// columns unchanged.
+///
+/// Created using [`Backend::patch`](crate::backend::Backend::patch), and applied
+/// by [`Patch::execute`] using a single `update` statement.
+///
+/// Patching a versioned component increments its version, and the change is
+/// recorded in the history of components declared using `#[enorm(history)]`.
+/// Tombstoned components are left unchanged, as with regular updates.
+pub struct Patch<'a, DB: Database, EntityId, T> {
+    pool: &'a Pool<DB>,
+    entity: &'a EntityId,
+    changes: Option<Arc<Broadcast<EntityId>>>,
+    assignments: Assignments<DB, T>,
+}
+
+impl<'a, DB: Database, EntityId, T> Patch<'a, DB, EntityId, T> {
+    pub(crate) fn new(
+        pool: &'a Pool<DB>,
+        entity: &'a EntityId,
+        changes: Option<Arc<Broadcast<EntityId>>>,
+    ) -> Self {
+        Patch {
+            pool,
+            entity,
+            changes,
+            assignments: Assignments::new(),
+        }
+    }
+
+    /// Set the column to the given value, see [`Assignments::set`].
+    pub fn set<Value>(mut self, column: ReflectedColumn<Value>, value: Value) -> Self
+    where
+        Value: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Send + 'static,
+    {
+        self.assignments = self.assignments.set(column, value);
+        self
+    }
+
+    /// Add `by` to the current value of the column, see [`Assignments::increment`].
+    pub fn increment<Value>(mut self, column: ReflectedColumn<Value>, by: Value) -> Self
+    where
+        Value: Add<Output = Value> + for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Send + 'static,
+    {
+        self.assignments = self.assignments.increment(column, by);
+        self
+    }
+}
+
+impl<DB, EntityId, T> Patch<'_, DB, EntityId, T>
+where
+    DB: Database + DatabasePlaceholder,
+    T: Component<DB>,
+{
+    /// Build the `update` statement, binding the values of the assignments
+    /// followed by the entity.
+    fn statement(&self) -> Result<String, Error> {
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut sql = String::new();
+        self.assignments.serialize(&mut sql, &mut placeholders)?;
+
+        sql.push_str(" where entity = ");
+        placeholders.next(&mut sql).unwrap();
+
+        if <T as Component<DB>>::SOFT_DELETE {
+            write!(sql, " and {DELETED_AT_COLUMN} is null").unwrap();
+        }
+
+        Ok(sql)
+    }
+
+    /// Apply the assignments within a single transaction.
+    ///
+    /// Does nothing if the entity doesn't have the component.
+    pub async fn execute(self) -> Result<(), Error>
+    where
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB> + Send,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB> + Clone + Sync,
+        <DB as Database>::QueryResult: RowsAffected,
+    {
+        if self.assignments.is_empty() {
+            return Ok(());
+        }
+
+        let sql = self.statement()?;
+
+        let query = self
+            .assignments
+            .bind(sqlx::query_as(&sql))
+            .bind(self.entity);
+
+        let mut history = EntityPrefixedQuery::new(self.entity);
+        <T as Component<DB>>::record_history(&mut history, ChangeKind::Update);
+
+        let mut tx = self.pool.begin().await?;
+        if tx.execute(query).await?.rows_affected() == 0 {
+            return Ok(());
+        }
+        history.execute(&mut tx).await?;
+        tx.commit().await?;
+
+        if let Some(changes) = self.changes {
+            changes.send(vec![Change {
+                entity: self.entity.clone(),
+                table: <T as Component<DB>>::table(),
+                kind: ChangeKind::Update,
+            }]);
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 use std::marker::PhantomData;
 
-use crate::condition::{
-    Equality, GreaterThan, GreaterThanOrEqual, Inequality, LessThan, LessThanOrEqual,
+use crate::{
+    condition::{
+        Between, Equality, GreaterThan, GreaterThanOrEqual, ILike, In, Inequality, IsNotNull,
+        IsNull, LessThan, LessThanOrEqual, Like, NotIn,
+    },
+    cte::{DEPTH_COLUMN, ENTITY_COLUMN},
 };
 
 pub trait Reflect {
@@ -9,6 +13,19 @@ pub trait Reflect {
     const FIELDS: Self::ReflectionType;
 }
 
+/// Pseudo-column holding the Entity ID, for filtering and ordering
+/// [`List`](crate::backend::List) queries by entity.
+pub const fn entity<EntityId>() -> ReflectedColumn<EntityId> {
+    ReflectedColumn::new(ENTITY_COLUMN)
+}
+
+/// Pseudo-column holding the depth of entities found by
+/// [`List::descendants`](crate::backend::List::descendants) or
+/// [`List::ancestors`](crate::backend::List::ancestors).
+pub const fn depth() -> ReflectedColumn<i32> {
+    ReflectedColumn::new(DEPTH_COLUMN)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ReflectedColumn<T> {
     column_name: &'static str,
@@ -22,6 +39,11 @@ impl<T> ReflectedColumn<T> {
             _data: PhantomData,
         }
     }
+
+    /// Name of the column within the queries generated by [`List`](crate::backend::List).
+    pub const fn column_name(&self) -> &'static str {
+        self.column_name
+    }
 }
 
 impl<T> ReflectedColumn<T> {
@@ -43,6 +65,15 @@ impl<T> ReflectedColumn<T> {
     pub const fn le(&self, value: T) -> LessThanOrEqual<T> {
         LessThanOrEqual::new(self.column_name, value)
     }
+    pub const fn between(&self, low: T, high: T) -> Between<T> {
+        Between::new(self.column_name, low, high)
+    }
+    pub fn is_in(&self, values: impl IntoIterator<Item = T>) -> In<T> {
+        In::new(self.column_name, values.into_iter().collect())
+    }
+    pub fn not_in(&self, values: impl IntoIterator<Item = T>) -> NotIn<T> {
+        NotIn::new(self.column_name, values.into_iter().collect())
+    }
 
     pub const fn equals(&self, value: T) -> Equality<T> {
         self.eq(value)
@@ -63,3 +94,21 @@ impl<T> ReflectedColumn<T> {
         self.le(value)
     }
 }
+
+impl ReflectedColumn<String> {
+    pub fn like(&self, pattern: impl Into<String>) -> Like<String> {
+        Like::new(self.column_name, pattern.into())
+    }
+    pub fn ilike(&self, pattern: impl Into<String>) -> ILike<String> {
+        ILike::new(self.column_name, pattern.into())
+    }
+}
+
+impl<T> ReflectedColumn<Option<T>> {
+    pub const fn is_null(&self) -> IsNull {
+        IsNull::new(self.column_name)
+    }
+    pub const fn is_not_null(&self) -> IsNotNull {
+        IsNotNull::new(self.column_name)
+    }
+}
@@ -0,0 +1,269 @@
+use std::{fmt::Write as _, sync::RwLock};
+
+use sqlx::{ColumnIndex, Database, Executor, IntoArguments, Pool};
+
+use crate::{
+    archetype::{ComponentTable, DatabasePlaceholder, Placeholders},
+    component::{Columns, Component},
+    dynamic::DynamicComponent,
+    error::Error,
+    history::{self, DatabaseClock, CHANGED_AT_COLUMN, HISTORY_SUFFIX, OPERATION_COLUMN},
+    soft_delete::DELETED_AT_COLUMN,
+};
+
+/// Table of a registered component, along with how its rows are written.
+#[derive(Debug)]
+pub struct RegisteredTable<DB: Database> {
+    pub(crate) name: &'static str,
+    pub(crate) columns: Columns<DB>,
+    pub(crate) soft_delete: bool,
+    pub(crate) versioned: bool,
+    pub(crate) history: bool,
+    pub(crate) tagged: bool,
+}
+
+impl<DB: Database> Clone for RegisteredTable<DB> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<DB: Database> Copy for RegisteredTable<DB> {}
+
+impl<DB: Database> RegisteredTable<DB> {
+    /// Describe the table of the component `T`.
+    pub fn of<T: Component<DB>>() -> Self {
+        RegisteredTable {
+            name: <T as Component<DB>>::table(),
+            columns: <T as Component<DB>>::columns,
+            soft_delete: <T as Component<DB>>::SOFT_DELETE,
+            versioned: <T as Component<DB>>::VERSIONED,
+            history: <T as Component<DB>>::HISTORY,
+            tagged: <T as Component<DB>>::TAGGED,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Tables of the components registered with a backend, in the order they were registered.
+///
+/// Only tables registered through the backend itself are known, regardless of
+/// which tables exist in the database.
+#[derive(Debug)]
+pub struct Registry<DB: Database> {
+    tables: RwLock<Vec<RegisteredTable<DB>>>,
+}
+
+impl<DB: Database> Default for Registry<DB> {
+    fn default() -> Self {
+        Registry {
+            tables: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl<DB: Database> Registry<DB> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include the table, unless it already is.
+    pub fn add(&self, table: RegisteredTable<DB>) {
+        let mut tables = self.tables.write().unwrap();
+
+        if !tables
+            .iter()
+            .any(|registered| registered.name == table.name)
+        {
+            tables.push(table);
+        }
+    }
+
+    fn registered(&self, table: &str) -> Option<RegisteredTable<DB>> {
+        self.tables
+            .read()
+            .unwrap()
+            .iter()
+            .find(|registered| registered.name == table)
+            .copied()
+    }
+
+    /// Whether the registered table is soft-deleted.
+    fn soft_delete(&self, table: &str) -> bool {
+        self.registered(table)
+            .is_some_and(|registered| registered.soft_delete)
+    }
+
+    /// Names of the registered tables.
+    pub fn tables(&self) -> Vec<&'static str> {
+        self.tables
+            .read()
+            .unwrap()
+            .iter()
+            .map(RegisteredTable::name)
+            .collect()
+    }
+
+    /// Name of the registered table, compared case-insensitively, since
+    /// databases like Postgres fold unquoted names to lowercase.
+    pub fn table(&self, name: &str) -> Option<&'static str> {
+        self.tables
+            .read()
+            .unwrap()
+            .iter()
+            .map(RegisteredTable::name)
+            .find(|table| table.eq_ignore_ascii_case(name))
+    }
+
+    /// Runtime description of the component stored in the registered table.
+    pub fn component(&self, table: &str) -> Option<DynamicComponent<DB>>
+    where
+        String: sqlx::Type<DB>,
+        i64: sqlx::Type<DB>,
+    {
+        self.registered(table)
+            .map(|registered| DynamicComponent::registered(&registered))
+    }
+
+    /// Names of the registered tables holding a component of the entity.
+    pub(crate) async fn components_of<'e, EntityId, E>(
+        &self,
+        executor: E,
+        entity: &EntityId,
+    ) -> Result<Vec<&'static str>, Error>
+    where
+        DB: DatabasePlaceholder,
+        E: Executor<'e, Database = DB>,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+        usize: ColumnIndex<<DB as Database>::Row>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        let tables = self
+            .tables
+            .read()
+            .unwrap()
+            .iter()
+            .map(|table| ComponentTable {
+                name: table.name,
+                soft_delete: table.soft_delete,
+            })
+            .collect();
+
+        Ok(present(executor, entity, tables)
+            .await?
+            .into_iter()
+            .map(|table| table.name)
+            .collect())
+    }
+
+    /// Delete the entity from every registered table within a single transaction,
+    /// returning the tables it was removed from. Components of soft-deleted
+    /// tables are tombstoned instead, and the removal of components which keep
+    /// a history is recorded.
+    pub(crate) async fn despawn<EntityId>(
+        &self,
+        pool: &Pool<DB>,
+        entity: &EntityId,
+    ) -> Result<Vec<&'static str>, Error>
+    where
+        DB: DatabasePlaceholder + DatabaseClock,
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+        for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+        for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        usize: ColumnIndex<<DB as Database>::Row>,
+        EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    {
+        let mut placeholders = Placeholders::new::<DB>();
+        let mut placeholder = String::new();
+        placeholders.next(&mut placeholder).unwrap();
+
+        let mut tx = pool.begin().await?;
+
+        let removed = self.components_of(&mut *tx, entity).await?;
+
+        // Tables registered later may reference those registered before them.
+        for table in removed.iter().rev() {
+            if self
+                .registered(table)
+                .is_some_and(|registered| registered.history)
+            {
+                let sql = format!(
+                    "insert into {table}{HISTORY_SUFFIX}(entity, {OPERATION_COLUMN}, {CHANGED_AT_COLUMN}) select __enorm_target.entity, 'remove', {} from {}",
+                    history::changed_at::<DB>(),
+                    history::target(table, &placeholder),
+                );
+                sqlx::query(&sql).bind(entity).execute(&mut *tx).await?;
+            }
+
+            let sql = if self.soft_delete(table) {
+                format!(
+                    "update {table} set {DELETED_AT_COLUMN} = current_timestamp where entity = {placeholder}"
+                )
+            } else {
+                format!("delete from {table} where entity = {placeholder}")
+            };
+            sqlx::query(&sql).bind(entity).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(removed)
+    }
+}
+
+/// Tables out of the given ones holding a component of the entity, leaving
+/// out the tombstones of soft-deleted components.
+pub(crate) async fn present<'e, DB, EntityId, E>(
+    executor: E,
+    entity: &EntityId,
+    tables: Vec<ComponentTable>,
+) -> Result<Vec<ComponentTable>, Error>
+where
+    DB: Database + DatabasePlaceholder,
+    E: Executor<'e, Database = DB>,
+    for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+    for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+    EntityId: for<'q> sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+{
+    if tables.is_empty() {
+        return Ok(tables);
+    }
+
+    let mut placeholders = Placeholders::new::<DB>();
+    let mut sql = String::new();
+
+    for (i, table) in tables.iter().enumerate() {
+        if i > 0 {
+            sql.push_str("\nunion all\n");
+        }
+        write!(
+            sql,
+            "select '{}' from {} where entity = ",
+            table.name, table.name
+        )
+        .unwrap();
+        placeholders.next(&mut sql).unwrap();
+
+        if table.soft_delete {
+            write!(sql, " and {DELETED_AT_COLUMN} is null").unwrap();
+        }
+    }
+
+    let mut query = sqlx::query_scalar::<DB, String>(&sql);
+    for _ in 0..placeholders.count() {
+        query = query.bind(entity);
+    }
+
+    let present = query.fetch_all(executor).await?;
+
+    Ok(tables
+        .into_iter()
+        .filter(|table| present.iter().any(|name| name == table.name))
+        .collect())
+}
@@ -2,7 +2,7 @@ use std::ops::{Deref, DerefMut};
 
 use sqlx::{prelude::FromRow, ColumnIndex, Decode, Row, ValueRef};
 
-use crate::serialization::Deserializeable;
+use crate::{cte::CommonTableExpression, serialization::Deserializeable};
 
 /// Wrapper around a database-specific row which tracks the offset
 /// into the row as columns are parsed.
@@ -95,6 +95,83 @@ impl<EntityId, T> DerefMut for Entity<EntityId, T> {
     }
 }
 
+/// Components of an entity, along with the components `U` of an entity it
+/// references, as returned by [`List::join_ref`](crate::backend::List::join_ref).
+///
+/// `U` is `None` if the reference is null, or the referenced entity doesn't
+/// have all of the components `U`.
+#[derive(Debug)]
+pub struct Referencing<T, U> {
+    pub components: T,
+    pub referenced: Option<U>,
+}
+
+/// Only the expression of the referencing components is included, since the
+/// referenced ones are joined in by [`List::join_ref`](crate::backend::List::join_ref).
+impl<DB, T, U> Deserializeable<DB> for Referencing<T, U>
+where
+    DB: sqlx::Database,
+    T: Deserializeable<DB>,
+    U: Deserializeable<DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    fn cte() -> Box<dyn CommonTableExpression> {
+        <T as Deserializeable<DB>>::cte()
+    }
+
+    fn deserialize(row: &mut OffsetRow<<DB as sqlx::Database>::Row>) -> Result<Self, sqlx::Error> {
+        let components = <T as Deserializeable<DB>>::deserialize(row)?;
+
+        // The referenced entity's ID precedes its components, and is null
+        // whenever there was nothing to join.
+        let referenced = if row.is_null() {
+            let columns = crate::cte::column_names(<U as Deserializeable<DB>>::cte().as_ref())
+                .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+
+            row.skip(1 + columns.len());
+            None
+        } else {
+            row.skip(1);
+            Some(<U as Deserializeable<DB>>::deserialize(row)?)
+        };
+
+        Ok(Referencing {
+            components,
+            referenced,
+        })
+    }
+}
+
+/// Components of an entity found by [`List::descendants`](crate::backend::List::descendants)
+/// or [`List::ancestors`](crate::backend::List::ancestors), along with the number of
+/// references followed to reach it from the root entity.
+#[derive(Debug)]
+pub struct Traversed<T> {
+    pub depth: i32,
+    pub components: T,
+}
+
+/// Only the expression of the components is included, since the entities
+/// and their depths are joined in by the [`List`](crate::backend::List).
+impl<DB, T> Deserializeable<DB> for Traversed<T>
+where
+    DB: sqlx::Database,
+    T: Deserializeable<DB>,
+    i32: for<'d> Decode<'d, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    fn cte() -> Box<dyn CommonTableExpression> {
+        <T as Deserializeable<DB>>::cte()
+    }
+
+    fn deserialize(row: &mut OffsetRow<<DB as sqlx::Database>::Row>) -> Result<Self, sqlx::Error> {
+        Ok(Traversed {
+            depth: row.try_get::<i32>()?,
+            components: <T as Deserializeable<DB>>::deserialize(row)?,
+        })
+    }
+}
+
 impl<'r, R, EntityId, T> FromRow<'r, R> for Entity<EntityId, T>
 where
     R: Row,
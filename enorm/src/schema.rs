@@ -0,0 +1,123 @@
+use std::marker::PhantomData;
+
+use sqlx::{Database, Executor, IntoArguments, Pool};
+
+use crate::{
+    component::{Component, DatabaseColumnType},
+    error::Error,
+    migration::DatabaseMigration,
+    registry::RegisteredTable,
+    tables::Tables,
+};
+
+/// Collection of component tables, which can be created and verified together.
+///
+/// ```ignore
+/// let schema = Schema::new()
+///     .component::<Name>()
+///     .archetype::<(Age, Position)>();
+///
+/// backend.register_schema(&schema).await?;
+/// ```
+pub struct Schema<DB: Database, EntityId> {
+    tables: Vec<(RegisteredTable<DB>, Vec<String>)>,
+    _data: PhantomData<(DB, EntityId)>,
+}
+
+impl<DB: Database, EntityId> Default for Schema<DB, EntityId> {
+    fn default() -> Self {
+        Schema {
+            tables: Vec::new(),
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<DB, EntityId> Schema<DB, EntityId>
+where
+    DB: DatabaseColumnType,
+    EntityId: sqlx::Type<DB>,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include the table of the component `T`.
+    pub fn component<T: Component<DB>>(mut self) -> Self {
+        self.add::<T>();
+        self
+    }
+
+    /// Include the tables of all components of the archetype `T`.
+    pub fn archetype<T: Tables<DB>>(mut self) -> Self {
+        <T as Tables<DB>>::tables(&mut self);
+        self
+    }
+
+    /// Include the table of the component `T`, unless it already is.
+    pub fn add<T: Component<DB>>(&mut self) {
+        let table = <T as Component<DB>>::table();
+
+        if !self
+            .tables
+            .iter()
+            .any(|(registered, _)| registered.name() == table)
+        {
+            let mut statements = vec![<T as Component<DB>>::create_table_statement::<EntityId>()];
+            statements.extend(<T as Component<DB>>::create_index_statements());
+            statements.extend(<T as Component<DB>>::create_history_statements::<EntityId>());
+
+            self.tables.push((RegisteredTable::of::<T>(), statements));
+        }
+    }
+
+    /// Names of the included tables, in the order they were added.
+    pub fn tables(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.tables.iter().map(|(registered, _)| registered.name())
+    }
+
+    /// The included tables, as registered with a backend.
+    pub(crate) fn components(&self) -> impl Iterator<Item = RegisteredTable<DB>> + '_ {
+        self.tables.iter().map(|(registered, _)| *registered)
+    }
+
+    /// Statements creating the included tables and their indexes.
+    pub fn statements(&self) -> impl Iterator<Item = &str> {
+        self.tables
+            .iter()
+            .flat_map(|(_, statements)| statements.iter().map(String::as_str))
+    }
+
+    /// Create all tables which don't exist yet, within a single transaction.
+    pub async fn create(&self, pool: &Pool<DB>) -> Result<(), Error>
+    where
+        for<'c> <DB as Database>::Arguments<'c>: IntoArguments<'c, DB>,
+        for<'c> &'c mut <DB as Database>::Connection: Executor<'c, Database = DB>,
+    {
+        let mut tx = pool.begin().await?;
+
+        for statement in self.statements() {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Names of the included tables which don't exist.
+    pub async fn missing_tables(&self, pool: &Pool<DB>) -> Result<Vec<&'static str>, Error>
+    where
+        DB: DatabaseMigration,
+    {
+        let mut missing = Vec::new();
+
+        for table in self.tables() {
+            if DB::table_columns(pool, table).await?.is_empty() {
+                missing.push(table);
+            }
+        }
+
+        Ok(missing)
+    }
+}
@@ -1,6 +1,8 @@
 use sqlx::{query::Query, ColumnIndex, Database};
 
-use crate::{cte::*, entity::EntityPrefixedQuery, row::OffsetRow, tables::Removable};
+use crate::{
+    bulk::BulkInsert, cte::*, entity::EntityPrefixedQuery, row::OffsetRow, tables::Removable,
+};
 
 pub trait Deserializeable<DB: Database>: Sized {
     fn cte() -> Box<dyn CommonTableExpression>;
@@ -24,6 +26,29 @@ pub trait Serializable<DB: Database>: Sized {
         query: &mut EntityPrefixedQuery<'query, DB, EntityId>,
     ) where
         EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query;
+
+    /// Insert the components, replacing any the entity already has.
+    fn upsert<'query, EntityId>(
+        &'query self,
+        query: &mut EntityPrefixedQuery<'query, DB, EntityId>,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query;
+
+    /// Insert the components of many entities.
+    ///
+    /// By default each entity is inserted using its own queries.
+    fn insert_many<'query, EntityId>(
+        rows: &[(&'query EntityId, &'query Self)],
+        query: &mut BulkInsert<'query, DB>,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + 'query,
+    {
+        for (entity, components) in rows {
+            let mut inserts = EntityPrefixedQuery::new(*entity);
+            components.insert(&mut inserts);
+            query.extend(inserts.queries);
+        }
+    }
 }
 
 impl<T: Deserializeable<DB>, DB: Database> Deserializeable<DB> for Option<T>
@@ -46,6 +71,60 @@ where
     }
 }
 
+/// Optional components are only inserted or updated if present.
+impl<T: Serializable<DB>, DB: Database> Serializable<DB> for Option<T> {
+    fn serialize<'query>(
+        &'query self,
+        query: Query<'query, DB, <DB as Database>::Arguments<'query>>,
+    ) -> Query<'query, DB, <DB as Database>::Arguments<'query>> {
+        match self {
+            Some(inner) => inner.serialize(query),
+            None => query,
+        }
+    }
+
+    fn insert<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        if let Some(inner) = self {
+            inner.insert(query);
+        }
+    }
+
+    fn update<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        if let Some(inner) = self {
+            inner.update(query);
+        }
+    }
+
+    fn upsert<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        if let Some(inner) = self {
+            inner.upsert(query);
+        }
+    }
+
+    fn insert_many<'query, EntityId>(
+        rows: &[(&'query EntityId, &'query Self)],
+        query: &mut BulkInsert<'query, DB>,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + 'query,
+    {
+        let present: Vec<_> = rows
+            .iter()
+            .filter_map(|(entity, components)| Some((*entity, components.as_ref()?)))
+            .collect();
+
+        <T as Serializable<DB>>::insert_many(&present, query);
+    }
+}
+
 impl<T: Removable<DB>, DB: Database> Removable<DB> for Option<T> {
     fn remove<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
     where
@@ -125,6 +204,37 @@ macro_rules! impl_deser_for_db{
                     self.$index.update(query);
                 )*
             }
+
+            fn upsert<'query, EntityId>(
+                &'query self,
+                query: &mut EntityPrefixedQuery<'query, $db, EntityId>
+            )
+            where
+                EntityId: sqlx::Encode<'query, $db> + sqlx::Type<$db> + Clone + 'query
+            {
+                $(
+                    #[allow(unused)]
+                    const $list: () = ();
+                    self.$index.upsert(query);
+                )*
+            }
+
+            fn insert_many<'query, EntityId>(
+                rows: &[(&'query EntityId, &'query Self)],
+                query: &mut BulkInsert<'query, $db>,
+            )
+            where
+                EntityId: sqlx::Encode<'query, $db> + sqlx::Type<$db> + 'query
+            {
+                $(
+                    let components: Vec<_> = rows
+                        .iter()
+                        .map(|(entity, components)| (*entity, &components.$index))
+                        .collect();
+
+                    <$list as Serializable<$db>>::insert_many(&components, query);
+                )*
+            }
         }
     }
 }
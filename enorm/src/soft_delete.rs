@@ -0,0 +1,26 @@
+use sqlx::Database;
+
+use crate::component::Component;
+
+/// Name of the column holding the time a soft-deleted component was removed at.
+pub const DELETED_AT_COLUMN: &str = "deleted_at";
+
+/// Components declared using `#[enorm(soft_delete)]`.
+///
+/// Their tables hold a `deleted_at` column, and removing the component only
+/// sets it, leaving the row in place as a tombstone. Tombstoned components are
+/// excluded from all queries, unless listed using
+/// [`List::include_deleted`](crate::backend::List::include_deleted), and aren't
+/// updated anymore. Upserting the component replaces its tombstone, whereas
+/// inserting it fails until the tombstone is purged.
+///
+/// ```ignore
+/// backend.remove::<Account>(&id).await?;
+///
+/// // Permanently delete all tombstoned accounts.
+/// backend.purge::<Account>().await?;
+/// ```
+pub trait SoftDeleteComponent<DB: Database>: Component<DB> {
+    /// Permanently deletes all tombstoned components.
+    const PURGE: &'static str;
+}
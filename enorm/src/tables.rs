@@ -1,6 +1,6 @@
 use sqlx::Database;
 
-use crate::entity::EntityPrefixedQuery;
+use crate::{component::DatabaseColumnType, entity::EntityPrefixedQuery, schema::Schema};
 
 pub trait Removable<DB: Database>: Sized {
     fn remove<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
@@ -50,3 +50,38 @@ impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5);
 impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6);
 impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7);
 impl_compound!(A:0, B:1, C:2, D:3, E:4, F:5, G:6, H:7, I:8);
+
+/// Types whose components are stored in tables, such as components themselves,
+/// archetypes and tuples thereof.
+pub trait Tables<DB: DatabaseColumnType> {
+    /// Include the tables of all components in the schema.
+    fn tables<EntityId: sqlx::Type<DB>>(schema: &mut Schema<DB, EntityId>);
+}
+
+impl<DB: DatabaseColumnType, T: Tables<DB>> Tables<DB> for Option<T> {
+    fn tables<EntityId: sqlx::Type<DB>>(schema: &mut Schema<DB, EntityId>) {
+        <T as Tables<DB>>::tables(schema)
+    }
+}
+
+macro_rules! impl_tables_for_tuple {
+    ($($list:ident),*) => {
+        impl<DB: DatabaseColumnType, $($list),*> Tables<DB> for ($($list,)*)
+        where
+            $($list: Tables<DB>,)*
+        {
+            fn tables<EntityId: sqlx::Type<DB>>(schema: &mut Schema<DB, EntityId>) {
+                $(<$list as Tables<DB>>::tables(schema);)*
+            }
+        }
+    };
+}
+
+impl_tables_for_tuple!(A, B);
+impl_tables_for_tuple!(A, B, C);
+impl_tables_for_tuple!(A, B, C, D);
+impl_tables_for_tuple!(A, B, C, D, E);
+impl_tables_for_tuple!(A, B, C, D, E, F);
+impl_tables_for_tuple!(A, B, C, D, E, F, G);
+impl_tables_for_tuple!(A, B, C, D, E, F, G, H);
+impl_tables_for_tuple!(A, B, C, D, E, F, G, H, I);
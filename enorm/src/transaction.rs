@@ -0,0 +1,282 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use sqlx::{ColumnIndex, Database, Executor, IntoArguments};
+
+use crate::{
+    archetype::{component_tables, Archetype, DatabasePlaceholder},
+    backend::{fetch_single, GenerateNew, List, Pagination, Report, Source},
+    bulk::DatabaseBulkInsert,
+    changes::{changed, upserted, Broadcast, Change, ChangeKind},
+    condition::All,
+    entity::RowsAffected,
+    error::Error,
+    registry::present,
+    row::Entity,
+    serialization::{Deserializeable, Serializable},
+    tables::Removable,
+};
+
+/// Groups several operations into a single database transaction.
+///
+/// Created using [`Backend::transaction`](crate::backend::Backend::transaction),
+/// and exposes the same operations as the backend itself. None of the changes
+/// are visible outside of the transaction until it is committed.
+///
+/// If the transaction is dropped without calling [`Transaction::commit`], it is
+/// rolled back.
+pub struct Transaction<'c, DB: Database, EntityId> {
+    tx: sqlx::Transaction<'c, DB>,
+    /// Subscribers to notify of the changes made, once committed.
+    changes: Option<Arc<Broadcast<EntityId>>>,
+    pending: Vec<Change<EntityId>>,
+    _entity: PhantomData<EntityId>,
+}
+
+impl<'c, DB: Database, EntityId> Transaction<'c, DB, EntityId> {
+    /// Wrap an existing [`sqlx::Transaction`].
+    pub fn new(tx: sqlx::Transaction<'c, DB>) -> Self {
+        Transaction {
+            tx,
+            changes: None,
+            pending: Vec::new(),
+            _entity: PhantomData,
+        }
+    }
+
+    /// Report the changes made within the transaction to the subscribers, once committed.
+    #[cfg(any(feature = "sqlite", feature = "mysql"))]
+    pub(crate) fn with_changes(mut self, changes: Arc<Broadcast<EntityId>>) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
+    /// Discard all changes made within the transaction.
+    pub async fn rollback(self) -> Result<(), Error> {
+        self.tx.rollback().await.map_err(Error::from)
+    }
+}
+
+impl<DB: Database, EntityId> Source<DB> for &mut Transaction<'_, DB, EntityId>
+where
+    for<'q> &'q mut <DB as sqlx::Database>::Connection: Executor<'q, Database = DB>,
+    EntityId: Send,
+{
+    fn executor(&mut self) -> impl Executor<'_, Database = DB> {
+        &mut *self.tx
+    }
+
+    async fn begin(&mut self) -> Result<sqlx::Transaction<'_, DB>, Error> {
+        Ok(sqlx::Transaction::begin(&mut *self.tx).await?)
+    }
+}
+
+/// Changes of writes through the lists of the transaction are reported once
+/// it is committed, along with its other changes.
+impl<DB: Database, EntityId: Clone> Report<EntityId> for &mut Transaction<'_, DB, EntityId> {
+    fn subscribed(&self) -> bool {
+        self.changes
+            .as_ref()
+            .is_some_and(|changes| changes.subscribed())
+    }
+
+    fn report(&mut self, changes: Vec<Change<EntityId>>) {
+        if let Some(broadcast) = &self.changes {
+            self.pending.extend(broadcast.pending(|| changes));
+        }
+    }
+}
+
+impl<'c, DB, EntityId> Transaction<'c, DB, EntityId>
+where
+    DB: Database + DatabasePlaceholder + DatabaseBulkInsert,
+    for<'q> <DB as sqlx::Database>::Arguments<'q>: IntoArguments<'q, DB> + Send,
+    for<'q> &'q mut <DB as sqlx::Database>::Connection: Executor<'q, Database = DB>,
+    EntityId: for<'q> sqlx::Encode<'q, DB>
+        + for<'r> sqlx::Decode<'r, DB>
+        + sqlx::Type<DB>
+        + Unpin
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    /// Commit all changes made within the transaction.
+    pub async fn commit(self) -> Result<(), Error> {
+        self.tx.commit().await?;
+
+        if let Some(changes) = self.changes {
+            changes.send(self.pending);
+        }
+
+        Ok(())
+    }
+
+    /// What the changes of a write are determined from, which is only
+    /// computed if anyone is subscribed.
+    fn pending<T>(&self, changes: impl FnOnce() -> Vec<T>) -> Vec<T> {
+        match &self.changes {
+            Some(broadcast) => broadcast.pending(changes),
+            None => Vec::new(),
+        }
+    }
+
+    /// Remember the changes for when the transaction is committed.
+    fn record(&mut self, changes: impl FnOnce() -> Vec<Change<EntityId>>) {
+        if let Some(broadcast) = &self.changes {
+            self.pending.extend(broadcast.pending(changes));
+        }
+    }
+
+    pub async fn spawn<T>(&mut self, components: &T) -> Result<EntityId, Error>
+    where
+        EntityId: GenerateNew,
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    {
+        let entity = EntityId::generate_new();
+        self.insert(&entity, components).await?;
+        Ok(entity)
+    }
+
+    pub async fn insert<T>(&mut self, entity: &EntityId, components: &T) -> Result<(), Error>
+    where
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    {
+        <T as Archetype<DB>>::insert_in(components, &mut self.tx, entity).await?;
+        self.record(|| changed(entity, &components.component_tables(), ChangeKind::Insert));
+
+        Ok(())
+    }
+
+    pub async fn insert_many<T>(&mut self, entities: &[(EntityId, T)]) -> Result<(), Error>
+    where
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    {
+        let rows: Vec<_> = entities
+            .iter()
+            .map(|(entity, components)| (entity, components))
+            .collect();
+
+        <T as Archetype<DB>>::insert_many_in(&rows, &mut self.tx).await?;
+        self.record(|| {
+            rows.iter()
+                .flat_map(|(entity, components)| {
+                    changed(*entity, &components.component_tables(), ChangeKind::Insert)
+                })
+                .collect()
+        });
+
+        Ok(())
+    }
+
+    pub async fn spawn_many<T>(&mut self, components: &[T]) -> Result<Vec<EntityId>, Error>
+    where
+        EntityId: GenerateNew,
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+    {
+        let entities: Vec<_> = components
+            .iter()
+            .map(|_| EntityId::generate_new())
+            .collect();
+
+        let rows: Vec<_> = entities.iter().zip(components).collect();
+
+        <T as Archetype<DB>>::insert_many_in(&rows, &mut self.tx).await?;
+        self.record(|| {
+            rows.iter()
+                .flat_map(|(entity, components)| {
+                    changed(*entity, &components.component_tables(), ChangeKind::Insert)
+                })
+                .collect()
+        });
+
+        Ok(entities)
+    }
+
+    pub async fn update<T>(&mut self, entity: &EntityId, components: &T) -> Result<(), Error>
+    where
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+        <DB as Database>::QueryResult: RowsAffected,
+        for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        let tables = self.pending(|| components.component_tables());
+
+        <T as Archetype<DB>>::update_in(components, &mut self.tx, entity).await?;
+
+        // Components which the entity doesn't have aren't updated.
+        let updated = present(&mut *self.tx, entity, tables).await?;
+        self.record(|| changed(entity, &updated, ChangeKind::Update));
+
+        Ok(())
+    }
+
+    pub async fn upsert<T>(&mut self, entity: &EntityId, components: &T) -> Result<(), Error>
+    where
+        T: Archetype<DB> + Serializable<DB> + Unpin + Send + 'static,
+        for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        let tables = self.pending(|| components.component_tables());
+
+        // Components which the entity already has are updated, the others inserted.
+        let updated = present(&mut *self.tx, entity, tables.clone()).await?;
+        <T as Archetype<DB>>::upsert_in(components, &mut self.tx, entity).await?;
+        self.record(|| upserted(entity, tables, &updated));
+
+        Ok(())
+    }
+
+    pub async fn remove<T>(&mut self, entity: &EntityId) -> Result<(), Error>
+    where
+        T: Archetype<DB> + Removable<DB> + Unpin + Send + 'static,
+        for<'r> String: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    {
+        let tables = self.pending(|| component_tables(<T as Deserializeable<DB>>::cte().as_ref()));
+
+        // Components which the entity doesn't have aren't removed.
+        let removed = present(&mut *self.tx, entity, tables).await?;
+        <T as Archetype<DB>>::remove_in(&mut self.tx, entity).await?;
+        self.record(|| changed(entity, &removed, ChangeKind::Remove));
+
+        Ok(())
+    }
+
+    /// Query the transaction for entities.
+    ///
+    /// See [`List`] for options allowng further filtering and processing.
+    #[allow(clippy::type_complexity)]
+    pub fn list<T>(
+        &mut self,
+    ) -> List<
+        DB,
+        EntityId,
+        T,
+        (),
+        All,
+        Entity<EntityId, T>,
+        fn(Entity<EntityId, T>) -> Entity<EntityId, T>,
+        &mut Transaction<'c, DB, EntityId>,
+    > {
+        fn identity<EntityId, T>(entity: Entity<EntityId, T>) -> Entity<EntityId, T> {
+            entity
+        }
+        List {
+            source: self,
+            _data: PhantomData,
+            condition: All,
+            map: identity::<EntityId, T>,
+            pagination: Pagination::default(),
+            joins: Vec::new(),
+            traversal: None,
+            include_deleted: false,
+            as_of: None,
+            changes: None,
+        }
+    }
+
+    pub async fn get<T>(&mut self, entity: &EntityId) -> Result<T, Error>
+    where
+        T: Deserializeable<DB> + Unpin + Send + 'static,
+    {
+        fetch_single(&mut *self.tx, entity, <T as Deserializeable<DB>>::cte()).await
+    }
+}
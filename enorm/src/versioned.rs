@@ -0,0 +1,181 @@
+use std::ops::{Deref, DerefMut};
+
+use sqlx::{query::Query, ColumnIndex, Database};
+
+use crate::{
+    bulk::BulkInsert,
+    changes::ChangeKind,
+    component::{Component, DatabaseColumnType},
+    cte::{CommonTableExpression, Merge, Version},
+    entity::EntityPrefixedQuery,
+    prelude::Archetype,
+    row::OffsetRow,
+    schema::Schema,
+    serialization::{Deserializeable, Serializable},
+    tables::{Removable, Tables},
+};
+
+/// Name of the hidden column holding the version of a versioned component.
+pub const VERSION_COLUMN: &str = "__enorm_version";
+
+/// Components declared using `#[enorm(versioned)]`.
+///
+/// Their tables hold a hidden version column, which starts at 1 and is
+/// incremented by every update.
+pub trait VersionedComponent<DB: Database>: Component<DB> {
+    /// Updates the component like [`Component::UPDATE`], but only if its
+    /// version matches the one bound after the columns.
+    const GUARDED_UPDATE: &'static str;
+    /// Selects the component of the entity bound first, unless it has been
+    /// removed, telling a conflicting update apart from a missing component.
+    const EXISTS: &'static str;
+}
+
+/// A versioned component, along with the version it was read at.
+///
+/// Updating a `Versioned` component only succeeds if nobody else has updated
+/// it since it was read, and otherwise fails with
+/// [`Error::Conflict`](crate::Error::Conflict), leaving it unchanged. If the
+/// component has been removed since, the update fails with
+/// [`Error::NotFound`](crate::Error::NotFound) instead.
+///
+/// ```ignore
+/// let mut player = backend.get::<Versioned<Player>>(&id).await?;
+/// player.score += 10;
+///
+/// backend.update(&id, &player).await?;
+/// ```
+///
+/// Inserting a `Versioned` component ignores its version, since new
+/// components always start at version 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Versioned<T> {
+    pub version: i64,
+    pub component: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn into_inner(self) -> T {
+        self.component
+    }
+}
+
+impl<T> Deref for Versioned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.component
+    }
+}
+
+impl<T> DerefMut for Versioned<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.component
+    }
+}
+
+impl<T, DB> Deserializeable<DB> for Versioned<T>
+where
+    DB: Database,
+    T: VersionedComponent<DB>,
+    for<'r> i64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+    fn cte() -> Box<dyn CommonTableExpression> {
+        Box::new(Merge {
+            tables: vec![
+                <T as Deserializeable<DB>>::cte(),
+                Box::new(Version {
+                    table: <T as Component<DB>>::table(),
+                }),
+            ],
+        })
+    }
+
+    fn deserialize(row: &mut OffsetRow<<DB as Database>::Row>) -> Result<Self, sqlx::Error> {
+        let component = <T as Deserializeable<DB>>::deserialize(row)?;
+        let version = row.try_get::<i64>()?;
+
+        Ok(Versioned { version, component })
+    }
+}
+
+impl<T, DB> Serializable<DB> for Versioned<T>
+where
+    DB: Database,
+    T: VersionedComponent<DB>,
+    for<'q> i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+{
+    fn serialize<'query>(
+        &'query self,
+        query: Query<'query, DB, <DB as Database>::Arguments<'query>>,
+    ) -> Query<'query, DB, <DB as Database>::Arguments<'query>> {
+        <T as Serializable<DB>>::serialize(&self.component, query)
+    }
+
+    fn insert<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        <T as Serializable<DB>>::insert(&self.component, query)
+    }
+
+    fn update<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        query.guarded::<T>(
+            <T as VersionedComponent<DB>>::GUARDED_UPDATE,
+            move |query| {
+                <T as Serializable<DB>>::serialize(&self.component, query).bind(self.version)
+            },
+        );
+
+        <T as Component<DB>>::record_history(query, ChangeKind::Update)
+    }
+
+    fn upsert<'query, EntityId>(&'query self, query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        <T as Serializable<DB>>::upsert(&self.component, query)
+    }
+
+    fn insert_many<'query, EntityId>(
+        rows: &[(&'query EntityId, &'query Self)],
+        query: &mut BulkInsert<'query, DB>,
+    ) where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + 'query,
+    {
+        let components: Vec<_> = rows
+            .iter()
+            .map(|(entity, versioned)| (*entity, &versioned.component))
+            .collect();
+
+        <T as Serializable<DB>>::insert_many(&components, query)
+    }
+}
+
+impl<T, DB> Archetype<DB> for Versioned<T>
+where
+    DB: Database,
+    T: VersionedComponent<DB>,
+    for<'r> i64: sqlx::Decode<'r, DB> + sqlx::Type<DB>,
+    usize: ColumnIndex<<DB as Database>::Row>,
+{
+}
+
+impl<T: VersionedComponent<DB> + Removable<DB>, DB: Database> Removable<DB> for Versioned<T> {
+    fn remove<'query, EntityId>(query: &mut EntityPrefixedQuery<'query, DB, EntityId>)
+    where
+        EntityId: sqlx::Encode<'query, DB> + sqlx::Type<DB> + Clone + 'query,
+    {
+        <T as Removable<DB>>::remove(query)
+    }
+}
+
+impl<T: VersionedComponent<DB> + Tables<DB>, DB: DatabaseColumnType> Tables<DB> for Versioned<T> {
+    fn tables<EntityId: sqlx::Type<DB>>(schema: &mut Schema<DB, EntityId>) {
+        <T as Tables<DB>>::tables(schema)
+    }
+}
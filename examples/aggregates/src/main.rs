@@ -0,0 +1,80 @@
+use enorm::prelude::*;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Age(i64);
+
+#[derive(Component, Debug)]
+pub struct Dead;
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+    backend.register::<Dead>().await.unwrap();
+
+    backend
+        .insert(&1, &(Name("Jimothy".to_string()), Age(10)))
+        .await
+        .unwrap();
+
+    backend
+        .insert(&2, &(Name("Andrea".to_string()), Age(32)))
+        .await
+        .unwrap();
+
+    backend
+        .insert(&3, &(Name("Tutankhamun".to_string()), Age(3300), Dead))
+        .await
+        .unwrap();
+
+    // Aggregates are computed by the database, without fetching the entities.
+    assert_eq!(backend.list::<Name>().count().await.unwrap(), 3);
+    assert_eq!(
+        backend
+            .list::<Name>()
+            .without::<Dead>()
+            .count()
+            .await
+            .unwrap(),
+        2
+    );
+
+    assert!(backend
+        .list::<Age>()
+        .filter(Age::FIELDS.self_0.gt(18))
+        .exists()
+        .await
+        .unwrap());
+
+    assert!(!backend
+        .list::<Age>()
+        .filter(Age::FIELDS.self_0.gt(5000))
+        .exists()
+        .await
+        .unwrap());
+
+    let living = || backend.list::<Age>().without::<Dead>();
+
+    assert_eq!(living().sum(Age::FIELDS.self_0).await.unwrap(), Some(42));
+    assert_eq!(living().min(Age::FIELDS.self_0).await.unwrap(), Some(10));
+    assert_eq!(living().max(Age::FIELDS.self_0).await.unwrap(), Some(32));
+    assert_eq!(living().avg(Age::FIELDS.self_0).await.unwrap(), Some(21.0));
+
+    // Aggregating over no entities at all yields nothing.
+    assert_eq!(
+        backend
+            .list::<Age>()
+            .filter(Age::FIELDS.self_0.gt(5000))
+            .sum(Age::FIELDS.self_0)
+            .await
+            .unwrap(),
+        None
+    );
+}
@@ -17,6 +17,9 @@ impl Deserializeable<Sqlite> for LightSwitch {
                 Box::new(Extract {
                     table: "LightSwitch",
                     columns: &["field_a", "field_b"],
+                    exclude_deleted: false,
+                    history: false,
+                    as_of: None,
                 }),
                 Counter::cte(),
             ],
@@ -97,6 +100,17 @@ impl Serializable<Sqlite> for LightSwitch {
             <Self as Serializable<::sqlx::Sqlite>>::serialize(self, query)
         })
     }
+
+    fn upsert<'query, EntityId>(
+        &'query self,
+        query: &mut enorm::entity::EntityPrefixedQuery<'query, Sqlite, EntityId>,
+    ) where
+        EntityId: sqlx::Encode<'query, Sqlite> + sqlx::Type<Sqlite> + Clone + 'query,
+    {
+        query.query(<Self as Component<::sqlx::Sqlite>>::UPSERT, move |query| {
+            <Self as Serializable<::sqlx::Sqlite>>::serialize(self, query)
+        })
+    }
 }
 
 impl Component<Sqlite> for LightSwitch {
@@ -104,6 +118,8 @@ impl Component<Sqlite> for LightSwitch {
 
     const UPDATE: &'static str = "";
 
+    const UPSERT: &'static str = "";
+
     const DELETE: &'static str = "";
 
     fn table() -> &'static str {
@@ -114,20 +130,15 @@ impl Component<Sqlite> for LightSwitch {
         todo!()
     }
 
-    fn create_component_table<'pool, EntityId>(
-        pool: &'pool sqlx::Pool<Sqlite>,
-    ) -> impl std::future::Future<
-        Output = Result<<Sqlite as sqlx::Database>::QueryResult, sqlx::Error>,
-    > + Send
+    fn create_table_statement<EntityId>() -> String
     where
         EntityId: sqlx::Type<Sqlite>,
     {
         use sqlx::TypeInfo;
-        async move {
-            let sql = format!("create table if not exists LightSwitch(entity {} primary key, field_a integer null, field_b integer null);", <EntityId as sqlx::Type::<Sqlite>>::type_info().name());
-
-            sqlx::query(&sql).execute(pool).await
-        }
+        format!(
+            "create table if not exists LightSwitch(entity {} primary key, field_a integer null, field_b integer null);",
+            <EntityId as sqlx::Type<Sqlite>>::type_info().name()
+        )
     }
 }
 
@@ -0,0 +1,70 @@
+use enorm::prelude::*;
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Age(i64);
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Robot;
+
+#[derive(Archetype, Debug, PartialEq, Eq)]
+struct Person {
+    name: Name,
+    age: Option<Age>,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+    backend.register::<Robot>().await.unwrap();
+
+    // Each component table is filled using as few statements as possible,
+    // all within a single transaction.
+    let people: Vec<_> = (0..50_000)
+        .map(|i| {
+            let person = Person {
+                name: Name(format!("Person #{i}")),
+                age: (i % 2 == 0).then_some(Age(i)),
+            };
+
+            (i, person)
+        })
+        .collect();
+
+    backend.insert_many(&people).await.unwrap();
+
+    assert_eq!(backend.list::<Name>().count().await.unwrap(), 50_000);
+    assert_eq!(backend.list::<Age>().count().await.unwrap(), 25_000);
+    assert_eq!(
+        backend.get::<Person>(&1234).await.unwrap(),
+        Person {
+            name: Name("Person #1234".to_string()),
+            age: Some(Age(1234)),
+        }
+    );
+
+    // Tuples work just as well.
+    let robots = [
+        (100_000, (Name("R2-D2".to_string()), Robot)),
+        (100_001, (Name("C-3PO".to_string()), Robot)),
+    ];
+
+    backend.insert_many(&robots).await.unwrap();
+    assert_eq!(backend.list::<Robot>().count().await.unwrap(), 2);
+
+    // If any of the entities can't be inserted, none of them are.
+    let duplicates = [(200_000, Age(10)), (1, Age(20)), (1, Age(30))];
+
+    assert!(matches!(
+        backend.insert_many(&duplicates).await,
+        Err(enorm::Error::ConstraintViolation(_))
+    ));
+    assert!(backend.get::<Age>(&200_000).await.is_err());
+}
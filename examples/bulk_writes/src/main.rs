@@ -0,0 +1,188 @@
+use std::time::{Duration, SystemTime};
+
+use enorm::prelude::*;
+use futures::{StreamExt as _, TryStreamExt as _};
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+#[enorm(history)]
+pub struct Player {
+    team: String,
+    score: i64,
+}
+
+#[derive(Component, Debug, PartialEq)]
+#[enorm(soft_delete)]
+pub struct Session {
+    expires_at: i64,
+}
+
+#[derive(Component, Debug)]
+pub struct Banned;
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Player>().await.unwrap();
+    backend.register::<Session>().await.unwrap();
+    backend.register::<Banned>().await.unwrap();
+
+    for (entity, team, score) in [(1, "red", 10), (2, "red", 20), (3, "blue", 30)] {
+        backend
+            .insert(
+                &entity,
+                &(
+                    Name(format!("Player {entity}")),
+                    Player {
+                        team: team.to_string(),
+                        score,
+                    },
+                    Session {
+                        expires_at: entity * 100,
+                    },
+                ),
+            )
+            .await
+            .unwrap();
+    }
+    backend.insert(&3, &Banned).await.unwrap();
+
+    let changes = backend.changes().await.unwrap();
+    let mut changes = std::pin::pin!(changes);
+
+    std::thread::sleep(Duration::from_millis(2));
+    let before = SystemTime::now();
+    std::thread::sleep(Duration::from_millis(2));
+
+    // All matching components are updated by a single statement.
+    let updated = backend
+        .list::<Player>()
+        .filter(Player::FIELDS.team.equals("red".to_string()))
+        .update_set(Assignments::new().increment(Player::FIELDS.score, 5))
+        .await
+        .unwrap();
+
+    println!("{updated}");
+    // 2
+    assert_eq!(updated, 2);
+
+    let scores = backend
+        .list::<Player>()
+        .order_by(Player::FIELDS.score, Order::Asc)
+        .components()
+        .fetch()
+        .map_ok(|player| player.score)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(scores, vec![15, 25, 30]);
+
+    // Each updated component is reported, and recorded in its history.
+    for entity in [1, 2] {
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(
+            (change.entity, change.table, change.kind),
+            (entity, "Player", ChangeKind::Update)
+        );
+    }
+    assert_eq!(
+        backend.get_at::<Player>(&1, before).await.unwrap().score,
+        10
+    );
+
+    // Filters restrict the writes just like they restrict the results.
+    backend
+        .list::<Player>()
+        .with::<Banned>()
+        .update_set(Assignments::new().set(Player::FIELDS.score, 0))
+        .await
+        .unwrap();
+    assert_eq!(backend.get::<Player>(&3).await.unwrap().score, 0);
+    changes.next().await.unwrap().unwrap();
+
+    // Deleting removes the component from all matching entities, which keep
+    // their other components.
+    let removed = backend
+        .list::<Player>()
+        .filter(Player::FIELDS.score.less_than(20))
+        .delete()
+        .await
+        .unwrap();
+    assert_eq!(removed, 2);
+    assert_eq!(backend.list::<Player>().count().await.unwrap(), 1);
+    assert_eq!(backend.list::<Name>().count().await.unwrap(), 3);
+
+    for entity in [1, 3] {
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(
+            (change.entity, change.table, change.kind),
+            (entity, "Player", ChangeKind::Remove)
+        );
+    }
+
+    // Soft-deleted components are tombstoned instead.
+    let expired = backend
+        .list::<Session>()
+        .filter(Session::FIELDS.expires_at.less_than(250))
+        .delete()
+        .await
+        .unwrap();
+    assert_eq!(expired, 2);
+    assert_eq!(backend.list::<Session>().count().await.unwrap(), 1);
+    assert_eq!(
+        backend
+            .list::<Session>()
+            .include_deleted()
+            .count()
+            .await
+            .unwrap(),
+        3
+    );
+
+    for entity in [1, 2] {
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(
+            (change.entity, change.table, change.kind),
+            (entity, "Session", ChangeKind::Remove)
+        );
+    }
+
+    // Tombstones are left untouched by later writes.
+    let expired = backend
+        .list::<Session>()
+        .include_deleted()
+        .delete()
+        .await
+        .unwrap();
+    assert_eq!(expired, 1);
+    assert_eq!(changes.next().await.unwrap().unwrap().entity, 3);
+    assert_eq!(backend.purge::<Session>().await.unwrap(), 3);
+
+    // Without any assignments, nothing is written.
+    let updated = backend
+        .list::<Player>()
+        .update_set(Assignments::new())
+        .await
+        .unwrap();
+    assert_eq!(updated, 0);
+
+    // Within transactions, changes are only reported once committed.
+    let mut tx = backend.transaction().await.unwrap();
+    let removed = tx.list::<Name>().delete().await.unwrap();
+    assert_eq!(removed, 3);
+    assert_eq!(tx.list::<Name>().count().await.unwrap(), 0);
+    tx.commit().await.unwrap();
+
+    for entity in [1, 2, 3] {
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(
+            (change.entity, change.table, change.kind),
+            (entity, "Name", ChangeKind::Remove)
+        );
+    }
+    assert_eq!(backend.list::<Name>().count().await.unwrap(), 0);
+}
@@ -0,0 +1,146 @@
+use enorm::prelude::*;
+use futures::StreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Age(i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+
+    // Only changes made after subscribing are reported.
+    let changes = backend.changes().await.unwrap();
+    let mut changes = std::pin::pin!(changes);
+
+    backend
+        .insert(&1, &(Name("Jimothy".to_string()), Age(10)))
+        .await
+        .unwrap();
+    backend.update(&1, &Age(11)).await.unwrap();
+    backend.remove::<Name>(&1).await.unwrap();
+
+    // Changes made within transactions are reported once committed.
+    let mut tx = backend.transaction().await.unwrap();
+    tx.insert(&2, &Name("Andrea".to_string())).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let mut received = Vec::new();
+    for _ in 0..5 {
+        let change = changes.next().await.unwrap().unwrap();
+        println!(
+            "{} {} of entity {}",
+            change.kind.as_str(),
+            change.table,
+            change.entity
+        );
+        received.push((change.entity, change.table, change.kind));
+    }
+    // insert Name of entity 1
+    // insert Age of entity 1
+    // update Age of entity 1
+    // remove Name of entity 1
+    // insert Name of entity 2
+
+    assert_eq!(
+        received,
+        vec![
+            (1, "Name", ChangeKind::Insert),
+            (1, "Age", ChangeKind::Insert),
+            (1, "Age", ChangeKind::Update),
+            (1, "Name", ChangeKind::Remove),
+            (2, "Name", ChangeKind::Insert),
+        ]
+    );
+
+    // Rolled back changes are never reported.
+    let mut tx = backend.transaction().await.unwrap();
+    tx.insert(&3, &Name("Bob".to_string())).await.unwrap();
+    tx.rollback().await.unwrap();
+
+    backend.despawn(&2).await.unwrap();
+
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!(
+        change,
+        Change {
+            entity: 2,
+            table: "Name",
+            kind: ChangeKind::Remove
+        }
+    );
+
+    // Optional components are only reported if present.
+    backend
+        .insert(&4, &(Name("Andrea".to_string()), None::<Age>))
+        .await
+        .unwrap();
+    backend.update(&4, &Name("Andy".to_string())).await.unwrap();
+
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!((change.table, change.kind), ("Name", ChangeKind::Insert));
+
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!((change.table, change.kind), ("Name", ChangeKind::Update));
+
+    // Writes which don't affect any component aren't reported.
+    backend.update(&4, &Age(30)).await.unwrap();
+    backend.remove::<Age>(&4).await.unwrap();
+    backend
+        .patch::<Age>(&4)
+        .set(Age::FIELDS.self_0, 31)
+        .execute()
+        .await
+        .unwrap();
+    backend.remove::<Name>(&4).await.unwrap();
+
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!((change.table, change.kind), ("Name", ChangeKind::Remove));
+
+    // Upserts report inserts of new components and updates of existing ones.
+    backend
+        .upsert(&5, &(Name("Jim".to_string()), Age(40)))
+        .await
+        .unwrap();
+    backend
+        .upsert(&5, &(Name("Jimmy".to_string()), Age(41)))
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let change = changes.next().await.unwrap().unwrap();
+        received.push((change.table, change.kind));
+    }
+    assert_eq!(
+        received,
+        vec![
+            ("Name", ChangeKind::Insert),
+            ("Age", ChangeKind::Insert),
+            ("Name", ChangeKind::Update),
+            ("Age", ChangeKind::Update),
+        ]
+    );
+
+    // Subscribers which fall too far behind miss the following changes, and
+    // their streams end with an error.
+    let entities: Vec<_> = (0..2 * enorm::changes::CHANGES_BUFFER as i64)
+        .map(|entity| (100 + entity, Age(entity)))
+        .collect();
+    backend.insert_many(&entities).await.unwrap();
+
+    let mut received = 0;
+    while let Some(change) = changes.next().await {
+        match change {
+            Ok(_) => received += 1,
+            Err(err) => assert!(matches!(err, enorm::Error::Lagged)),
+        }
+    }
+    assert!(received < entities.len());
+}
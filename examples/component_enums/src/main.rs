@@ -12,6 +12,9 @@ impl Deserializeable<Sqlite> for LightSwitch {
         Box::new(Extract {
             table: "LightSwitch",
             columns: &["tag", "field_a", "field_b"],
+            exclude_deleted: false,
+            history: false,
+            as_of: None,
         })
     }
 
@@ -76,6 +79,17 @@ impl Serializable<Sqlite> for LightSwitch {
             <Self as Serializable<::sqlx::Sqlite>>::serialize(self, query)
         })
     }
+
+    fn upsert<'query, EntityId>(
+        &'query self,
+        query: &mut enorm::entity::EntityPrefixedQuery<'query, Sqlite, EntityId>,
+    ) where
+        EntityId: sqlx::Encode<'query, Sqlite> + sqlx::Type<Sqlite> + Clone + 'query,
+    {
+        query.query(<Self as Component<::sqlx::Sqlite>>::UPSERT, move |query| {
+            <Self as Serializable<::sqlx::Sqlite>>::serialize(self, query)
+        })
+    }
 }
 
 impl Component<Sqlite> for LightSwitch {
@@ -84,6 +98,8 @@ impl Component<Sqlite> for LightSwitch {
 
     const UPDATE: &'static str = "";
 
+    const UPSERT: &'static str = "insert into LightSwitch(entity, tag, field_a, field_b) values(?, ?, ?, ?) on conflict(entity) do update set tag = excluded.tag, field_a = excluded.field_a, field_b = excluded.field_b;";
+
     const DELETE: &'static str = "";
 
     fn table() -> &'static str {
@@ -94,19 +110,14 @@ impl Component<Sqlite> for LightSwitch {
         todo!()
     }
 
-    fn create_component_table<'pool, EntityId>(
-        pool: &'pool sqlx::Pool<Sqlite>,
-    ) -> impl std::future::Future<
-        Output = Result<<Sqlite as sqlx::Database>::QueryResult, sqlx::Error>,
-    > + Send
+    fn create_table_statement<EntityId>() -> String
     where
         EntityId: sqlx::Type<Sqlite>,
     {
-        async move {
-            let sql = format!("create table if not exists LightSwitch(entity {} primary key, tag text not null, field_a integer null, field_b integer null);", <EntityId as sqlx::Type::<Sqlite>>::type_info().name());
-            let query = sqlx::query(&sql);
-            query.execute(pool).await
-        }
+        format!(
+            "create table if not exists LightSwitch(entity {} primary key, tag text not null, field_a integer null, field_b integer null);",
+            <EntityId as sqlx::Type<Sqlite>>::type_info().name()
+        )
     }
 }
 
@@ -120,7 +131,10 @@ async fn main() {
     // This creates the component tables where data will be persisted.
     backend.register::<LightSwitch>().await.unwrap();
 
-    backend.insert(&1, &LightSwitch::On { field_a: 10 }).await;
+    backend
+        .insert(&1, &LightSwitch::On { field_a: 10 })
+        .await
+        .unwrap();
 
     let switch: LightSwitch = backend.get(&1).await.unwrap();
 
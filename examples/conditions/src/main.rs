@@ -23,12 +23,14 @@ async fn main() {
     let jimothy = 1;
     backend
         .insert(&jimothy, &(Name("Jimothy".to_string()), Age(10)))
-        .await;
+        .await
+        .unwrap();
 
     let andrea = 2;
     backend
         .insert(&andrea, &(Name("Andrea".to_string()), Age(32)))
-        .await;
+        .await
+        .unwrap();
 
     // Let's name an Archetype instead of just relying on a tuple.
     #[derive(Archetype, Debug)]
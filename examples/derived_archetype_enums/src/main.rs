@@ -29,7 +29,7 @@ async fn main() {
     // This creates the component tables where data will be persisted.
     backend.register::<Counter>().await.unwrap();
 
-    backend.insert(&1, &Counter(10)).await;
+    backend.insert(&1, &Counter(10)).await.unwrap();
 
     let switch1: LightSwitch = backend.get(&1).await.unwrap();
     println!("{switch1:#?}");
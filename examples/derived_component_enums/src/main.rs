@@ -1,4 +1,5 @@
 use enorm::prelude::*;
+use futures::TryStreamExt as _;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 
 #[derive(Debug, Component, PartialEq, Eq)]
@@ -27,7 +28,10 @@ async fn main() {
     // This creates the component tables where data will be persisted.
     backend.register::<LightSwitch>().await.unwrap();
 
-    backend.insert(&1, &LightSwitch::On { field_a: 10 }).await;
+    backend
+        .insert(&1, &LightSwitch::On { field_a: 10 })
+        .await
+        .unwrap();
     backend
         .insert(
             &2,
@@ -36,8 +40,9 @@ async fn main() {
                 field_c: 30,
             },
         )
-        .await;
-    backend.insert(&3, &LightSwitch::Whatever).await;
+        .await
+        .unwrap();
+    backend.insert(&3, &LightSwitch::Whatever).await.unwrap();
 
     assert_eq!(
         backend.get::<LightSwitch>(&1).await.unwrap(),
@@ -56,4 +61,39 @@ async fn main() {
         backend.get::<LightSwitch>(&3).await.unwrap(),
         LightSwitch::Whatever
     );
+
+    // The variant and the columns of each variant can be filtered on.
+    let switched_off = backend
+        .list::<LightSwitch>()
+        .filter(LightSwitch::FIELDS.variant().eq(LightSwitchVariant::Off))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(switched_off, vec![2]);
+
+    // Columns belonging to other variants are null.
+    let without_a = backend
+        .list::<LightSwitch>()
+        .filter(LightSwitch::FIELDS.field_a.is_null())
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(without_a, vec![2, 3]);
+
+    let bright = backend
+        .list::<LightSwitch>()
+        .filter(LightSwitch::FIELDS.field_b.ge(Some(20)))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(bright, vec![2]);
 }
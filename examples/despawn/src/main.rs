@@ -0,0 +1,51 @@
+use enorm::prelude::*;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Age(i64);
+
+#[derive(Component, Debug)]
+pub struct Dead;
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // The backend keeps track of registered components, so it knows where
+    // to look for the components of an entity.
+    backend.register::<Name>().await.unwrap();
+    backend
+        .register_schema(&Schema::new().archetype::<(Age, Dead)>())
+        .await
+        .unwrap();
+
+    backend
+        .insert(&1, &(Name("Jimothy".to_string()), Age(10)))
+        .await
+        .unwrap();
+    backend
+        .insert(&2, &(Name("Andrea".to_string()), Dead))
+        .await
+        .unwrap();
+
+    // Remove Jimothy entirely, without having to know what components he has.
+    let removed = backend.despawn(&1).await.unwrap();
+
+    println!("{removed:?}");
+    // ["Name", "Age"]
+    assert_eq!(removed, vec!["Name", "Age"]);
+
+    assert!(matches!(
+        backend.get::<Name>(&1).await,
+        Err(enorm::Error::NotFound)
+    ));
+
+    // Andrea is unaffected.
+    assert_eq!(backend.get::<Name>(&2).await.unwrap().0, "Andrea");
+
+    // Despawning an entity which doesn't exist removes nothing.
+    assert!(backend.despawn(&1).await.unwrap().is_empty());
+}
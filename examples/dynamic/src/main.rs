@@ -0,0 +1,219 @@
+use enorm::{prelude::*, versioned::VERSION_COLUMN};
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Player {
+    pub name: String,
+    pub score: i64,
+    pub rating: f64,
+    pub active: bool,
+    pub title: Option<String>,
+}
+
+#[derive(Component, Debug, PartialEq)]
+pub enum Status {
+    Online,
+    Away { minutes: i64 },
+}
+
+// Dynamic writes follow the same rules as typed ones.
+#[derive(Component, Debug, PartialEq)]
+#[enorm(soft_delete, versioned, history)]
+pub struct Badge(String);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Player>().await.unwrap();
+    backend.register::<Status>().await.unwrap();
+    backend.register::<Badge>().await.unwrap();
+
+    backend
+        .insert(
+            &1,
+            &Player {
+                name: "Jimothy".to_string(),
+                score: 10,
+                rating: 1.5,
+                active: true,
+                title: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    // Look up the component by the name of its table, without knowing its type.
+    let player = backend.component("Player").unwrap();
+
+    let row = backend.get_dynamic(&player, &1).await.unwrap();
+
+    println!("{row:?}");
+    // [("name", Text("Jimothy")), ("score", Integer(10)), ("rating", Real(1.5)), ("active", Bool(true)), ("title", Null)]
+    assert_eq!(
+        row,
+        vec![
+            ("name", Value::Text("Jimothy".to_string())),
+            ("score", Value::Integer(10)),
+            ("rating", Value::Real(1.5)),
+            ("active", Value::Bool(true)),
+            ("title", Value::Null),
+        ]
+    );
+
+    // Dynamically inserted components can be read using their types as well.
+    backend
+        .insert_dynamic(
+            &player,
+            &2,
+            &[
+                ("name", "Andrea".into()),
+                ("score", 20.into()),
+                ("rating", 2.0.into()),
+                ("active", false.into()),
+                ("title", "Champion".into()),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let andrea = backend.get::<Player>(&2).await.unwrap();
+    assert_eq!(andrea.title.as_deref(), Some("Champion"));
+
+    backend
+        .update_dynamic(
+            &player,
+            &1,
+            &[("score", 15.into()), ("title", "Rookie".into())],
+        )
+        .await
+        .unwrap();
+
+    let jimothy = backend.get::<Player>(&1).await.unwrap();
+    assert_eq!(jimothy.score, 15);
+    assert_eq!(jimothy.title.as_deref(), Some("Rookie"));
+
+    let players = backend.list_dynamic(&player).await.unwrap();
+    assert_eq!(players.len(), 2);
+
+    let (_, andrea) = players.iter().find(|(entity, _)| *entity == 2).unwrap();
+    assert_eq!(andrea[1], ("score", Value::Integer(20)));
+
+    // Column names are checked against the definition of the component.
+    assert!(matches!(
+        backend
+            .update_dynamic(&player, &1, &[("nickname", "Jim".into())])
+            .await,
+        Err(enorm::Error::UnknownColumn(_))
+    ));
+
+    // Components which haven't been registered are unknown.
+    assert!(backend.component("Age").is_none());
+
+    // The variant of enum components is held by their tag column.
+    let status = backend.component("Status").unwrap();
+
+    backend
+        .insert_dynamic(
+            &status,
+            &1,
+            &[("__enorm_tag", "Away".into()), ("minutes", 5.into())],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        backend.get::<Status>(&1).await.unwrap(),
+        Status::Away { minutes: 5 }
+    );
+    assert_eq!(
+        backend.get_dynamic(&status, &1).await.unwrap(),
+        vec![
+            ("__enorm_tag", Value::Text("Away".to_string())),
+            ("minutes", Value::Integer(5)),
+        ]
+    );
+
+    // Versioned components are read along with their version, which updates
+    // must include. Updates increment it and are recorded in the history.
+    let badge = backend.component("Badge").unwrap();
+
+    backend
+        .insert_dynamic(&badge, &1, &[("column0", "Rookie".into())])
+        .await
+        .unwrap();
+
+    let row = backend.get_dynamic(&badge, &1).await.unwrap();
+    assert_eq!(
+        row,
+        vec![
+            ("column0", Value::Text("Rookie".to_string())),
+            (VERSION_COLUMN, Value::Integer(1)),
+        ]
+    );
+
+    backend
+        .update_dynamic(
+            &badge,
+            &1,
+            &[("column0", "Veteran".into()), (VERSION_COLUMN, 1.into())],
+        )
+        .await
+        .unwrap();
+
+    let versioned = backend.get::<Versioned<Badge>>(&1).await.unwrap();
+    assert_eq!(versioned.version, 2);
+
+    // Updates using a version which has changed since are rejected.
+    assert!(matches!(
+        backend
+            .update_dynamic(
+                &badge,
+                &1,
+                &[("column0", "Stale".into()), (VERSION_COLUMN, 1.into())],
+            )
+            .await,
+        Err(enorm::Error::Conflict)
+    ));
+    assert!(matches!(
+        backend
+            .update_dynamic(&badge, &1, &[("column0", "Unversioned".into())])
+            .await,
+        Err(enorm::Error::InvalidQuery(_))
+    ));
+
+    let now = std::time::SystemTime::now();
+    std::thread::sleep(std::time::Duration::from_millis(2));
+    assert_eq!(
+        backend.get_at::<Badge>(&1, now).await.unwrap(),
+        Badge("Veteran".to_string())
+    );
+
+    // Removed soft-deleted components are neither read nor updated.
+    backend.remove::<Badge>(&1).await.unwrap();
+
+    assert!(matches!(
+        backend.get_dynamic(&badge, &1).await,
+        Err(enorm::Error::NotFound)
+    ));
+    assert!(backend.list_dynamic(&badge).await.unwrap().is_empty());
+
+    backend
+        .update_dynamic(
+            &badge,
+            &1,
+            &[("column0", "Ghost".into()), (VERSION_COLUMN, 2.into())],
+        )
+        .await
+        .unwrap();
+    let badges = backend
+        .list::<Badge>()
+        .include_deleted()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(badges, vec![Badge("Veteran".to_string())]);
+}
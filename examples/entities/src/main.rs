@@ -0,0 +1,88 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Age(i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+
+    let jimothy = 1;
+    let andrea = 2;
+    let bartholomew = 3;
+    backend
+        .insert_many(&[
+            (jimothy, (Name("Jimothy".to_string()), Age(10))),
+            (andrea, (Name("Andrea".to_string()), Age(32))),
+            (bartholomew, (Name("Bartholomew".to_string()), Age(67))),
+        ])
+        .await
+        .unwrap();
+
+    // Load the names of a known set of entities in a single query,
+    // instead of calling `get` for each of them.
+    let names = backend
+        .list::<Name>()
+        .entities(&[jimothy, bartholomew, 4])
+        .map(|entity| (*entity.id(), entity.into_components().0))
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{names:#?}");
+    // [
+    //     (
+    //         1,
+    //         "Jimothy",
+    //     ),
+    //     (
+    //         3,
+    //         "Bartholomew",
+    //     ),
+    // ]
+    assert_eq!(
+        names,
+        vec![
+            (jimothy, "Jimothy".to_string()),
+            (bartholomew, "Bartholomew".to_string())
+        ]
+    );
+
+    // The Entity ID can also be used within conditions, like any other column.
+    let older = backend
+        .list::<(Name, Age)>()
+        .filter(condition::And::new(
+            entity().ne(andrea),
+            Age::FIELDS.self_0.gt(18),
+        ))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(older, vec![bartholomew]);
+
+    // ... and for ordering.
+    let reversed = backend
+        .list::<Name>()
+        .filter(entity().is_in([jimothy, andrea]))
+        .order_by(entity::<i64>(), Order::Desc)
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(reversed, vec![andrea, jimothy]);
+}
@@ -0,0 +1,173 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Parent(#[enorm(references = Name, on_delete = cascade)] i64);
+
+#[derive(Component, Debug)]
+pub struct Hidden;
+
+// References can be removed, and followed as they were before.
+#[derive(Component, Debug)]
+#[enorm(soft_delete, history)]
+pub struct Owner(i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend
+        .register_schema(&Schema::new().archetype::<(Parent, Hidden, Owner)>())
+        .await
+        .unwrap();
+
+    // A small scene graph:
+    //
+    // world
+    // ├── house
+    // │   ├── door
+    // │   └── window (hidden)
+    // └── tree
+    //     └── bird
+    let world = 1;
+    backend
+        .insert(&world, &Name("world".to_string()))
+        .await
+        .unwrap();
+
+    for (entity, name, parent) in [
+        (2, "house", world),
+        (3, "door", 2),
+        (4, "window", 2),
+        (5, "tree", world),
+        (6, "bird", 5),
+    ] {
+        backend
+            .insert(&entity, &(Name(name.to_string()), Parent(parent)))
+            .await
+            .unwrap();
+    }
+
+    backend.insert(&4, &Hidden).await.unwrap();
+
+    // Everything within the world, along with how deep it is.
+    let descendants = backend
+        .list::<Name>()
+        .descendants::<Parent>(Parent::FIELDS.self_0, world)
+        .order_by(depth(), Order::Asc)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    for descendant in &descendants {
+        println!("{}: {}", descendant.depth, descendant.components.0);
+    }
+    // 1: house
+    // 1: tree
+    // 2: door
+    // 2: window
+    // 2: bird
+    assert_eq!(descendants.len(), 5);
+
+    // Traversals can be filtered like any other list.
+    let visible_children = backend
+        .list::<Name>()
+        .descendants::<Parent>(Parent::FIELDS.self_0, 2)
+        .without::<Hidden>()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(visible_children.len(), 1);
+    assert_eq!(visible_children[0].components.0, "door");
+
+    // Limit how far references are followed.
+    let children = backend
+        .list::<Name>()
+        .descendants::<Parent>(Parent::FIELDS.self_0, world)
+        .max_depth(1)
+        .count()
+        .await
+        .unwrap();
+
+    assert_eq!(children, 2);
+
+    // Walk up from the bird to the root of the graph.
+    let ancestors = backend
+        .list::<Name>()
+        .ancestors::<Parent>(Parent::FIELDS.self_0, 6)
+        .order_by(depth(), Order::Asc)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let path: Vec<_> = ancestors
+        .iter()
+        .map(|ancestor| ancestor.components.0.as_str())
+        .collect();
+
+    println!("{path:?}");
+    // ["tree", "world"]
+    assert_eq!(path, vec!["tree", "world"]);
+
+    // The house owns the tree, which owns the bird.
+    backend.insert(&5, &Owner(2)).await.unwrap();
+    backend.insert(&6, &Owner(5)).await.unwrap();
+
+    let owned = std::time::SystemTime::now();
+    std::thread::sleep(std::time::Duration::from_millis(2));
+
+    // Removed references are no longer followed, unless asked to.
+    backend.remove::<Owner>(&5).await.unwrap();
+
+    let owned_by_house = || {
+        backend
+            .list::<Name>()
+            .descendants::<Owner>(Owner::FIELDS.self_0, 2)
+    };
+
+    assert_eq!(owned_by_house().count().await.unwrap(), 0);
+    assert_eq!(owned_by_house().include_deleted().count().await.unwrap(), 2);
+    assert_eq!(owned_by_house().as_of(owned).count().await.unwrap(), 2);
+
+    // The door and window own each other. Cycles end on their own, and every
+    // entity is found once, at its shortest distance.
+    backend.insert(&3, &Owner(4)).await.unwrap();
+    backend.insert(&4, &Owner(3)).await.unwrap();
+
+    let owned_by_door = backend
+        .list::<Name>()
+        .descendants::<Owner>(Owner::FIELDS.self_0, 3)
+        .order_by(depth(), Order::Asc)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let owned_by_door: Vec<_> = owned_by_door
+        .iter()
+        .map(|owned| (owned.depth, owned.components.0.as_str()))
+        .collect();
+    assert_eq!(owned_by_door, vec![(1, "window"), (2, "door")]);
+
+    // The column must belong to the component holding the references.
+    assert!(matches!(
+        backend
+            .list::<Name>()
+            .descendants::<Owner>(Parent::FIELDS.self_0, 3)
+            .count()
+            .await,
+        Err(enorm::Error::InvalidQuery(_))
+    ));
+}
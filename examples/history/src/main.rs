@@ -0,0 +1,192 @@
+use std::time::{Duration, SystemTime};
+
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+// Every change of a component with a history is also appended to its
+// `Score_history` table, from which past states can be read.
+#[derive(Component, Debug, PartialEq)]
+#[enorm(history)]
+pub struct Score {
+    points: i64,
+}
+
+#[derive(Component, Debug, PartialEq)]
+#[enorm(history)]
+pub enum Rank {
+    Bronze,
+    Silver { stars: i32 },
+}
+
+// Score, as redefined later on.
+mod v2 {
+    use enorm::prelude::*;
+
+    #[derive(Component, Debug, PartialEq)]
+    #[enorm(table = "Score", history)]
+    pub struct Score {
+        pub points: i64,
+        pub bonus: Option<i64>,
+    }
+}
+
+/// The current time, making sure changes made before and after it are
+/// recorded at different timestamps.
+fn tick() -> SystemTime {
+    std::thread::sleep(Duration::from_millis(2));
+    let now = SystemTime::now();
+    std::thread::sleep(Duration::from_millis(2));
+    now
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Score>().await.unwrap();
+    backend.register::<Rank>().await.unwrap();
+
+    // The history table isn't mistaken for part of the component.
+    assert!(backend.migration::<Score>().await.unwrap().is_empty());
+
+    let before = tick();
+
+    backend
+        .insert(
+            &1,
+            &(
+                Name("Jimothy".to_string()),
+                Score { points: 10 },
+                Rank::Bronze,
+            ),
+        )
+        .await
+        .unwrap();
+    backend
+        .insert(&2, &(Name("Andrea".to_string()), Score { points: 20 }))
+        .await
+        .unwrap();
+
+    let inserted = tick();
+
+    backend.update(&1, &Score { points: 15 }).await.unwrap();
+    backend
+        .update(&1, &Rank::Silver { stars: 2 })
+        .await
+        .unwrap();
+    backend.remove::<Score>(&2).await.unwrap();
+
+    let updated = tick();
+
+    // Upserts are recorded as inserts or updates of the component.
+    backend.upsert(&4, &Score { points: 40 }).await.unwrap();
+    let upserted = tick();
+    backend.upsert(&4, &Score { points: 45 }).await.unwrap();
+
+    assert_eq!(
+        backend.get_at::<Score>(&4, upserted).await.unwrap(),
+        Score { points: 40 }
+    );
+    assert_eq!(
+        backend.get_at::<Score>(&4, tick()).await.unwrap(),
+        Score { points: 45 }
+    );
+    backend.despawn(&4).await.unwrap();
+
+    backend.despawn(&1).await.unwrap();
+
+    // Components are read as they were at the time.
+    assert_eq!(
+        backend.get_at::<(Score, Rank)>(&1, inserted).await.unwrap(),
+        (Score { points: 10 }, Rank::Bronze)
+    );
+    assert_eq!(
+        backend.get_at::<(Score, Rank)>(&1, updated).await.unwrap(),
+        (Score { points: 15 }, Rank::Silver { stars: 2 })
+    );
+
+    // Components which didn't exist at the time are missing.
+    assert!(matches!(
+        backend.get_at::<Score>(&1, before).await,
+        Err(enorm::Error::NotFound)
+    ));
+    assert!(matches!(
+        backend.get_at::<Score>(&2, updated).await,
+        Err(enorm::Error::NotFound)
+    ));
+
+    // Lists can be filtered and ordered as usual.
+    let scores = backend
+        .list_at::<Score>(inserted)
+        .order_by(Score::FIELDS.points, Order::Desc)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{scores:?}");
+    // [Score { points: 20 }, Score { points: 10 }]
+    assert_eq!(scores, vec![Score { points: 20 }, Score { points: 10 }]);
+
+    assert_eq!(
+        backend
+            .list_at::<Score>(updated)
+            .with::<Rank>()
+            .count()
+            .await
+            .unwrap(),
+        1
+    );
+
+    // Everything has since been removed.
+    assert_eq!(backend.list::<Score>().count().await.unwrap(), 0);
+    assert_eq!(backend.list_at::<Score>(tick()).count().await.unwrap(), 0);
+
+    // Migrations add new columns to the history table as well, where they
+    // are null for the changes recorded before.
+    let migration = backend.migration::<v2::Score>().await.unwrap();
+
+    println!("{migration}");
+    // alter table Score add column bonus INTEGER null;
+    // alter table Score_history add column bonus INTEGER null;
+    backend.migrate(&migration).await.unwrap();
+
+    backend
+        .insert(
+            &3,
+            &v2::Score {
+                points: 30,
+                bonus: Some(5),
+            },
+        )
+        .await
+        .unwrap();
+
+    let migrated = tick();
+
+    assert_eq!(
+        backend
+            .list_at::<v2::Score>(migrated)
+            .components()
+            .fetch()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap(),
+        vec![v2::Score {
+            points: 30,
+            bonus: Some(5)
+        }]
+    );
+    assert_eq!(
+        backend.get_at::<v2::Score>(&1, updated).await.unwrap(),
+        v2::Score {
+            points: 15,
+            bonus: None
+        }
+    );
+}
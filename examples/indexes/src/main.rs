@@ -0,0 +1,64 @@
+use enorm::prelude::*;
+use sqlx::{Pool, Sqlite};
+
+#[derive(Component, Debug)]
+pub struct Email(#[enorm(unique)] String);
+
+#[derive(Component, Debug)]
+#[enorm(index(city, street))]
+pub struct Address {
+    #[enorm(index)]
+    pub postcode: String,
+    pub city: String,
+    pub street: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let pool = Pool::<Sqlite>::connect("sqlite::memory:").await.unwrap();
+
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::new(pool.clone());
+
+    // Indexes are created along with the component tables.
+    backend.register::<Email>().await.unwrap();
+    backend.register::<Address>().await.unwrap();
+
+    // Indexed columns are described by the component's metadata.
+    let columns = <Address as Component<Sqlite>>::columns();
+    assert!(columns[0].indexed() && !columns[0].unique());
+    assert!(!columns[1].indexed());
+    assert!(<Email as Component<Sqlite>>::columns()[0].unique());
+
+    let indexes: Vec<String> = sqlx::query_scalar(
+        "select name from sqlite_master where type = 'index' and sql is not null order by name",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    println!("{indexes:?}");
+    // ["Address_city_street_idx", "Address_postcode_idx", "Email_column0_key"]
+    assert_eq!(
+        indexes,
+        vec![
+            "Address_city_street_idx",
+            "Address_postcode_idx",
+            "Email_column0_key"
+        ]
+    );
+
+    backend
+        .insert(&1, &Email("alice@example.com".to_string()))
+        .await
+        .unwrap();
+
+    // Unique columns reject values already used by other entities.
+    let error = backend
+        .insert(&2, &Email("alice@example.com".to_string()))
+        .await
+        .unwrap_err();
+
+    println!("{error}");
+    assert!(matches!(error, enorm::Error::ConstraintViolation(_)));
+}
@@ -0,0 +1,46 @@
+use enorm::prelude::*;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Age(i64);
+
+#[derive(Component, Debug)]
+pub struct Dead;
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+    backend.register::<Dead>().await.unwrap();
+
+    backend
+        .insert(&1, &(Name("Jimothy".to_string()), Age(10)))
+        .await
+        .unwrap();
+    backend
+        .insert(&2, &(Name("Andrea".to_string()), Dead))
+        .await
+        .unwrap();
+
+    // List the tables of all registered components an entity has.
+    let components = backend.components_of(&1).await.unwrap();
+
+    println!("{components:?}");
+    // ["Name", "Age"]
+    assert_eq!(components, vec!["Name", "Age"]);
+
+    assert!(backend.components_of(&3).await.unwrap().is_empty());
+
+    // Check for components without fetching them.
+    assert!(backend.has::<Dead>(&2).await.unwrap());
+    assert!(!backend.has::<Dead>(&1).await.unwrap());
+
+    // Archetypes require all of their components to be present.
+    assert!(backend.has::<(Name, Age)>(&1).await.unwrap());
+    assert!(!backend.has::<(Name, Age)>(&2).await.unwrap());
+}
@@ -0,0 +1,207 @@
+use enorm::{migration::ColumnChange, prelude::*};
+
+// The component as it was originally defined.
+mod v1 {
+    use enorm::prelude::*;
+
+    #[derive(Component, Debug)]
+    #[enorm(table = "Person")]
+    pub struct Person {
+        pub name: String,
+        pub nickname: String,
+        #[enorm(index)]
+        pub height: i64,
+    }
+}
+
+// The component as it is defined now.
+mod v2 {
+    use enorm::prelude::*;
+
+    #[derive(Component, Debug, PartialEq)]
+    #[enorm(table = "Person")]
+    pub struct Person {
+        pub full_name: String,
+        #[enorm(index)]
+        pub height: f64,
+        #[enorm(unique)]
+        pub email: Option<String>,
+    }
+}
+
+// A later definition adding a column which isn't nullable.
+mod v3 {
+    use enorm::prelude::*;
+
+    #[derive(Component, Debug)]
+    #[enorm(table = "Person")]
+    pub struct Person {
+        pub full_name: String,
+        pub height: f64,
+        pub email: Option<String>,
+        pub age: i64,
+    }
+}
+
+// A component referencing another, and the same component without the reference.
+mod team {
+    use enorm::prelude::*;
+
+    #[derive(Component, Debug)]
+    #[enorm(table = "Team")]
+    pub struct Team(pub String);
+
+    #[derive(Component, Debug)]
+    #[enorm(table = "Lead")]
+    pub struct Lead {
+        #[enorm(references = Team)]
+        pub team: i64,
+        pub since: i64,
+    }
+
+    pub mod v2 {
+        use enorm::prelude::*;
+
+        #[derive(Component, Debug)]
+        #[enorm(table = "Lead")]
+        pub struct Lead {
+            pub since: i64,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // Migrating a component whose table doesn't exist yet creates it.
+    let migration = backend.migration::<v1::Person>().await.unwrap();
+    assert!(migration.creates_table());
+    assert_eq!(backend.migrate(&migration).await.unwrap(), 1);
+
+    backend
+        .insert(
+            &1,
+            &v1::Person {
+                name: "Jimothy".to_string(),
+                nickname: "Jim".to_string(),
+                height: 180,
+            },
+        )
+        .await
+        .unwrap();
+
+    // Compare the new definition against the table. Renames can't be told
+    // apart from dropping one column and adding another, so they must be
+    // pointed out explicitly. Columns which the component no longer has are
+    // kept, but made nullable.
+    let migration = backend
+        .migration::<v2::Person>()
+        .await
+        .unwrap()
+        .rename("name", "full_name");
+
+    println!("{migration}");
+    // drop index Person_height_idx;
+    // alter table Person rename column name to full_name;
+    // alter table Person add column __enorm_migrate_height REAL not null default 0;
+    // update Person set __enorm_migrate_height = cast(height as REAL);
+    // alter table Person drop column height;
+    // alter table Person rename column __enorm_migrate_height to height;
+    // alter table Person add column email TEXT null;
+    // alter table Person add column __enorm_migrate_nickname TEXT null;
+    // update Person set __enorm_migrate_nickname = cast(nickname as TEXT);
+    // alter table Person drop column nickname;
+    // alter table Person rename column __enorm_migrate_nickname to nickname;
+    // create index Person_height_idx on Person(height);
+    // create unique index Person_email_key on Person(email);
+    // -- Person.nickname is no longer part of the component
+    assert_eq!(
+        migration.changes(),
+        &[
+            ColumnChange::Rename {
+                from: "name".to_string(),
+                to: "full_name".to_string()
+            },
+            ColumnChange::Retype {
+                column: "height".to_string(),
+                from: "INTEGER".to_string(),
+                to: "REAL".to_string(),
+                nullable: false,
+            },
+            ColumnChange::Add {
+                column: "email".to_string(),
+                column_type: "TEXT".to_string(),
+                nullable: true,
+            },
+            ColumnChange::Drop {
+                column: "nickname".to_string(),
+                column_type: "TEXT".to_string(),
+                nullable: false,
+            },
+        ]
+    );
+    assert_eq!(migration.leftover_columns(), vec!["nickname"]);
+
+    assert_eq!(backend.migrate(&migration).await.unwrap(), 2);
+    assert_eq!(
+        backend.schema_version::<v2::Person>().await.unwrap(),
+        Some(2)
+    );
+
+    assert_eq!(
+        backend.get::<v2::Person>(&1).await.unwrap(),
+        v2::Person {
+            full_name: "Jimothy".to_string(),
+            height: 180.0,
+            email: None,
+        }
+    );
+
+    // Once applied, there's nothing left to do.
+    assert!(backend.migration::<v2::Person>().await.unwrap().is_empty());
+
+    // Leftover columns can be dropped, along with their values, once they're
+    // no longer needed.
+    let migration = backend
+        .migration::<v2::Person>()
+        .await
+        .unwrap()
+        .drop_removed();
+    assert!(!migration.is_empty());
+    assert_eq!(backend.migrate(&migration).await.unwrap(), 3);
+    assert!(backend.migration::<v2::Person>().await.unwrap().is_empty());
+
+    // Existing rows would have no values for columns which aren't nullable,
+    // so they can only be added to tables without any rows.
+    let migration = backend.migration::<v3::Person>().await.unwrap();
+    assert!(matches!(
+        backend.migrate(&migration).await,
+        Err(enorm::Error::InvalidQuery(_))
+    ));
+    assert_eq!(
+        backend.schema_version::<v2::Person>().await.unwrap(),
+        Some(3)
+    );
+
+    // Sqlite can't drop columns used by foreign key constraints, nor replace
+    // them with nullable ones.
+    for migration in [
+        backend.migration::<team::Team>().await.unwrap(),
+        backend.migration::<team::Lead>().await.unwrap(),
+    ] {
+        backend.migrate(&migration).await.unwrap();
+    }
+
+    let migration = backend.migration::<team::v2::Lead>().await.unwrap();
+    assert_eq!(migration.leftover_columns(), vec!["team"]);
+    assert!(matches!(
+        backend.migrate(&migration).await,
+        Err(enorm::Error::InvalidQuery(_))
+    ));
+    assert!(matches!(
+        backend.migrate(&migration.drop_removed()).await,
+        Err(enorm::Error::InvalidQuery(_))
+    ));
+}
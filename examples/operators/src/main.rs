@@ -0,0 +1,100 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Age(i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+
+    let jimothy = 1;
+    let andrea = 2;
+    let bartholomew = 3;
+    backend
+        .insert_many(&[
+            (jimothy, (Name("Jimothy".to_string()), Age(10))),
+            (andrea, (Name("Andrea".to_string()), Age(32))),
+            (bartholomew, (Name("Bartholomew".to_string()), Age(67))),
+        ])
+        .await
+        .unwrap();
+
+    // Everyone of working age.
+    let working = backend
+        .list::<(Name, Age)>()
+        .filter(Age::FIELDS.self_0.between(18, 65))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{working:#?}");
+    // [
+    //     2,
+    // ]
+    assert_eq!(working, vec![andrea]);
+
+    // Anyone aged exactly 10 or 67.
+    let ages = backend
+        .list::<(Name, Age)>()
+        .filter(Age::FIELDS.self_0.is_in([10, 67]))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(ages, vec![jimothy, bartholomew]);
+
+    // An empty list matches nothing.
+    let nobody = backend
+        .list::<(Name, Age)>()
+        .filter(Age::FIELDS.self_0.is_in([]))
+        .count()
+        .await
+        .unwrap();
+
+    assert_eq!(nobody, 0);
+
+    // Names containing "an", regardless of case, except for those of
+    // 10 year olds.
+    let names = backend
+        .list::<(Name, Age)>()
+        .filter(condition::And::new(
+            Name::FIELDS.self_0.ilike("%AN%"),
+            Age::FIELDS.self_0.not_in([10]),
+        ))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{names:#?}");
+    // [
+    //     2,
+    // ]
+    assert_eq!(names, vec![andrea]);
+
+    // Names that don't start with an uppercase "B".
+    let names = backend
+        .list::<(Name, Age)>()
+        .filter(condition::Not::new(Name::FIELDS.self_0.like("B%")))
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(names, vec![jimothy, andrea]);
+}
@@ -23,11 +23,15 @@ async fn main() {
     let jimothy = 1;
     backend
         .insert(&jimothy, &(Name("Jimothy".to_string()), Age(10)))
-        .await;
+        .await
+        .unwrap();
 
     // It's rude to ask a woman her age!
     let andrea = 2;
-    backend.insert(&andrea, &(Name("Andrea".to_string()))).await;
+    backend
+        .insert(&andrea, &(Name("Andrea".to_string())))
+        .await
+        .unwrap();
 
     // Let's name an Archetype instead of just relying on a tuple.
     #[derive(Archetype, Debug)]
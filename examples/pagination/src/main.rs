@@ -0,0 +1,95 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Age(i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+
+    let people = [("Jimothy", 10), ("Andrea", 32), ("Frank", 54), ("Bob", 32)];
+
+    for (entity, (name, age)) in (1..).zip(people) {
+        backend
+            .insert(&entity, &(Name(name.to_string()), Age(age)))
+            .await
+            .unwrap();
+    }
+
+    // Oldest first. Andrea and Bob are the same age, so they're
+    // returned in the order of their entity IDs.
+    let oldest = backend
+        .list::<(Name, Age)>()
+        .order_by(Age::FIELDS.self_0, Order::Desc)
+        .limit(3)
+        .map(|entity| entity.into_components().0)
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        oldest,
+        vec![
+            Name("Frank".to_string()),
+            Name("Andrea".to_string()),
+            Name("Bob".to_string())
+        ]
+    );
+
+    // Skip the first two names alphabetically.
+    let names = backend
+        .list::<Name>()
+        .order_by(Name::FIELDS.self_0, Order::Asc)
+        .offset(2)
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(names, vec![3, 1]);
+
+    // Page through all entities, two at a time.
+    let mut pages = Vec::new();
+    let mut last = None;
+
+    loop {
+        let mut page = backend.list::<Age>().limit(2);
+
+        if let Some(last) = last {
+            page = page.after(last);
+        }
+
+        let ids = page.ids().fetch().try_collect::<Vec<_>>().await.unwrap();
+
+        let Some(id) = ids.last() else {
+            break;
+        };
+
+        last = Some(*id);
+        pages.push(ids);
+    }
+
+    assert_eq!(pages, vec![vec![1, 2], vec![3, 4]]);
+
+    // Paging after an entity only follows the order of Entity IDs.
+    assert!(matches!(
+        backend
+            .list::<Age>()
+            .order_by(Age::FIELDS.self_0, Order::Desc)
+            .after(2)
+            .count()
+            .await,
+        Err(enorm::Error::InvalidQuery(_))
+    ));
+}
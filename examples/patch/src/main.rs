@@ -0,0 +1,111 @@
+use enorm::prelude::*;
+use futures::StreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, Clone, PartialEq)]
+#[enorm(versioned)]
+pub struct Player {
+    name: String,
+    score: i64,
+    level: i32,
+    title: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Player>().await.unwrap();
+
+    backend
+        .insert(
+            &1,
+            &Player {
+                name: "Jimothy".to_string(),
+                score: 100,
+                level: 1,
+                title: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let changes = backend.changes().await.unwrap();
+    let mut changes = std::pin::pin!(changes);
+
+    let read: Versioned<Player> = backend.get(&1).await.unwrap();
+
+    // Only the given columns are written, using a single statement.
+    backend
+        .patch::<Player>(&1)
+        .set(Player::FIELDS.level, 3)
+        .set(Player::FIELDS.title, Some("Champion".to_string()))
+        .increment(Player::FIELDS.score, 25)
+        .execute()
+        .await
+        .unwrap();
+
+    let player = backend.get::<Player>(&1).await.unwrap();
+
+    println!("{player:?}");
+    // Player { name: "Jimothy", score: 125, level: 3, title: Some("Champion") }
+    assert_eq!(
+        player,
+        Player {
+            name: "Jimothy".to_string(),
+            score: 125,
+            level: 3,
+            title: Some("Champion".to_string()),
+        }
+    );
+
+    // Increments are applied by the database, so none are lost.
+    futures::future::try_join_all((0..10).map(|_| {
+        backend
+            .patch::<Player>(&1)
+            .increment(Player::FIELDS.score, -1)
+            .execute()
+    }))
+    .await
+    .unwrap();
+
+    assert_eq!(backend.get::<Player>(&1).await.unwrap().score, 115);
+
+    // Patches are reported as updates.
+    let change = changes.next().await.unwrap().unwrap();
+    assert_eq!(
+        (change.entity, change.table, change.kind),
+        (1, "Player", ChangeKind::Update)
+    );
+
+    // Patching a versioned component increments its version, so concurrent
+    // updates of the whole component are still detected.
+    let mut stale = read;
+    stale.level = 2;
+    assert!(matches!(
+        backend.update(&1, &stale).await,
+        Err(enorm::Error::Conflict)
+    ));
+
+    // Columns must belong to the patched component.
+    assert!(matches!(
+        backend
+            .patch::<Name>(&1)
+            .set(Player::FIELDS.name, "Andrea".to_string())
+            .execute()
+            .await,
+        Err(enorm::Error::UnknownColumn(_))
+    ));
+
+    // Patching a component the entity doesn't have does nothing.
+    backend
+        .patch::<Player>(&2)
+        .set(Player::FIELDS.level, 10)
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(backend.list::<Player>().count().await.unwrap(), 1);
+}
@@ -0,0 +1,88 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+/// The team an entity belongs to, which must be an entity with a `Name`.
+///
+/// When the team's `Name` is removed, so are the memberships of its members.
+#[derive(Component, Debug, PartialEq)]
+pub struct Member(#[enorm(references = Name, on_delete = cascade)] i64);
+
+/// Removing the `Name` of a manager is prevented while they're still managing someone.
+#[derive(Component, Debug, PartialEq)]
+pub struct Manager(#[enorm(references = Name)] i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // Referenced components are created ahead of those referencing them.
+    let schema = Schema::new().archetype::<(Member, Manager)>();
+    assert_eq!(
+        schema.tables().collect::<Vec<_>>(),
+        vec!["Name", "Member", "Manager"]
+    );
+    backend.register_schema(&schema).await.unwrap();
+
+    // References are described by the component's metadata.
+    let reference = <Member as Component<sqlx::Sqlite>>::columns()[0]
+        .references()
+        .copied()
+        .unwrap();
+    assert_eq!(reference.table, "Name");
+
+    let team = 1;
+    let andrea = 2;
+    let jimothy = 3;
+
+    backend
+        .insert(&team, &Name("Red Team".to_string()))
+        .await
+        .unwrap();
+    backend
+        .insert(&andrea, &(Name("Andrea".to_string()), Member(team)))
+        .await
+        .unwrap();
+    backend
+        .insert(
+            &jimothy,
+            &(Name("Jimothy".to_string()), Member(team), Manager(andrea)),
+        )
+        .await
+        .unwrap();
+
+    // Entities can only reference entities which have the referenced component.
+    let error = backend.insert(&4, &Member(99)).await.unwrap_err();
+    assert!(matches!(error, enorm::Error::ConstraintViolation(_)));
+
+    // Follow the reference, fetching the name of each member's team.
+    let members = backend
+        .list::<(Name, Member)>()
+        .join_ref::<Name>(Member::FIELDS.self_0)
+        .order_by(entity::<i64>(), Order::Asc)
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    for member in &members {
+        let (Name(name), _) = &member.components;
+        let Name(team) = member.referenced.as_ref().unwrap();
+        println!("{name} is on {team}");
+    }
+    // Andrea is on Red Team
+    // Jimothy is on Red Team
+    assert_eq!(members.len(), 2);
+
+    // Andrea manages Jimothy, so can't lose their name.
+    let error = backend.remove::<Name>(&andrea).await.unwrap_err();
+    assert!(matches!(error, enorm::Error::ConstraintViolation(_)));
+
+    // Disbanding the team removes its memberships as well.
+    backend.remove::<Name>(&team).await.unwrap();
+    assert_eq!(backend.list::<Member>().count().await.unwrap(), 0);
+}
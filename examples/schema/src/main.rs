@@ -0,0 +1,55 @@
+use enorm::prelude::*;
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[derive(Component, Debug)]
+pub struct Age(i64);
+
+#[derive(Component, Debug)]
+pub struct Position {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Component, Debug)]
+pub struct Dead;
+
+#[derive(Archetype, Debug)]
+#[allow(unused)]
+struct Person {
+    name: Name,
+    age: Age,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // Collect the components used by the application, either one at a time,
+    // or all the components of an archetype or tuple at once.
+    let schema = Schema::new()
+        .archetype::<Person>()
+        .archetype::<(Name, Position)>()
+        .component::<Dead>();
+
+    // Components shared between archetypes are only included once.
+    println!("{:?}", schema.tables().collect::<Vec<_>>());
+    // ["Name", "Age", "Position", "Dead"]
+    assert_eq!(
+        schema.tables().collect::<Vec<_>>(),
+        vec!["Name", "Age", "Position", "Dead"]
+    );
+
+    assert_eq!(
+        backend.missing_tables(&schema).await.unwrap(),
+        vec!["Name", "Age", "Position", "Dead"]
+    );
+
+    // Create all the tables at once.
+    backend.register_schema(&schema).await.unwrap();
+
+    // Verify that every table exists, for example at startup.
+    assert!(backend.missing_tables(&schema).await.unwrap().is_empty());
+}
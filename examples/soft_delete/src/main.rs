@@ -0,0 +1,103 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq)]
+pub struct Name(String);
+
+// Removing a soft-deleted component only tombstones it, by setting its
+// hidden `deleted_at` column.
+#[derive(Component, Debug, PartialEq)]
+#[enorm(soft_delete)]
+pub struct Account {
+    balance: i64,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Account>().await.unwrap();
+
+    // The hidden `deleted_at` column isn't mistaken for a removed field.
+    assert!(backend.migration::<Account>().await.unwrap().is_empty());
+
+    backend
+        .insert(&1, &(Name("Jimothy".to_string()), Account { balance: 10 }))
+        .await
+        .unwrap();
+    backend
+        .insert(&2, &(Name("Andrea".to_string()), Account { balance: 20 }))
+        .await
+        .unwrap();
+
+    backend.remove::<Account>(&1).await.unwrap();
+
+    // Tombstoned components are treated as if they had been removed.
+    assert!(matches!(
+        backend.get::<Account>(&1).await,
+        Err(enorm::Error::NotFound)
+    ));
+    assert_eq!(backend.list::<Account>().count().await.unwrap(), 1);
+    assert_eq!(backend.components_of(&1).await.unwrap(), vec!["Name"]);
+
+    // Updating a tombstoned component leaves it unchanged.
+    backend
+        .update(&1, &Account { balance: 1000 })
+        .await
+        .unwrap();
+
+    // But they can still be listed explicitly, for instance when auditing.
+    let accounts = backend
+        .list::<(Name, Account)>()
+        .include_deleted()
+        .components()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    println!("{accounts:?}");
+    // [(Name("Jimothy"), Account { balance: 10 }), (Name("Andrea"), Account { balance: 20 })]
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].1, Account { balance: 10 });
+
+    // Despawning entities tombstones their soft-deleted components as well.
+    assert_eq!(backend.despawn(&2).await.unwrap(), vec!["Name", "Account"]);
+    assert_eq!(backend.list::<Account>().count().await.unwrap(), 0);
+    assert_eq!(
+        backend
+            .list::<Account>()
+            .include_deleted()
+            .count()
+            .await
+            .unwrap(),
+        2
+    );
+
+    // Upserting a component replaces its tombstone.
+    backend.upsert(&2, &Account { balance: 30 }).await.unwrap();
+    assert_eq!(
+        backend.get::<Account>(&2).await.unwrap(),
+        Account { balance: 30 }
+    );
+
+    // Purging permanently deletes all remaining tombstones.
+    let purged = backend.purge::<Account>().await.unwrap();
+
+    println!("{purged}");
+    // 1
+    assert_eq!(purged, 1);
+    assert_eq!(
+        backend
+            .list::<Account>()
+            .include_deleted()
+            .count()
+            .await
+            .unwrap(),
+        1
+    );
+
+    // Once purged, the component can be inserted again.
+    backend.insert(&1, &Account { balance: 40 }).await.unwrap();
+}
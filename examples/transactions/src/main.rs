@@ -0,0 +1,76 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Item(String);
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Gold(i64);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    backend.register::<Item>().await.unwrap();
+    backend.register::<Gold>().await.unwrap();
+
+    let merchant = 1;
+    backend
+        .insert(&merchant, &(Item("Sword".to_string()), Gold(100)))
+        .await
+        .unwrap();
+
+    let customer = 2;
+    backend.insert(&customer, &Gold(50)).await.unwrap();
+
+    // Hand the sword to the customer and take their gold. Either all
+    // of these changes are applied, or none of them are.
+    let mut tx = backend.transaction().await.unwrap();
+
+    let sword = tx.get::<Item>(&merchant).await.unwrap();
+    tx.remove::<Item>(&merchant).await.unwrap();
+    tx.insert(&customer, &sword).await.unwrap();
+    tx.update(&customer, &Gold(20)).await.unwrap();
+    tx.update(&merchant, &Gold(130)).await.unwrap();
+
+    // Changes are visible within the transaction itself.
+    let owners = tx
+        .list::<Item>()
+        .ids()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(owners, [customer]);
+
+    tx.commit().await.unwrap();
+
+    assert_eq!(
+        backend.get::<Item>(&customer).await.unwrap(),
+        Item("Sword".to_string())
+    );
+    assert_eq!(backend.get::<Gold>(&merchant).await.unwrap(), Gold(130));
+
+    // Transactions which are dropped before being committed are rolled back.
+    {
+        let mut tx = backend.transaction().await.unwrap();
+        tx.remove::<Item>(&customer).await.unwrap();
+    }
+
+    assert_eq!(
+        backend.get::<Item>(&customer).await.unwrap(),
+        Item("Sword".to_string())
+    );
+
+    // Failing operations leave the transaction open, so it can be explicitly rolled back.
+    let mut tx = backend.transaction().await.unwrap();
+    tx.update(&customer, &Gold(0)).await.unwrap();
+    assert!(matches!(
+        tx.insert(&customer, &Gold(0)).await,
+        Err(enorm::Error::ConstraintViolation(_))
+    ));
+    tx.rollback().await.unwrap();
+
+    assert_eq!(backend.get::<Gold>(&customer).await.unwrap(), Gold(20));
+}
@@ -0,0 +1,62 @@
+use enorm::prelude::*;
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Name(String);
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Age(i64);
+
+#[derive(Component, Debug, PartialEq, Eq)]
+pub struct Admin;
+
+#[derive(Debug, Component, PartialEq, Eq)]
+enum Status {
+    Online { since: i64 },
+    Offline,
+}
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using u64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+
+    // This creates the component tables where data will be persisted.
+    backend.register::<Name>().await.unwrap();
+    backend.register::<Age>().await.unwrap();
+    backend.register::<Admin>().await.unwrap();
+    backend.register::<Status>().await.unwrap();
+
+    let jimothy = 1;
+    backend
+        .insert(&jimothy, &Name("Jimothy".to_string()))
+        .await
+        .unwrap();
+
+    // Upserting sets components whether or not the entity already has them:
+    // Jimothy's name is replaced, while his age is inserted.
+    backend
+        .upsert(&jimothy, &(Name("Jim".to_string()), Age(10)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        backend.get::<(Name, Age)>(&jimothy).await.unwrap(),
+        (Name("Jim".to_string()), Age(10))
+    );
+
+    // Marker components are left as they are, if already present.
+    backend.upsert(&jimothy, &Admin).await.unwrap();
+    backend.upsert(&jimothy, &Admin).await.unwrap();
+    assert_eq!(backend.get::<Admin>(&jimothy).await.unwrap(), Admin);
+
+    // Enum components replace the previous variant entirely.
+    backend
+        .upsert(&jimothy, &Status::Online { since: 100 })
+        .await
+        .unwrap();
+    backend.upsert(&jimothy, &Status::Offline).await.unwrap();
+    assert_eq!(
+        backend.get::<Status>(&jimothy).await.unwrap(),
+        Status::Offline
+    );
+}
@@ -0,0 +1,99 @@
+use enorm::prelude::*;
+use futures::TryStreamExt as _;
+
+// Versioned components have a hidden version column, which is incremented
+// whenever they are updated.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[enorm(versioned)]
+pub struct Balance(i64);
+
+#[derive(Component, Debug)]
+pub struct Name(String);
+
+#[tokio::main]
+async fn main() {
+    // Create an Sqlite backend using i64 as entity IDs
+    let backend: SqliteBackend<i64> = SqliteBackend::in_memory().await;
+    backend.register::<Balance>().await.unwrap();
+
+    backend.insert(&1, &Balance(100)).await.unwrap();
+
+    // Reading the component as `Versioned` includes its version.
+    let mut first = backend.get::<Versioned<Balance>>(&1).await.unwrap();
+    let mut second = first.clone();
+
+    println!("{first:?}");
+    // Versioned { version: 1, component: Balance(100) }
+    assert_eq!(first.version, 1);
+
+    // Updating a `Versioned` component only succeeds if nobody else has
+    // updated it since it was read.
+    first.0 += 50;
+    backend.update(&1, &first).await.unwrap();
+
+    second.0 -= 30;
+    let conflict = backend.update(&1, &second).await;
+
+    println!("{conflict:?}");
+    // Err(Conflict)
+    assert!(matches!(conflict, Err(enorm::Error::Conflict)));
+
+    // The conflicting update is rejected, so it can be retried on top of
+    // the latest version instead of overwriting the first one.
+    let mut latest = backend.get::<Versioned<Balance>>(&1).await.unwrap();
+    assert_eq!(latest.version, 2);
+    assert_eq!(latest.component, Balance(150));
+
+    latest.0 -= 30;
+    backend.update(&1, &latest).await.unwrap();
+
+    // Plain updates and upserts aren't guarded, but still increment the version.
+    backend.update(&1, &Balance(10)).await.unwrap();
+    backend.upsert(&1, &Balance(0)).await.unwrap();
+
+    let balance = backend.get::<Versioned<Balance>>(&1).await.unwrap();
+    assert_eq!(balance.version, 5);
+    assert_eq!(balance.into_inner(), Balance(0));
+
+    // Versioned components can be part of archetypes, whose update is
+    // rolled back entirely if any of them conflict.
+    backend.register::<Name>().await.unwrap();
+    backend
+        .insert(&2, &(Name("Jimothy".to_string()), Balance(10)))
+        .await
+        .unwrap();
+
+    let stale = backend.get::<Versioned<Balance>>(&2).await.unwrap();
+    backend.update(&2, &Balance(20)).await.unwrap();
+
+    let conflict = backend
+        .update(&2, &(Name("Andrea".to_string()), stale))
+        .await;
+    assert!(matches!(conflict, Err(enorm::Error::Conflict)));
+    assert_eq!(backend.get::<Name>(&2).await.unwrap().0, "Jimothy");
+
+    // Listed components include their version as well.
+    let versions: Vec<_> = backend
+        .list::<Versioned<Balance>>()
+        .fetch()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entity| (*entity.id(), entity.version))
+        .collect();
+
+    println!("{versions:?}");
+    // [(1, 5), (2, 2)]
+    assert_eq!(versions, vec![(1, 5), (2, 2)]);
+    // Updating a component which has since been removed isn't a conflict,
+    // the component is simply missing.
+    let removed = backend.get::<Versioned<Balance>>(&2).await.unwrap();
+    backend.remove::<Balance>(&2).await.unwrap();
+
+    let missing = backend.update(&2, &removed).await;
+
+    println!("{missing:?}");
+    // Err(NotFound)
+    assert!(matches!(missing, Err(enorm::Error::NotFound)));
+}